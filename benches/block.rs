@@ -0,0 +1,77 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use drmeter::{DRMeter, Weighting, WindowFunction};
+
+const RATE: u32 = 48_000;
+const SECONDS: usize = 30;
+
+/// Stereo signal with changing level
+fn signal() -> Vec<f32> {
+    (0..RATE as usize * SECONDS * 2)
+        .map(|i| f32::sin(i as f32 * 0.01) * (i % 7_777) as f32 / 7_777.0)
+        .collect()
+}
+
+/// 5.1 signal with changing level
+fn surround_signal() -> Vec<f32> {
+    (0..RATE as usize * SECONDS * 6)
+        .map(|i| f32::sin(i as f32 * 0.003) * (i % 9_999) as f32 / 9_999.0)
+        .collect()
+}
+
+fn measure(data: &[f32], setup: impl Fn(&mut DRMeter)) -> f64 {
+    let mut dr = DRMeter::new(2, RATE).unwrap();
+    setup(&mut dr);
+    dr.add_frames_f32(data).unwrap();
+    dr.finalize().unwrap();
+    dr.exact_dr().unwrap()
+}
+
+fn block(c: &mut Criterion) {
+    let data = signal();
+    let planes: [Vec<f32>; 2] = [
+        data.iter().step_by(2).copied().collect(),
+        data.iter().skip(1).step_by(2).copied().collect(),
+    ];
+
+    let mut group = c.benchmark_group("block");
+    group.throughput(Throughput::Elements((RATE as usize * SECONDS) as u64));
+    group.bench_function("interleaved", |b| {
+        b.iter(|| measure(black_box(&data), |_| ()))
+    });
+    group.bench_function("planar", |b| {
+        b.iter(|| {
+            let mut dr = DRMeter::new(2, RATE).unwrap();
+            dr.add_frames_planar_f32(&[black_box(&planes[0]), black_box(&planes[1])])
+                .unwrap();
+            dr.finalize().unwrap();
+            dr.exact_dr().unwrap()
+        })
+    });
+    let surround = surround_signal();
+    group.bench_function("interleaved 5.1", |b| {
+        b.iter(|| {
+            let mut dr = DRMeter::new(6, RATE).unwrap();
+            dr.add_frames_f32(black_box(&surround)).unwrap();
+            dr.finalize().unwrap();
+            dr.exact_dr().unwrap()
+        })
+    });
+    group.bench_function("weighted", |b| {
+        b.iter(|| {
+            measure(black_box(&data), |dr| {
+                dr.set_weighting(Weighting::A).unwrap()
+            })
+        })
+    });
+    group.bench_function("windowed", |b| {
+        b.iter(|| {
+            measure(black_box(&data), |dr| {
+                dr.set_window_function(WindowFunction::Hann).unwrap()
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, block);
+criterion_main!(benches);
@@ -1,152 +1,429 @@
-use drmeter::DRMeter;
-use ffmpeg::format::sample::Type;
-use ffmpeg::format::Sample;
-use ffmpeg::util::frame::audio::Audio as FAudio;
-use ffmpeg_next as ffmpeg;
-
-fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    ffmpeg_next::init().unwrap();
-    ffmpeg_next::log::set_level(ffmpeg_next::log::Level::Quiet);
-    let mut ictx = ffmpeg::format::input(&args[1]).unwrap();
-    let input = ictx
-        .streams()
-        .best(ffmpeg::media::Type::Audio)
-        .ok_or(ffmpeg::Error::StreamNotFound)
-        .unwrap();
-    let idx = input.index();
-    let context_decoder =
-        ffmpeg::codec::context::Context::from_parameters(input.parameters()).unwrap();
-    let mut decoder = context_decoder.decoder().audio().unwrap();
-    decoder.set_parameters(input.parameters()).unwrap();
-    let mut sample_type = decoder.format();
-
-    let req_resample = match sample_type {
-        // empty
-        Sample::None => panic!("No samples"),
-        // our DR meter cannot handle them so we need to resample
-        Sample::U8(_) | Sample::I64(_) => {
-            sample_type = Sample::I16(Type::Packed);
-            println!("Resampling will be used!");
-            true
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use drmeter::decode::ffmpeg::measure_path_ffmpeg;
+use drmeter::report::{ReportWriter, TextReport};
+use drmeter::units::amplitude_to_dbfs;
+use drmeter::{BlockResult, Comparison, DRAlbum, DRMeter, DRResults, DRScore};
+
+/// Extensions of files that are analyzed in album mode
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "aac", "aif", "aiff", "ape", "dsf", "flac", "m4a", "mka", "mp3", "mpc", "ogg", "opus", "tak",
+    "wav", "wma", "wv",
+];
+
+/// Exit code when any track is below `--fail-below` threshold
+const EXIT_BELOW_THRESHOLD: u8 = 2;
+
+fn usage() -> ExitCode {
+    eprintln!("Usage: drmeter [-j N] [--fail-below DR<n>] [--json-lines] <file>...");
+    eprintln!("       drmeter compare <old> <new>");
+    eprintln!("       drmeter album [--log] [--group-by-tags] <dir>...");
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let mut fail_below: Option<DRScore> = None;
+    let mut jobs = 1;
+    let mut write_log = false;
+    let mut group_by_tags = false;
+    let mut json_lines = false;
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fail-below" => match args.next().map(|s| s.parse()) {
+                Some(Ok(score)) => fail_below = Some(score),
+                _ => return usage(),
+            },
+            _ if arg.starts_with("--fail-below=") => match arg["--fail-below=".len()..].parse() {
+                Ok(score) => fail_below = Some(score),
+                Err(_) => return usage(),
+            },
+            "-j" | "--jobs" => match args.next().map(|s| s.parse()) {
+                Some(Ok(n)) => jobs = n,
+                _ => return usage(),
+            },
+            "--log" => write_log = true,
+            "--group-by-tags" => group_by_tags = true,
+            "--json-lines" => json_lines = true,
+            _ => files.push(arg),
         }
-        // it's fine
-        Sample::I16(_) => false,
-        Sample::I32(_) => false,
-        Sample::F32(_) => false,
-        Sample::F64(_) => false,
+    }
+    let compare = files.first().is_some_and(|cmd| cmd == "compare");
+    if compare {
+        files.remove(0);
+        if files.len() != 2 {
+            return usage();
+        }
+    }
+    let album_dirs = match files.first().map(String::as_str) {
+        Some("album") if files.len() >= 2 => Some(files.split_off(1)),
+        Some("album") => return usage(),
+        _ => None,
     };
+    if let Some(dirs) = &album_dirs {
+        files.clear();
+        for dir in dirs {
+            match album_files(Path::new(dir)) {
+                Ok(dir_files) => files.extend(dir_files),
+                Err(e) => {
+                    eprintln!("Cannot read {dir}: {e}");
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+    }
+    if files.is_empty() {
+        return usage();
+    }
 
-    let mut dr = DRMeter::new(
-        decoder.channel_layout().channels() as u32,
-        decoder.rate() as u32,
-    )
-    .unwrap();
+    ffmpeg_next::log::set_level(ffmpeg_next::log::Level::Quiet);
 
-    println!(
-        "Channels: {}, Sample rate: {}Hz",
-        decoder.channels(),
-        decoder.rate()
-    );
+    // 0 jobs means one per core
+    if jobs == 0 {
+        jobs = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    }
+
+    if compare {
+        let mut results = Vec::new();
+        for_each_analyzed(&files, jobs, false, |_, r| results.push(r.clone()));
+        return print_comparison(&files[0], &results[0], &files[1], &results[1]);
+    }
+
+    let mut below = false;
+    let mut tracks = Vec::new();
+    let json_lines = json_lines && album_dirs.is_none();
+    for_each_analyzed(&files, jobs, json_lines, |file, results| {
+        if album_dirs.is_some() {
+            tracks.push((file.to_owned(), results.clone()));
+        } else if json_lines {
+            println!(
+                "{{\"type\":\"track\",\"file\":{},\"dr\":{},\"exact_dr\":{}}}",
+                json_string(file),
+                results.dr_score().get(),
+                json_number(results.exact_dr())
+            );
+        } else {
+            if files.len() > 1 {
+                println!("========== {file} ==========");
+            }
+            print_scores(results);
+        }
+
+        let score = results.dr_score();
+        if let Some(threshold) = fail_below {
+            if score < threshold {
+                eprintln!("{file}: {score} is below {threshold}");
+                below = true;
+            }
+        }
+    });
 
-    for (packet_stream, packet) in ictx.packets() {
-        if packet_stream.index() == idx {
-            if let Err(e) = decoder.send_packet(&packet) {
-                println!("Error while sending a packet to the decoder {e}");
-                break;
+    if album_dirs.is_some() {
+        let albums = if group_by_tags {
+            DRAlbum::group_by_tags(tracks)
+        } else {
+            // every directory is one album
+            tracks
+                .chunk_by(|(a, _), (b, _)| Path::new(a).parent() == Path::new(b).parent())
+                .map(|dir_tracks| {
+                    let mut album = DRAlbum::new();
+                    for (file, results) in dir_tracks {
+                        album.add_track(file.as_str(), results.clone());
+                    }
+                    album
+                })
+                .collect()
+        };
+        for album in &albums {
+            // tracks are named by their files until the report
+            let dir = Path::new(&album.tracks()[0].name)
+                .parent()
+                .unwrap_or(Path::new(""));
+            let album = with_track_names(album);
+            if albums.len() > 1 {
+                println!("========== {} ==========", dir.display());
             }
-            let mut decoded = FAudio::empty();
-            while decoder.receive_frame(&mut decoded).is_ok() {
-                if req_resample {
-                    let mut resampler = decoder
-                        .resampler(
-                            Sample::I16(Type::Packed),
-                            decoder.channel_layout(),
-                            decoder.rate(),
-                        )
-                        .unwrap();
-                    let mut resampled = FAudio::empty();
-                    resampler.run(&decoded, &mut resampled).unwrap();
-                    decoded = resampled;
+            TextReport.write_album(&mut io::stdout(), &album).unwrap();
+            if write_log {
+                let path = dir.join("dr.txt");
+                if let Err(e) = write_dr_log(&path, &album) {
+                    eprintln!("Cannot write {}: {e}", path.display());
+                    return ExitCode::FAILURE;
                 }
+            }
+        }
+    }
+
+    if below {
+        ExitCode::from(EXIT_BELOW_THRESHOLD)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Audio files of album directory in name order
+fn album_files(dir: &Path) -> io::Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in dir.read_dir()? {
+        let path = entry?.path();
+        let is_audio = path
+            .extension()
+            .is_some_and(|ext| AUDIO_EXTENSIONS.iter().any(|a| ext.eq_ignore_ascii_case(a)));
+        if is_audio && path.is_file() {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Name of track in album reports
+fn track_name(file: &str) -> String {
+    let path = Path::new(file);
+    path.file_stem()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Copy of album with tracks named by [`track_name`] of their files
+fn with_track_names(album: &DRAlbum) -> DRAlbum {
+    let mut named = DRAlbum::new();
+    for track in album.tracks() {
+        named.add_disc_track(track.disc, track_name(&track.name), track.results.clone());
+    }
+    named
+}
+
+/// Analyze files on `jobs` worker threads and call `f` with results in file order
+///
+/// With `json_lines` every finished block is printed as soon as it is measured.
+fn for_each_analyzed(
+    files: &[String],
+    jobs: usize,
+    json_lines: bool,
+    mut f: impl FnMut(&str, &DRResults),
+) {
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
 
-                let planes = decoded.planes();
-                debug_assert_eq!(decoded.format(), sample_type);
-
-                match sample_type {
-                    Sample::I16(t) => match t {
-                        Type::Packed => dr.add_frames_i16(plane(&decoded, 0)),
-                        Type::Planar => {
-                            let l: Vec<_> = (0..planes).map(|x| plane(&decoded, x)).collect();
-                            dr.add_frames_planar_i16(&l)
-                        }
-                    },
-                    Sample::I32(t) => match t {
-                        Type::Packed => dr.add_frames_i32(plane(&decoded, 0)),
-                        Type::Planar => {
-                            let l: Vec<_> = (0..planes).map(|x| plane(&decoded, x)).collect();
-                            dr.add_frames_planar_i32(&l)
-                        }
-                    },
-                    Sample::F32(t) => match t {
-                        Type::Packed => dr.add_frames_f32(plane(&decoded, 0)),
-                        Type::Planar => {
-                            let l: Vec<_> = (0..planes).map(|x| plane(&decoded, x)).collect();
-                            dr.add_frames_planar_f32(&l)
-                        }
-                    },
-                    Sample::F64(t) => match t {
-                        Type::Packed => dr.add_frames_f64(plane(&decoded, 0)),
-                        Type::Planar => {
-                            let l: Vec<_> = (0..planes).map(|x| plane(&decoded, x)).collect();
-                            dr.add_frames_planar_f64(&l)
-                        }
-                    },
-
-                    Sample::None | Sample::U8(_) | Sample::I64(_) => panic!("should not be"),
+    thread::scope(|s| {
+        for _ in 0..jobs.min(files.len()) {
+            let tx = tx.clone();
+            let next = &next;
+            s.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(file) = files.get(i) else {
+                    break;
+                };
+                if tx.send((i, analyze(file, json_lines))).is_err() {
+                    break;
                 }
-                .unwrap();
+            });
+        }
+        drop(tx);
+
+        // buffer out of order results until all preceding files are done
+        let mut pending = BTreeMap::new();
+        let mut current = 0;
+        for (i, dr) in rx {
+            pending.insert(i, dr);
+            while let Some(dr) = pending.remove(&current) {
+                f(&files[current], &dr);
+                current += 1;
+            }
+        }
+    });
+}
+
+/// Decode best audio stream of file and measure it
+fn analyze(path: &str, json_lines: bool) -> DRResults {
+    let mut printed_blocks = 0;
+    measure_path_ffmpeg(path, |dr| {
+        // packets are much shorter than blocks, so at most one block finished
+        if let Some(block) = dr.last_block().filter(|_| json_lines) {
+            if block.index >= printed_blocks {
+                print_block(path, dr, block);
+                printed_blocks = block.index + 1;
             }
         }
+    })
+    .unwrap()
+}
+
+/// Format value as JSON string
+fn json_string(value: &str) -> String {
+    let mut s = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            c if (c as u32) < 0x20 => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+/// Format value as JSON number (`null` if not finite)
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value:.2}")
+    } else {
+        "null".to_owned()
     }
+}
 
-    dr.finalize().unwrap();
+/// Print finished block as one JSON line with DR of blocks so far
+fn print_block(path: &str, dr: &DRMeter, block: &BlockResult) {
+    let levels = |values: &[f64]| -> String {
+        let values: Vec<String> = values
+            .iter()
+            .map(|v| json_number(amplitude_to_dbfs(*v)))
+            .collect();
+        values.join(",")
+    };
+    println!(
+        "{{\"type\":\"block\",\"file\":{},\"block\":{},\"time\":{},\"peak\":[{}],\"rms\":[{}],\"dr\":{}}}",
+        json_string(path),
+        block.index,
+        json_number(block.time(dr.rate()).as_secs_f64()),
+        levels(&block.peak),
+        levels(&block.rms),
+        json_number(dr.exact_dr().unwrap_or(f64::NAN)),
+    );
+}
 
+fn print_scores(dr: &DRResults) {
+    println!("Channels: {}, Sample rate: {}Hz", dr.channels(), dr.rate());
     for ch in 0..dr.channels() {
         println!("---------- CHANNEL {ch} ----------");
         println!(
-            "Score: DR{} ({})",
+            "Score: {} ({})",
             dr.channel_dr_score(ch).unwrap(),
             dr.exact_channel_dr(ch).unwrap()
         );
     }
 
     println!("----------- GLOBAL -----------");
-    println!(
-        "Score: DR{} ({})",
-        dr.dr_score().unwrap(),
-        dr.exact_dr().unwrap()
-    );
+    println!("Score: {} ({})", dr.dr_score(), dr.exact_dr());
 }
 
-/// Fix from https://github.com/zmwangx/rust-ffmpeg/pull/104
-#[inline]
-fn plane<T: ffmpeg::frame::audio::Sample>(ss: &FAudio, index: usize) -> &[T] {
-    if index >= ss.planes() {
-        panic!("out of bounds");
+/// Write album log in the format of TT DR Offline Meter (`dr.txt`)
+fn write_dr_log(path: &Path, album: &DRAlbum) -> io::Result<()> {
+    const LINE: &str =
+        "--------------------------------------------------------------------------------";
+    let db = |v: Result<f64, drmeter::Error>| amplitude_to_dbfs(v.unwrap_or_default());
+
+    let mut w = BufWriter::new(File::create(path)?);
+    let first = album.tracks().first().map(|t| &t.results);
+    let metadata = first.map(DRResults::metadata).cloned().unwrap_or_default();
+    writeln!(w, "{LINE}")?;
+    writeln!(
+        w,
+        "Analyzed: {} / {}",
+        metadata
+            .album_artist
+            .or(metadata.artist)
+            .as_deref()
+            .unwrap_or("Unknown"),
+        metadata.album.as_deref().unwrap_or("Unknown")
+    )?;
+    writeln!(w, "{LINE}")?;
+    writeln!(w)?;
+    writeln!(w, "DR         Peak         RMS     Duration Track")?;
+    writeln!(w, "{LINE}")?;
+    for track in album.tracks() {
+        let r = &track.results;
+        let peak = (0..r.channels())
+            .map(|ch| db(r.first_peak(ch)))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let rms = (0..r.channels())
+            .map(|ch| db(r.rms(ch)))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let secs = r.duration().as_secs();
+        writeln!(
+            w,
+            "{:<6}{peak:>9.2} dB{rms:>9.2} dB{:>10} {}",
+            r.dr_score(),
+            format!("{}:{:02}", secs / 60, secs % 60),
+            track.name
+        )?;
+    }
+    writeln!(w, "{LINE}")?;
+    writeln!(w)?;
+    writeln!(w, "Number of tracks:  {}", album.tracks().len())?;
+    writeln!(w, "Official DR value: {}", album.dr_score())?;
+    writeln!(w)?;
+    if let Some(r) = first {
+        writeln!(w, "Samplerate:        {} Hz", r.rate())?;
+        writeln!(w, "Channels:          {}", r.channels())?;
     }
-    if !<T as ffmpeg::frame::audio::Sample>::is_valid(ss.format(), ss.channels() as u16) {
-        panic!("unsupported type");
+    if let Some(codec) = &metadata.codec {
+        writeln!(w, "Codec:             {codec}")?;
     }
+    writeln!(
+        w,
+        "================================================================================"
+    )?;
+    w.flush()
+}
 
-    if ss.is_planar() {
-        unsafe { std::slice::from_raw_parts((*ss.as_ptr()).data[index] as *const T, ss.samples()) }
-    } else {
-        unsafe {
-            std::slice::from_raw_parts(
-                (*ss.as_ptr()).data[0] as *const T,
-                ss.samples() * usize::from(ss.channels()),
-            )
+/// Print old and new results side by side with deltas
+fn print_comparison(old_name: &str, old: &DRResults, new_name: &str, new: &DRResults) -> ExitCode {
+    let cmp = match Comparison::new(old, new) {
+        Ok(cmp) => cmp,
+        Err(e) => {
+            eprintln!("Cannot compare {old_name} and {new_name}: {e}");
+            return ExitCode::FAILURE;
         }
+    };
+
+    println!("Old: {old_name}");
+    println!("New: {new_name}");
+    println!("{:<16}{:>10}{:>10}{:>10}", "", "old", "new", "delta");
+    let row = |name: &str, old: f64, new: f64, delta: f64| {
+        println!("{name:<16}{old:>10.2}{new:>10.2}{delta:>+10.2}");
+    };
+    let db = |v: Result<f64, drmeter::Error>| amplitude_to_dbfs(v.unwrap());
+
+    for (ch, delta) in (0..).zip(cmp.channels()) {
+        println!("---------- CHANNEL {ch} ----------");
+        row(
+            "DR",
+            old.exact_channel_dr(ch).unwrap(),
+            new.exact_channel_dr(ch).unwrap(),
+            delta.dr,
+        );
+        row(
+            "Peak (dB)",
+            db(old.first_peak(ch)),
+            db(new.first_peak(ch)),
+            delta.first_peak,
+        );
+        row(
+            "2nd peak (dB)",
+            db(old.second_peak(ch)),
+            db(new.second_peak(ch)),
+            delta.second_peak,
+        );
+        row("RMS (dB)", db(old.rms(ch)), db(new.rms(ch)), delta.rms);
     }
+
+    println!("----------- GLOBAL -----------");
+    row("DR", old.exact_dr(), new.exact_dr(), cmp.dr());
+    println!("Score: {} -> {}", old.dr_score(), new.dr_score());
+
+    ExitCode::SUCCESS
 }
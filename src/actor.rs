@@ -0,0 +1,150 @@
+//! Background analysis on a worker thread
+//!
+//! [`DRActor`] owns a [`DRMeter`] on its own thread. Frames and other commands
+//! are sent over a channel and results come back as [`Event`]s,
+//! so GUI applications never block on measurement.
+
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::{DRMeter, DRResults, Error};
+
+/// Interleaved frames sent to the actor
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frames {
+    /// Interleaved `i16` frames
+    I16(Vec<i16>),
+    /// Interleaved `i32` frames
+    I32(Vec<i32>),
+    /// Interleaved `f32` frames
+    F32(Vec<f32>),
+    /// Interleaved `f64` frames
+    F64(Vec<f64>),
+}
+
+/// Command for [`DRActor`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Add frames to be processed
+    Frames(Frames),
+    /// Finalize meter and send [`Event::Results`]
+    Finalize,
+    /// Send current [`Event::Dr`]
+    Query,
+}
+
+/// Event sent by [`DRActor`]
+// results are sent only once, so they are not boxed
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Frames were processed
+    Progress {
+        /// Total number of processed frames
+        frames: u64,
+    },
+    /// Exact DR of fully finished blocks (answer to [`Command::Query`])
+    Dr(Result<f64, Error>),
+    /// Results of finalized meter (answer to [`Command::Finalize`])
+    Results(Result<DRResults, Error>),
+    /// Command failed
+    Error(Error),
+}
+
+/// [`DRMeter`] running on a worker thread
+#[derive(Debug)]
+pub struct DRActor {
+    commands: Sender<Command>,
+    events: Receiver<Event>,
+    worker: JoinHandle<DRMeter>,
+}
+
+impl DRActor {
+    /// Spawn worker thread that owns the meter
+    pub fn spawn(meter: DRMeter) -> Self {
+        let (commands, command_rx) = mpsc::channel();
+        let (event_tx, events) = mpsc::channel();
+        let worker = thread::spawn(move || Self::run(meter, command_rx, event_tx));
+
+        Self {
+            commands,
+            events,
+            worker,
+        }
+    }
+
+    fn run(mut meter: DRMeter, commands: Receiver<Command>, events: Sender<Event>) -> DRMeter {
+        for command in commands {
+            let event = match command {
+                Command::Frames(frames) => {
+                    let res = match &frames {
+                        Frames::I16(f) => meter.add_frames_i16(f),
+                        Frames::I32(f) => meter.add_frames_i32(f),
+                        Frames::F32(f) => meter.add_frames_f32(f),
+                        Frames::F64(f) => meter.add_frames_f64(f),
+                    };
+                    match res {
+                        Ok(()) => Event::Progress {
+                            frames: meter.frames(),
+                        },
+                        Err(e) => Event::Error(e),
+                    }
+                }
+                Command::Finalize => Event::Results(meter.finalize().and_then(|_| meter.results())),
+                Command::Query => Event::Dr(meter.exact_dr()),
+            };
+            // receiver may be gone, but commands can still be processed
+            let _ = events.send(event);
+        }
+
+        meter
+    }
+
+    /// Send command to the worker
+    ///
+    /// Fails only if the worker thread has died.
+    pub fn send(&self, command: Command) -> Result<(), SendError<Command>> {
+        self.commands.send(command)
+    }
+
+    /// Events sent by the worker
+    pub fn events(&self) -> &Receiver<Event> {
+        &self.events
+    }
+
+    /// Stop accepting commands and wait for the worker to process
+    /// remaining ones, returning the meter.
+    pub fn join(self) -> thread::Result<DRMeter> {
+        drop(self.commands);
+        self.worker.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analyze() {
+        let actor = DRActor::spawn(DRMeter::new(1, 8000).unwrap());
+        let frames: Vec<f32> = (0..8000 * 3)
+            .map(|i| f32::sin(i as f32 * 0.1) * (i % 8000) as f32 / 8000.0)
+            .collect();
+        for chunk in frames.chunks(4000) {
+            actor
+                .send(Command::Frames(Frames::F32(chunk.to_vec())))
+                .unwrap();
+        }
+        actor.send(Command::Finalize).unwrap();
+
+        let events: Vec<Event> = actor.events().iter().take(7).collect();
+        assert_eq!(events[5], Event::Progress { frames: 24000 });
+        let Event::Results(Ok(results)) = &events[6] else {
+            panic!("expected results");
+        };
+
+        let meter = actor.join().unwrap();
+        assert!(meter.finalized());
+        assert_eq!(results.exact_dr(), meter.exact_dr().unwrap());
+    }
+}
@@ -0,0 +1,307 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::report::ReportWriter;
+use crate::units::amplitude_to_dbfs;
+use crate::{Aggregation, DRMeter, DRResults, DRScore, Error};
+
+/// Track of a [`DRAlbum`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    /// Name of track
+    pub name: String,
+    /// Disc number (starting with 1)
+    pub disc: u32,
+    /// Results of track
+    pub results: DRResults,
+}
+
+/// Album made of finalized tracks
+///
+/// Official album DR is average of track DR values.
+/// Multi-disc albums also have DR of every disc.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DRAlbum {
+    tracks: Vec<Track>,
+    /// render track gain offsets in reports
+    gain_alignment: bool,
+}
+
+/// Returns loudness of track in dBFS (top 20% RMS energy averaged over channels)
+fn loudness(results: &DRResults) -> f64 {
+    let energy = (0..results.channels())
+        .map(|ch| results.rms(ch).unwrap_or_default().powi(2))
+        .sum::<f64>()
+        / results.channels() as f64;
+    amplitude_to_dbfs(energy.sqrt())
+}
+
+impl DRAlbum {
+    /// Create a new empty album
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add track results (on first disc)
+    pub fn add_track(&mut self, name: impl Into<String>, results: DRResults) {
+        self.add_disc_track(1, name, results)
+    }
+
+    /// Add track results on given disc
+    pub fn add_disc_track(&mut self, disc: u32, name: impl Into<String>, results: DRResults) {
+        self.tracks.push(Track {
+            name: name.into(),
+            disc,
+            results,
+        });
+    }
+
+    /// Group tracks named by their file paths into albums
+    /// by their `ALBUM` and `ALBUMARTIST` tags
+    ///
+    /// Tracks are placed on disc of their `DISC` tag (first disc if untagged),
+    /// so discs stored in separate folders end up in one album.
+    /// Track artist is not used, so compilations stay together.
+    /// Tracks without `ALBUM` tag are grouped by their parent directory.
+    /// Albums are returned in order of their first track.
+    pub fn group_by_tags(tracks: impl IntoIterator<Item = (String, DRResults)>) -> Vec<Self> {
+        #[derive(PartialEq)]
+        enum Key {
+            Tags(String, Option<String>),
+            Directory(Option<PathBuf>),
+        }
+
+        let mut keys: Vec<Key> = Vec::new();
+        let mut albums: Vec<Self> = Vec::new();
+        for (name, results) in tracks {
+            let metadata = results.metadata();
+            let key = match &metadata.album {
+                Some(album) => Key::Tags(album.clone(), metadata.album_artist.clone()),
+                None => Key::Directory(Path::new(&name).parent().map(Path::to_path_buf)),
+            };
+            let disc = metadata.disc.unwrap_or(1);
+            let index = match keys.iter().position(|k| *k == key) {
+                Some(index) => index,
+                None => {
+                    keys.push(key);
+                    albums.push(Self::new());
+                    albums.len() - 1
+                }
+            };
+            albums[index].add_disc_track(disc, name, results);
+        }
+        albums
+    }
+
+    /// Add results of finalized meter as track (on first disc)
+    ///
+    /// Returns [`Error::NotFinalized`] if the meter was not finalized.
+    pub fn add_meter(&mut self, name: impl Into<String>, meter: &DRMeter) -> Result<(), Error> {
+        self.add_track(name, meter.results()?);
+        Ok(())
+    }
+
+    /// Returns whether reports include track gain offsets
+    pub const fn gain_alignment(&self) -> bool {
+        self.gain_alignment
+    }
+
+    /// Include gain offsets of [`track_gains`](Self::track_gains) in reports
+    pub fn set_gain_alignment(&mut self, enabled: bool) {
+        self.gain_alignment = enabled;
+    }
+
+    /// Returns gain offsets (in dB) of tracks in order they were added,
+    /// that align loudness (top 20% RMS) of tracks across the album
+    ///
+    /// Tracks are aligned to the quietest track, so offsets are never
+    /// positive and applying them cannot clip.
+    /// Silent tracks have no measurable loudness and get `0.0`.
+    pub fn track_gains(&self) -> Vec<f64> {
+        let loudness: Vec<f64> = self
+            .tracks
+            .iter()
+            .map(|track| loudness(&track.results))
+            .collect();
+        let reference = loudness
+            .iter()
+            .copied()
+            .filter(|l| l.is_finite())
+            .fold(f64::INFINITY, f64::min);
+        loudness
+            .into_iter()
+            .map(|l| if l.is_finite() { reference - l } else { 0.0 })
+            .collect()
+    }
+
+    /// Returns tracks in order they were added
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Returns track at index (in order tracks were added)
+    pub fn track(&self, index: usize) -> Option<&Track> {
+        self.tracks.get(index)
+    }
+
+    /// Returns first track with given name
+    pub fn track_by_name(&self, name: &str) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.name == name)
+    }
+
+    /// Returns disc numbers in ascending order
+    pub fn discs(&self) -> Vec<u32> {
+        let mut discs: Vec<u32> = self.tracks.iter().map(|track| track.disc).collect();
+        discs.sort_unstable();
+        discs.dedup();
+        discs
+    }
+
+    /// Returns tracks of given disc
+    pub fn disc_tracks(&self, disc: u32) -> impl Iterator<Item = &Track> {
+        self.tracks.iter().filter(move |track| track.disc == disc)
+    }
+
+    /// Return exact DR of given disc
+    ///
+    /// Disc without tracks has `NaN` DR.
+    pub fn disc_exact_dr(&self, disc: u32) -> f64 {
+        let (sum, count) = self
+            .disc_tracks(disc)
+            .fold((0.0, 0), |(sum, count), track| {
+                (sum + track.results.exact_dr(), count + 1)
+            });
+        sum / count as f64
+    }
+
+    /// Return DR score of given disc
+    pub fn disc_dr_score(&self, disc: u32) -> DRScore {
+        DRScore::from_exact(self.disc_exact_dr(disc))
+    }
+
+    /// Return exact album DR (of whole set for multi-disc albums)
+    ///
+    /// Album without tracks has `NaN` DR.
+    pub fn exact_dr(&self) -> f64 {
+        self.exact_dr_with(Aggregation::Mean)
+    }
+
+    /// Return album DR score
+    pub fn dr_score(&self) -> DRScore {
+        DRScore::from_exact(self.exact_dr())
+    }
+
+    /// Return exact album DR aggregated from track DR with given statistic
+    ///
+    /// Album without tracks has `NaN` DR.
+    pub fn exact_dr_with(&self, aggregation: Aggregation) -> f64 {
+        aggregation.apply(self.tracks.iter().map(|track| track.results.exact_dr()))
+    }
+
+    /// Return album DR score aggregated from track DR with given statistic
+    pub fn dr_score_with(&self, aggregation: Aggregation) -> DRScore {
+        DRScore::from_exact(self.exact_dr_with(aggregation))
+    }
+
+    /// Render combined report of all tracks with given report format
+    pub fn write_report(
+        &self,
+        report: &impl ReportWriter,
+        writer: &mut dyn io::Write,
+    ) -> io::Result<()> {
+        report.write_album(writer, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::TextReport;
+    use crate::TrackMetadata;
+
+    fn meter(amplitude: f32, seconds: usize) -> DRMeter {
+        let data: Vec<f32> = (0..48_000 * seconds)
+            .map(|i| amplitude * f32::sin(i as f32 * 0.01) * (1.0 + (i / 48_000 % 3) as f32))
+            .collect();
+        let mut dr = DRMeter::new(1, 48_000).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        dr
+    }
+
+    #[test]
+    fn meters() {
+        let meters = [meter(0.1, 9), meter(0.3, 12)];
+        let mut album = DRAlbum::new();
+        assert_eq!(
+            album.add_meter("open", &DRMeter::new(1, 48_000).unwrap()),
+            Err(Error::NotFinalized)
+        );
+        album.add_meter("first", &meters[0]).unwrap();
+        album.add_meter("second", &meters[1]).unwrap();
+
+        assert_eq!(album.tracks().len(), 2);
+        assert_eq!(album.track(1).unwrap().name, "second");
+        assert!(album.track(2).is_none());
+        assert_eq!(
+            album.track_by_name("first").unwrap().results.exact_dr(),
+            meters[0].exact_dr().unwrap()
+        );
+        assert_eq!(
+            album.exact_dr(),
+            DRMeter::exact_dr_multiple(meters.iter()).unwrap()
+        );
+
+        let mut report = Vec::new();
+        album.write_report(&TextReport, &mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.contains("Number of tracks: 2"), "{report}");
+        assert!(report.ends_with(&format!("Official DR value: {}\n", album.dr_score())));
+    }
+
+    #[test]
+    fn group_by_tags() {
+        let results = meter(0.2, 6).results().unwrap();
+        let track = |name: &str, album: Option<&str>, album_artist, artist, disc| {
+            let metadata = TrackMetadata {
+                artist: Some(String::from(artist)),
+                album: album.map(String::from),
+                album_artist: Some(String::from(album_artist)),
+                disc,
+                ..Default::default()
+            };
+            (String::from(name), results.clone().with_metadata(metadata))
+        };
+        let albums = DRAlbum::group_by_tags([
+            // disc folders of one album
+            track("cd1/01", Some("Live"), "Band", "Band", Some(1)),
+            track("cd2/01", Some("Live"), "Band", "Band", Some(2)),
+            // compilation of different artists
+            track("hits/01", Some("Hits"), "Various Artists", "Band", None),
+            track("hits/02", Some("Hits"), "Various Artists", "Other", None),
+            // same album title of other artist
+            track("other/01", Some("Live"), "Other", "Other", None),
+            track("cd1/02", Some("Live"), "Band", "Band", Some(1)),
+            // untagged folders
+            track("rip/01", None, "", "Band", None),
+            track("demo/01", None, "", "Band", None),
+            track("rip/02", None, "", "Band", None),
+        ]);
+
+        assert_eq!(albums.len(), 5);
+        let names = |album: &DRAlbum, disc| -> Vec<String> {
+            album
+                .disc_tracks(disc)
+                .map(|track| track.name.clone())
+                .collect()
+        };
+        assert_eq!(albums[0].discs(), [1, 2]);
+        assert_eq!(names(&albums[0], 1), ["cd1/01", "cd1/02"]);
+        assert_eq!(names(&albums[0], 2), ["cd2/01"]);
+        assert_eq!(albums[1].discs(), [1]);
+        assert_eq!(names(&albums[1], 1), ["hits/01", "hits/02"]);
+        assert_eq!(names(&albums[2], 1), ["other/01"]);
+        assert_eq!(names(&albums[3], 1), ["rip/01", "rip/02"]);
+        assert_eq!(names(&albums[4], 1), ["demo/01"]);
+    }
+}
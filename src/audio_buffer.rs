@@ -0,0 +1,136 @@
+//! Input of decoded [symphonia](https://github.com/pdeljanov/Symphonia) buffers
+
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef};
+use symphonia::core::sample::{i24, u24};
+
+use crate::utils::Sample;
+use crate::{DRMeter, Error};
+
+impl DRMeter {
+    /// Add decoded buffer of any sample format to be processed.
+    ///
+    /// `u8`, `i16`, `i32`, `f32` and `f64` planes are processed in place,
+    /// other formats are converted to `i32` first.
+    ///
+    /// Returns [`Error::Mismatch`] if sample rate of buffer differs from the meter.
+    pub fn add_audio_buffer(&mut self, buffer: &AudioBufferRef) -> Result<(), Error> {
+        if buffer.spec().rate != self.rate() {
+            return Err(Error::Mismatch);
+        }
+
+        match buffer {
+            AudioBufferRef::U8(buf) => self.add_buffer(buf),
+            AudioBufferRef::U16(buf) => {
+                self.add_buffer_converted(buf, |s| (i32::from(s) - 0x8000) << 16)
+            }
+            AudioBufferRef::U24(buf) => {
+                self.add_buffer_converted(buf, |s: u24| (s.inner() as i32 - 0x80_0000) << 8)
+            }
+            AudioBufferRef::U32(buf) => {
+                self.add_buffer_converted(buf, |s| (s ^ 0x8000_0000) as i32)
+            }
+            AudioBufferRef::S8(buf) => self.add_buffer_converted(buf, |s| i32::from(s) << 24),
+            AudioBufferRef::S16(buf) => self.add_buffer(buf),
+            AudioBufferRef::S24(buf) => self.add_buffer_converted(buf, |s: i24| s.inner() << 8),
+            AudioBufferRef::S32(buf) => self.add_buffer(buf),
+            AudioBufferRef::F32(buf) => self.add_buffer(buf),
+            AudioBufferRef::F64(buf) => self.add_buffer(buf),
+        }
+    }
+
+    fn add_buffer<T: Sample + symphonia::core::sample::Sample>(
+        &mut self,
+        buffer: &AudioBuffer<T>,
+    ) -> Result<(), Error> {
+        self.add_planar(buffer.planes().planes())
+    }
+
+    /// Scale samples to full range of `i32`, so integer samples stay exact
+    fn add_buffer_converted<T: symphonia::core::sample::Sample>(
+        &mut self,
+        buffer: &AudioBuffer<T>,
+        convert: impl Fn(T) -> i32,
+    ) -> Result<(), Error> {
+        let planes: Vec<Vec<i32>> = buffer
+            .planes()
+            .planes()
+            .iter()
+            .map(|plane| plane.iter().map(|s| convert(*s)).collect())
+            .collect();
+        let planes: Vec<&[i32]> = planes.iter().map(Vec::as_slice).collect();
+        self.add_planar(&planes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use symphonia::core::audio::{Channels, Signal, SignalSpec};
+
+    use super::*;
+
+    fn buffer<T: symphonia::core::sample::Sample>(
+        rate: u32,
+        samples: &[i32],
+        convert: impl Fn(i32) -> T,
+    ) -> AudioBuffer<T> {
+        let spec = SignalSpec::new(rate, Channels::FRONT_LEFT | Channels::FRONT_RIGHT);
+        let mut buf = AudioBuffer::new(samples.len() as u64, spec);
+        buf.render_reserved(Some(samples.len()));
+        let (left, right) = buf.chan_pair_mut(0, 1);
+        for (i, s) in samples.iter().enumerate() {
+            left[i] = convert(*s);
+            right[i] = convert(*s / 2);
+        }
+        buf
+    }
+
+    #[test]
+    fn sample_formats() {
+        let samples: Vec<i32> = (0..48_000 * 10)
+            .map(|i| (f64::sin(i as f64 * 0.01) * (1 + i / 144_000) as f64 * 30.0) as i32)
+            .collect();
+        let mut expected = DRMeter::new(2, 48_000).unwrap();
+        let s16 = buffer(48_000, &samples, |s| (s << 8) as i16);
+        expected
+            .add_audio_buffer(&AudioBufferRef::S16(Cow::Borrowed(&s16)))
+            .unwrap();
+        expected.finalize().unwrap();
+        let expected = expected.exact_dr().unwrap();
+
+        let buffers = [
+            AudioBufferRef::U8(Cow::Owned(buffer(48_000, &samples, |s| (s + 128) as u8))),
+            AudioBufferRef::S8(Cow::Owned(buffer(48_000, &samples, |s| s as i8))),
+            AudioBufferRef::U16(Cow::Owned(buffer(48_000, &samples, |s| {
+                ((s << 8) + 0x8000) as u16
+            }))),
+            AudioBufferRef::U24(Cow::Owned(buffer(48_000, &samples, |s| {
+                u24(((s << 16) + 0x80_0000) as u32)
+            }))),
+            AudioBufferRef::S24(Cow::Owned(buffer(48_000, &samples, |s| i24(s << 16)))),
+            AudioBufferRef::U32(Cow::Owned(buffer(48_000, &samples, |s| {
+                ((s << 24) as u32) ^ 0x8000_0000
+            }))),
+            AudioBufferRef::S32(Cow::Owned(buffer(48_000, &samples, |s| s << 24))),
+            AudioBufferRef::F32(Cow::Owned(buffer(48_000, &samples, |s| s as f32 / 128.0))),
+            AudioBufferRef::F64(Cow::Owned(buffer(48_000, &samples, |s| s as f64 / 128.0))),
+        ];
+        for buf in &buffers {
+            let mut dr = DRMeter::new(2, 48_000).unwrap();
+            dr.add_audio_buffer(buf).unwrap();
+            dr.finalize().unwrap();
+            assert!(
+                (dr.exact_dr().unwrap() - expected).abs() < 1e-6,
+                "{:?}",
+                buf.spec()
+            );
+        }
+
+        let mut dr = DRMeter::new(2, 44_100).unwrap();
+        assert_eq!(
+            dr.add_audio_buffer(&AudioBufferRef::S16(Cow::Borrowed(&s16))),
+            Err(Error::Mismatch)
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Command line DR meter decoding FLAC, MP3, OGG Vorbis and WAV files
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use drmeter::units::amplitude_to_dbfs;
+use drmeter::{analyze_file, DRAlbum, DRResults};
+
+fn usage() -> ExitCode {
+    eprintln!("Usage: drmeter <file>...");
+    ExitCode::FAILURE
+}
+
+fn main() -> ExitCode {
+    let files: Vec<String> = std::env::args().skip(1).collect();
+    if files.is_empty() || files.iter().any(|arg| arg.starts_with('-')) {
+        return usage();
+    }
+
+    let mut failed = false;
+    let mut album = DRAlbum::new();
+    for file in &files {
+        match analyze_file(file) {
+            Ok(results) => {
+                println!("========== {file} ==========");
+                print_scores(&results);
+                album.add_track(track_name(file), results);
+            }
+            Err(e) => {
+                eprintln!("Cannot analyze {file}: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    if !album.tracks().is_empty() {
+        println!();
+        print_summary(&album);
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Name of track in summary table
+fn track_name(file: &str) -> String {
+    let path = Path::new(file);
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn print_scores(dr: &DRResults) {
+    println!("Channels: {}, Sample rate: {}Hz", dr.channels(), dr.rate());
+    for ch in 0..dr.channels() {
+        println!(
+            "Channel {ch}: {} ({:.2})",
+            dr.channel_dr_score(ch).unwrap(),
+            dr.exact_channel_dr(ch).unwrap()
+        );
+    }
+    println!("Overall: {} ({:.2})", dr.dr_score(), dr.exact_dr());
+}
+
+/// Print one row per track with its highest peak and RMS over channels
+fn print_summary(album: &DRAlbum) {
+    let db = |v: Result<f64, drmeter::Error>| amplitude_to_dbfs(v.unwrap_or_default());
+
+    println!("DR         Peak         RMS     Duration File");
+    for track in album.tracks() {
+        let r = &track.results;
+        let peak = (0..r.channels())
+            .map(|ch| db(r.first_peak(ch)))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let rms = (0..r.channels())
+            .map(|ch| db(r.rms(ch)))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let secs = r.duration().as_secs();
+        println!(
+            "{:<6}{peak:>9.2} dB{rms:>9.2} dB{:>10} {}",
+            r.dr_score(),
+            format!("{}:{:02}", secs / 60, secs % 60),
+            track.name
+        );
+    }
+    if album.tracks().len() > 1 {
+        println!("Number of files:   {}", album.tracks().len());
+        println!("Official DR value: {}", album.dr_score());
+    }
+}
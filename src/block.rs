@@ -1,6 +1,75 @@
-use crate::utils::{Sample, Samples};
+use std::f64::consts::PI;
+
+use crate::math;
+#[cfg(feature = "profiling")]
+use crate::profile::{Profile, Timer};
+use crate::true_peak::Oversampler;
+use crate::utils::{Planar, Sample, Samples};
+use crate::weighting::WeightingFilter;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Float samples below this magnitude are measured as silence
+///
+/// Their squares would be subnormal, which makes accumulation very slow
+/// on some CPUs. This is about -3000 dBFS, so no result is affected.
+const FLUSH_THRESHOLD: f64 = 1e-150;
+
+/// Flush tiny float sample to zero (integer samples are never tiny)
+#[inline(always)]
+fn flush_tiny<T: Sample>(v: f64) -> f64 {
+    if T::FINITE || v.abs() >= FLUSH_THRESHOLD {
+        v
+    } else {
+        0.0
+    }
+}
+
+/// Periodic Hann window of `frames` length
+pub(crate) fn hann(frames: usize) -> Box<[f64]> {
+    (0..frames)
+        .map(|n| 0.5 - 0.5 * math::cos(2.0 * PI * n as f64 / frames as f64))
+        .collect()
+}
+
+/// Number of independent lanes of peak search
+const LANES: usize = 8;
+
+/// Largest magnitude of samples (in raw units of `T`)
+///
+/// Maximum does not depend on order of samples, so it is searched in
+/// independent lanes, which compile to vector instructions of baseline
+/// target features (SSE2, NEON).
+/// Result is the same as of sequential search (NaN samples are skipped).
+///
+/// Energy is still summed sample by sample, because order of float
+/// additions changes the sum.
+#[inline]
+fn peak<T: Sample>(samples: &[T]) -> f64 {
+    let mut lanes = [0.0; LANES];
+    let chunks = samples.chunks_exact(LANES);
+    let rest = chunks.remainder();
+    for chunk in chunks {
+        for (lane, sample) in lanes.iter_mut().zip(chunk) {
+            let v = sample.as_f64_raw().abs();
+            if v > *lane {
+                *lane = v;
+            }
+        }
+    }
+    for sample in rest {
+        let v = sample.as_f64_raw().abs();
+        if v > lanes[0] {
+            lanes[0] = v;
+        }
+    }
+    lanes
+        .into_iter()
+        .fold(0.0, |max, v| if v > max { v } else { max })
+}
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Block {
     /// Number of channels
     channels: u32,
@@ -11,8 +80,37 @@ pub struct Block {
     /// Previously measured sample peak.
     sample_peak: Box<[f64]>,
 
+    /// Previously measured true peak (zero without oversampler).
+    true_peak: Box<[f64]>,
+
     /// This is energy per channel
     sum2: Box<[f64]>,
+
+    /// Exact energy of integer samples per channel (in raw sample units)
+    sum2_int: Box<[u128]>,
+
+    /// Squared max amplitude of integer samples in `sum2_int` (0 if empty)
+    int_scale: f64,
+
+    /// Weighting of RMS path, its state is kept across blocks
+    weighting: Option<WeightingFilter>,
+
+    /// Interpolation of peak path for true peaks, its state is kept across blocks
+    oversampler: Option<Oversampler>,
+
+    /// Window applied to energy (rectangular if `None`)
+    window: Option<Box<[f64]>>,
+
+    /// Sum of window over consumed frames
+    window_sum: f64,
+
+    /// Deinterleaved samples of processed chunk (channel after channel)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    scratch: Vec<f64>,
+
+    /// Time spent in peak and RMS phases
+    #[cfg(feature = "profiling")]
+    profile: Profile,
 }
 
 impl Block {
@@ -24,7 +122,17 @@ impl Block {
             channels,
             consumed_frames: 0,
             sample_peak: vec![0.0; channels as usize].into_boxed_slice(),
+            true_peak: vec![0.0; channels as usize].into_boxed_slice(),
             sum2: vec![0.0; channels as usize].into_boxed_slice(),
+            sum2_int: vec![0; channels as usize].into_boxed_slice(),
+            int_scale: 0.0,
+            weighting: None,
+            oversampler: None,
+            window: None,
+            window_sum: 0.0,
+            scratch: Vec::new(),
+            #[cfg(feature = "profiling")]
+            profile: Profile::default(),
         }
     }
 
@@ -33,10 +141,95 @@ impl Block {
         self.consumed_frames
     }
 
+    /// Accumulated time of peak and RMS phases
+    #[cfg(feature = "profiling")]
+    pub const fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Sample peak of consumed frames per channel
+    #[cfg(feature = "diagnostics")]
+    pub fn sample_peak(&self) -> &[f64] {
+        &self.sample_peak
+    }
+
+    /// Sum of squared consumed samples per channel
+    #[cfg(feature = "diagnostics")]
+    pub fn sum2(&self) -> Vec<f64> {
+        Iterator::zip(self.sum2.iter(), self.sum2_int.iter())
+            .map(|(sum, int)| self.int_sum2(*sum, *int))
+            .collect()
+    }
+
+    /// Float energy with exact integer energy added
+    fn int_sum2(&self, sum2: f64, sum2_int: u128) -> f64 {
+        if sum2_int == 0 {
+            sum2
+        } else {
+            sum2 + sum2_int as f64 / self.int_scale
+        }
+    }
+
+    /// Move integer energy to float energy
+    fn flush_int(&mut self) {
+        for (sum2, sum2_int) in self.sum2.iter_mut().zip(self.sum2_int.iter_mut()) {
+            if *sum2_int != 0 {
+                *sum2 += *sum2_int as f64 / self.int_scale;
+                *sum2_int = 0;
+            }
+        }
+        self.int_scale = 0.0;
+    }
+
+    /// Integer samples of type are accumulated in integers
+    fn integer_path<T: Sample>(&self) -> bool {
+        T::INTEGER && self.weighting.is_none() && self.window.is_none()
+    }
+
+    /// Set weighting filter of RMS path
+    pub fn set_weighting(&mut self, weighting: Option<WeightingFilter>) {
+        self.weighting = weighting;
+    }
+
+    /// Set interpolation of true peak path, sample peaks are not affected by it
+    pub fn set_oversampler(&mut self, oversampler: Option<Oversampler>) {
+        self.oversampler = oversampler;
+    }
+
+    /// Set window of energy, it must cover the whole block
+    pub fn set_window(&mut self, window: Option<Box<[f64]>>) {
+        self.window = window;
+    }
+
+    /// Length of window of energy
+    pub fn window_len(&self) -> Option<usize> {
+        self.window.as_ref().map(|window| window.len())
+    }
+
     pub fn reset(&mut self) {
         self.sample_peak.fill(0.0);
+        self.true_peak.fill(0.0);
         self.sum2.fill(0.0);
+        self.sum2_int.fill(0);
+        self.int_scale = 0.0;
         self.consumed_frames = 0;
+        self.window_sum = 0.0;
+    }
+
+    /// Reset block and state of weighting filter for a new stream
+    pub fn clear(&mut self) {
+        self.reset();
+        if let Some(weighting) = &mut self.weighting {
+            weighting.reset();
+        }
+        if let Some(oversampler) = &mut self.oversampler {
+            oversampler.reset();
+        }
+    }
+
+    /// True peak of consumed frames per channel (zero without oversampler)
+    pub fn true_peak(&self) -> &[f64] {
+        &self.true_peak
     }
 
     /// Return finalized block results
@@ -44,11 +237,15 @@ impl Block {
     /// NOTE: This does not finalize block, so you can still feed it.
     /// You must use `reset` method to really finalize the block.
     pub fn finish(&mut self) -> (Box<[f64]>, Box<[f64]>) {
+        // windowed energy is normalized by window, so steady signal keeps its RMS
+        let frames = match self.window {
+            Some(_) => self.window_sum,
+            None => self.consumed_frames as f64,
+        };
         (
             self.sample_peak.clone(),
-            self.sum2
-                .iter()
-                .map(|sum| f64::sqrt(2.0 * *sum / self.consumed_frames as f64))
+            Iterator::zip(self.sum2.iter(), self.sum2_int.iter())
+                .map(|(sum, int)| math::sqrt(2.0 * self.int_sum2(*sum, *int) / frames))
                 .collect(),
         )
     }
@@ -60,34 +257,253 @@ impl Block {
     pub fn process<'a, T: Sample + 'a, S: Samples<'a, T>>(&mut self, src: S) {
         assert!(src.channels() == self.channels as usize);
 
+        // integer energy of samples of other type would be scaled differently
+        if self.integer_path::<T>() && self.int_scale != T::MAX_AMPLITUDE * T::MAX_AMPLITUDE {
+            self.flush_int();
+            self.int_scale = T::MAX_AMPLITUDE * T::MAX_AMPLITUDE;
+        }
+
+        // accumulation strides through interleaved buffer once per channel,
+        // so it is cheaper to deinterleave it once
+        // (unless integer samples are accumulated without conversion)
+        if S::INTERLEAVED && self.channels > 1 && src.frames() > 0 && !self.integer_path::<T>() {
+            let mut scratch = std::mem::take(&mut self.scratch);
+            src.deinterleave(&mut scratch);
+            let planes: Vec<&[f64]> = scratch.chunks_exact(src.frames()).collect();
+            self.accumulate(Planar::new(&planes).expect("planes of same length"));
+            self.scratch = scratch;
+        } else {
+            self.accumulate(src);
+        }
+    }
+
+    /// Accumulate peaks and energy of frames
+    fn accumulate<'a, T: Sample + 'a, S: Samples<'a, T>>(&mut self, src: S) {
         debug_assert!(self.sample_peak.len() == self.channels as usize);
 
-        for (channel, sample_peak) in self.sample_peak.iter_mut().enumerate() {
-            let mut max = 0.0;
+        #[cfg(feature = "profiling")]
+        let timer = Timer::start();
 
+        for (channel, sample_peak) in self.sample_peak.iter_mut().enumerate() {
             debug_assert!(channel < src.channels());
 
-            src.foreach_sample(channel, |sample| {
-                let v = sample.as_f64_raw().abs();
-                if v > max {
-                    max = v;
+            let mut max = match src.channel_slice(channel) {
+                Some(samples) => peak(samples),
+                // strided samples
+                None => {
+                    let mut max = 0.0;
+                    src.foreach_sample(channel, |sample| {
+                        let v = sample.as_f64_raw().abs();
+                        if v > max {
+                            max = v;
+                        }
+                    });
+                    max
                 }
-            });
-
+            };
             max /= T::MAX_AMPLITUDE;
+
             if max > *sample_peak {
                 *sample_peak = max;
             }
         }
 
-        for (channel, sum2) in self.sum2.iter_mut().enumerate() {
+        if let Some(oversampler) = &mut self.oversampler {
+            for (channel, true_peak) in self.true_peak.iter_mut().enumerate() {
+                src.foreach_sample(channel, |sample| {
+                    let v = sample.to_sample::<f64>();
+                    // interpolation is delayed, so the sample itself counts too
+                    let v = f64::max(oversampler.process(channel, v), v.abs());
+                    if v > *true_peak {
+                        *true_peak = v;
+                    }
+                });
+            }
+        }
+
+        #[cfg(feature = "profiling")]
+        timer.stop(&mut self.profile.peak);
+        #[cfg(feature = "profiling")]
+        let timer = Timer::start();
+
+        let frames = src.frames();
+        let start = self.consumed_frames;
+        // window is sliced up front, so it is zipped without bounds checks
+        let window = self
+            .window
+            .as_deref()
+            .map(|window| &window[start..start + frames]);
+        let integer_path = self.integer_path::<T>();
+        for (channel, (sum2, sum2_int)) in self
+            .sum2
+            .iter_mut()
+            .zip(self.sum2_int.iter_mut())
+            .enumerate()
+        {
             debug_assert!(channel < src.channels());
 
-            src.foreach_sample(channel, |sample| {
-                *sum2 += sample.to_sample::<f64>() * sample.to_sample::<f64>();
-            });
+            let filter = self.weighting.as_mut().map(|f| f.channel(channel));
+            match (filter, window) {
+                (None, None) if integer_path => src.foreach_sample(channel, |sample| {
+                    *sum2_int += sample.square_raw() as u128;
+                }),
+                (None, None) => src.foreach_sample(channel, |sample| {
+                    let v = flush_tiny::<T>(sample.to_sample::<f64>());
+                    *sum2 += v * v;
+                }),
+                (None, Some(window)) => {
+                    src.foreach_sample_zipped(channel, window.iter(), |sample, w| {
+                        let v = flush_tiny::<T>(sample.to_sample::<f64>());
+                        *sum2 += w * v * v;
+                    })
+                }
+                (Some(mut filter), None) => src.foreach_sample(channel, |sample| {
+                    let v = filter.process(flush_tiny::<T>(sample.to_sample::<f64>()));
+                    *sum2 += v * v;
+                }),
+                (Some(mut filter), Some(window)) => {
+                    src.foreach_sample_zipped(channel, window.iter(), |sample, w| {
+                        let v = filter.process(flush_tiny::<T>(sample.to_sample::<f64>()));
+                        *sum2 += w * v * v;
+                    })
+                }
+            }
+        }
+        if let Some(window) = window {
+            self.window_sum += window.iter().sum::<f64>();
+        }
+
+        #[cfg(feature = "profiling")]
+        timer.stop(&mut self.profile.rms);
+
+        self.consumed_frames += frames;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Interleaved;
+
+    #[test]
+    fn lanes_peak() {
+        let mut data: Vec<f32> = (0..4800 * 2 + 5)
+            .map(|i| f32::sin(i as f32 * 0.01) * (i % 777) as f32 / 777.0)
+            .collect();
+        data[100] = f32::NAN;
+        data[4321] = -0.0;
+        let ints: Vec<i16> = (0..4803)
+            .map(|i| ((i * 37) % 65_536 - 32_768) as i16)
+            .collect();
+        let sequential = |samples: &[f64]| {
+            samples
+                .iter()
+                .fold(0.0, |max, v| if v.abs() > max { v.abs() } else { max })
+        };
+        let floats: Vec<f64> = data.iter().map(|s| f64::from(*s)).collect();
+        let expected_ints: Vec<f64> = ints.iter().map(|s| f64::from(*s)).collect();
+
+        // every length of remainder
+        for len in data.len() - LANES..=data.len() {
+            assert_eq!(peak(&data[..len]), sequential(&floats[..len]), "{len}");
+        }
+        assert_eq!(peak(&ints), sequential(&expected_ints));
+        assert_eq!(peak::<f64>(&[]), 0.0);
+    }
+
+    #[test]
+    fn deinterleave_once() {
+        let data: Vec<i16> = (0..4800 * 2).map(|i| ((i * 37) % 20_000) as i16).collect();
+        let left: Vec<i16> = data.iter().step_by(2).copied().collect();
+        let right: Vec<i16> = data.iter().skip(1).step_by(2).copied().collect();
+
+        let mut interleaved = Block::new(2);
+        interleaved.process(Interleaved::new(&data, 2).unwrap());
+        let mut planar = Block::new(2);
+        planar.process(Planar::new(&[&left, &right]).unwrap());
+
+        assert_eq!(interleaved.finish(), planar.finish());
+
+        let data: Vec<f32> = (0..4800 * 3)
+            .map(|i| f32::sin(i as f32 * 0.01) * (i % 3 + 1) as f32 / 3.0)
+            .collect();
+        let planes: Vec<Vec<f32>> = (0..3)
+            .map(|ch| data.iter().skip(ch).step_by(3).copied().collect())
+            .collect();
+        let mut interleaved = Block::new(3);
+        interleaved.process(Interleaved::new(&data, 3).unwrap());
+        let mut planar = Block::new(3);
+        planar.process(Planar::new(&[&planes[0], &planes[1], &planes[2]]).unwrap());
+
+        assert_eq!(interleaved.finish(), planar.finish());
+    }
+
+    #[test]
+    fn integer_accumulation() {
+        let data: Vec<i32> = (0..4800 * 2)
+            .map(|i| ((i * 7919) % 65_536 - 32_768) << 16)
+            .collect();
+        let shifted: Vec<i16> = data.iter().map(|s| (s >> 16) as i16).collect();
+        let floats: Vec<f64> = data.iter().map(|s| *s as f64 / 2_147_483_648.0).collect();
+
+        let mut int32 = Block::new(2);
+        int32.process(Interleaved::new(&data, 2).unwrap());
+        assert!(int32.sum2_int.iter().all(|sum| *sum > 0));
+        let mut int16 = Block::new(2);
+        int16.process(Interleaved::new(&shifted, 2).unwrap());
+        // exact accumulation is not affected by sample width
+        assert_eq!(int32.finish(), int16.finish());
+
+        let mut float = Block::new(2);
+        float.process(Interleaved::new(&floats, 2).unwrap());
+        let (_, rms) = float.finish();
+        for (int, float) in int32.finish().1.iter().zip(rms.iter()) {
+            assert!((int - float).abs() < 1e-12);
+        }
+
+        // mixed widths in one block
+        let mut mixed = Block::new(2);
+        mixed.process(Interleaved::new(&data[..4800], 2).unwrap());
+        mixed.process(Interleaved::new(&shifted[4800..], 2).unwrap());
+        for (mixed, int) in mixed.finish().1.iter().zip(int16.finish().1.iter()) {
+            assert!((mixed - int).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn flush_subnormal() {
+        let mut tiny = Block::new(1);
+        tiny.process(
+            Interleaved::new(&[0.5, 1e-160, -1e-300, f64::MIN_POSITIVE / 2.0], 1).unwrap(),
+        );
+        let mut zero = Block::new(1);
+        zero.process(Interleaved::new(&[0.5, 0.0, 0.0, 0.0], 1).unwrap());
+
+        assert_eq!(tiny.finish(), zero.finish());
+    }
+
+    #[test]
+    fn hann_window() {
+        // steady sine keeps its RMS, also when fed in pieces
+        let data: Vec<f64> = (0..4800).map(|i| f64::sin(i as f64 * 0.1)).collect();
+        let mut block = Block::new(1);
+        block.set_window(Some(hann(data.len())));
+        for chunk in data.chunks(1000) {
+            block.process(Interleaved::new(chunk, 1).unwrap());
         }
+        let (_, rms) = block.finish();
+        assert!((rms[0] - 1.0).abs() < 1e-3);
 
-        self.consumed_frames += src.frames();
+        // burst at block edge is attenuated
+        let mut edge = data.clone();
+        edge[..4600].fill(0.0);
+        let mut block = Block::new(1);
+        block.process(Interleaved::new(&edge, 1).unwrap());
+        let (_, rectangular) = block.finish();
+        let mut block = Block::new(1);
+        block.set_window(Some(hann(edge.len())));
+        block.process(Interleaved::new(&edge, 1).unwrap());
+        let (_, windowed) = block.finish();
+        assert!(windowed[0] < rectangular[0] / 5.0);
     }
 }
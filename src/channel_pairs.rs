@@ -0,0 +1,152 @@
+//! Detection of dual-mono programs and polarity-inverted channels
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::math;
+use crate::units::decibel;
+use crate::utils::{Sample, Samples};
+
+/// Energy of channel difference relative to channel energy (in dB)
+/// below which channels are considered identical
+pub const DUAL_MONO_THRESHOLD: f64 = -60.0;
+
+/// Correlation of channels at or below which one of them
+/// is considered polarity inverted
+pub const POLARITY_INVERSION_THRESHOLD: f64 = -0.8;
+
+/// Energy of channels and products of all channel pairs
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct ChannelPairs {
+    /// energy per channel
+    energy: Box<[f64]>,
+    /// sum of products of channel pairs `(a, b)` with `a < b`, ordered by `a` then `b`
+    product: Box<[f64]>,
+    /// deinterleaved samples of last added frames
+    #[cfg_attr(feature = "serde", serde(skip))]
+    scratch: Vec<f64>,
+}
+
+/// Channel pairs `(a, b)` with `a < b`, ordered by `a` then `b`
+fn pairs(channels: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..channels).flat_map(move |a| (a + 1..channels).map(move |b| (a, b)))
+}
+
+impl ChannelPairs {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            energy: vec![0.0; channels].into_boxed_slice(),
+            product: vec![0.0; channels * channels.saturating_sub(1) / 2].into_boxed_slice(),
+            scratch: Vec::new(),
+        }
+    }
+
+    /// Update sums with newly added frames
+    pub fn update<'a, T: Sample + 'a, S: Samples<'a, T>>(&mut self, src: &S) {
+        let frames = src.frames();
+        if frames == 0 {
+            return;
+        }
+        src.deinterleave(&mut self.scratch);
+        let planes: Vec<&[f64]> = self.scratch.chunks_exact(frames).collect();
+
+        for (energy, plane) in self.energy.iter_mut().zip(&planes) {
+            *energy += plane.iter().map(|s| s * s).sum::<f64>();
+        }
+        for (product, (a, b)) in self.product.iter_mut().zip(pairs(planes.len())) {
+            *product += Iterator::zip(planes[a].iter(), planes[b].iter())
+                .map(|(x, y)| x * y)
+                .sum::<f64>();
+        }
+    }
+
+    /// Clear sums for a new stream
+    pub fn reset(&mut self) {
+        self.energy.fill(0.0);
+        self.product.fill(0.0);
+    }
+
+    /// Add sums of other detector with the same number of channels
+    pub fn merge(&mut self, other: &Self) {
+        for (e, o) in self.energy.iter_mut().zip(other.energy.iter()) {
+            *e += o;
+        }
+        for (p, o) in self.product.iter_mut().zip(other.product.iter()) {
+            *p += o;
+        }
+    }
+
+    /// Pairs of channels that are not silent with their energies and product
+    fn audible(&self) -> impl Iterator<Item = ((u32, u32), f64, f64, f64)> + '_ {
+        Iterator::zip(pairs(self.energy.len()), self.product.iter())
+            .map(|((a, b), &product)| ((a, b), self.energy[a], self.energy[b], product))
+            .filter(|&(_, ea, eb, _)| ea > 0.0 && eb > 0.0)
+            .map(|((a, b), ea, eb, product)| ((a as u32, b as u32), ea, eb, product))
+    }
+
+    /// Returns pairs of identical channels `(a, b)` with `a < b`
+    ///
+    /// Silent channels are not considered identical.
+    pub fn dual_mono(&self) -> Vec<(u32, u32)> {
+        self.audible()
+            .filter(|&(_, ea, eb, product)| {
+                // energy of difference
+                let diff = f64::max(ea + eb - 2.0 * product, 0.0);
+                decibel(math::sqrt(diff / f64::max(ea, eb))) < DUAL_MONO_THRESHOLD
+            })
+            .map(|(pair, ..)| pair)
+            .collect()
+    }
+
+    /// Returns pairs of strongly negatively correlated channels `(a, b)` with `a < b`
+    pub fn inverted(&self) -> Vec<(u32, u32)> {
+        self.audible()
+            .filter(|&(_, ea, eb, product)| {
+                product / math::sqrt(ea * eb) <= POLARITY_INVERSION_THRESHOLD
+            })
+            .map(|(pair, ..)| pair)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DRMeter;
+
+    #[test]
+    fn dual_mono() {
+        let data: Vec<f32> = (0..48_000 * 3)
+            .flat_map(|i| {
+                let s = f32::sin(i as f32 * 0.01) * 0.5;
+                [s, s, 0.9 * s]
+            })
+            .collect();
+        let mut dr = DRMeter::new(3, 48_000).unwrap();
+        assert_eq!(dr.dual_mono_pairs(), None);
+        dr.set_dual_mono_detection(true).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.dual_mono_pairs(), Some(vec![(0, 1)]));
+        assert_eq!(dr.inverted_pairs(), None);
+        assert_eq!(dr.results().unwrap().dual_mono_pairs(), &[(0, 1)]);
+    }
+
+    #[test]
+    fn polarity_inversion() {
+        let data: Vec<f32> = (0..48_000 * 3)
+            .flat_map(|i| {
+                let s = f32::sin(i as f32 * 0.01) * 0.5;
+                let noise = f32::sin(i as f32 * 1.3) * 0.1;
+                [s, -s + noise, noise]
+            })
+            .collect();
+        let mut dr = DRMeter::new(3, 48_000).unwrap();
+        dr.set_polarity_detection(true).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.inverted_pairs(), Some(vec![(0, 1)]));
+        assert_eq!(dr.dual_mono_pairs(), None);
+        assert_eq!(dr.results().unwrap().inverted_pairs(), &[(0, 1)]);
+    }
+}
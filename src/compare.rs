@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use crate::units::amplitude_to_dbfs;
+use crate::{DRResults, Error};
+
+/// Difference of one channel between two results
+///
+/// All values are `new - old`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelDelta {
+    /// Difference of exact DR
+    pub dr: f64,
+    /// Difference of highest block peak (in dB)
+    pub first_peak: f64,
+    /// Difference of second highest block peak (in dB)
+    pub second_peak: f64,
+    /// Difference of top 20% RMS (in dB)
+    pub rms: f64,
+}
+
+/// Comparison of two [`DRResults`] with the same number of channels
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    dr: f64,
+    channels: Vec<ChannelDelta>,
+}
+
+impl Comparison {
+    /// Compare `new` results against `old` results
+    pub fn new(old: &DRResults, new: &DRResults) -> Result<Self, Error> {
+        if old.channels() != new.channels() {
+            return Err(Error::Mismatch);
+        }
+
+        let channels = (0..old.channels())
+            .map(|ch| {
+                Ok(ChannelDelta {
+                    dr: new.exact_channel_dr(ch)? - old.exact_channel_dr(ch)?,
+                    first_peak: amplitude_to_dbfs(new.first_peak(ch)?)
+                        - amplitude_to_dbfs(old.first_peak(ch)?),
+                    second_peak: amplitude_to_dbfs(new.second_peak(ch)?)
+                        - amplitude_to_dbfs(old.second_peak(ch)?),
+                    rms: amplitude_to_dbfs(new.rms(ch)?) - amplitude_to_dbfs(old.rms(ch)?),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(Self {
+            dr: new.exact_dr() - old.exact_dr(),
+            channels,
+        })
+    }
+
+    /// Difference of exact DR
+    pub const fn dr(&self) -> f64 {
+        self.dr
+    }
+
+    /// Difference of channel
+    pub fn channel(&self, channel_number: u32) -> Result<&ChannelDelta, Error> {
+        self.channels
+            .get(channel_number as usize)
+            .ok_or(Error::InvalidChannelIndex)
+    }
+
+    /// Differences of all channels
+    pub fn channels(&self) -> &[ChannelDelta] {
+        &self.channels
+    }
+}
+
+/// Difference of one entry between two scans
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanDiff<K> {
+    /// Entry is only in the new scan
+    Added(K),
+    /// Entry is only in the old scan
+    Removed(K),
+    /// Entry is in both scans
+    Compared(K, Comparison),
+    /// Entry is in both scans, but results cannot be compared
+    /// (egg. number of channels changed)
+    Incompatible(K),
+}
+
+/// Compare two scans of results keyed by the same keys (egg. file paths)
+///
+/// Differences are returned in key order.
+pub fn compare_scans<K: Ord + Clone>(
+    old: &BTreeMap<K, DRResults>,
+    new: &BTreeMap<K, DRResults>,
+) -> Vec<ScanDiff<K>> {
+    let mut diffs: Vec<_> = old
+        .iter()
+        .map(|(key, old)| match new.get(key) {
+            Some(new) => match Comparison::new(old, new) {
+                Ok(cmp) => ScanDiff::Compared(key.clone(), cmp),
+                Err(_) => ScanDiff::Incompatible(key.clone()),
+            },
+            None => ScanDiff::Removed(key.clone()),
+        })
+        .collect();
+    diffs.extend(
+        new.keys()
+            .filter(|key| !old.contains_key(key))
+            .map(|key| ScanDiff::Added(key.clone())),
+    );
+    diffs.sort_by(|a, b| a.key().cmp(b.key()));
+
+    diffs
+}
+
+impl<K> ScanDiff<K> {
+    /// Key of entry
+    pub const fn key(&self) -> &K {
+        match self {
+            ScanDiff::Added(k)
+            | ScanDiff::Removed(k)
+            | ScanDiff::Compared(k, _)
+            | ScanDiff::Incompatible(k) => k,
+        }
+    }
+}
+
+/// Load all saved results (`*.dr` files written with [`DRResults::save`])
+/// from directory, keyed by file stem
+#[cfg(feature = "bincode")]
+pub fn load_scan(
+    dir: impl AsRef<std::path::Path>,
+) -> bincode::Result<BTreeMap<std::ffi::OsString, DRResults>> {
+    let mut scan = BTreeMap::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "dr") {
+            let results = DRResults::load(std::io::BufReader::new(std::fs::File::open(&path)?))?;
+            scan.insert(path.file_stem().unwrap_or_default().to_owned(), results);
+        }
+    }
+
+    Ok(scan)
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+    use crate::DRMeter;
+
+    fn results(gain: f32) -> DRResults {
+        let data: Vec<f32> = (0..48_000 * 10)
+            .map(|i| gain * f32::sin(i as f32 * 0.01) * (i % 48_000) as f32 / 48_000.0)
+            .collect();
+        let mut dr = DRMeter::new(1, 48_000).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        dr.results().unwrap()
+    }
+
+    #[test]
+    fn gain_change() {
+        let old = results(0.5);
+        let new = results(0.25);
+        let cmp = Comparison::new(&old, &new).unwrap();
+        assert_float_eq!(cmp.dr(), 0.0, abs <= 0.01);
+        assert_float_eq!(cmp.channel(0).unwrap().rms, -6.02, abs <= 0.01);
+        assert_float_eq!(cmp.channel(0).unwrap().first_peak, -6.02, abs <= 0.01);
+    }
+
+    #[test]
+    fn scans() {
+        let old = BTreeMap::from([("a", results(0.5)), ("b", results(0.5))]);
+        let new = BTreeMap::from([("b", results(0.5)), ("c", results(0.5))]);
+        let diffs = compare_scans(&old, &new);
+        assert_eq!(diffs.len(), 3);
+        assert_eq!(diffs[0], ScanDiff::Removed("a"));
+        assert!(matches!(diffs[1], ScanDiff::Compared("b", _)));
+        assert_eq!(diffs[2], ScanDiff::Added("c"));
+    }
+}
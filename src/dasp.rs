@@ -0,0 +1,155 @@
+//! Input of [dasp](https://docs.rs/dasp) frames and signals
+//!
+//! Frames like `[f32; 2]` are read in place, without interleaving them
+//! into a sample buffer first.
+
+use dasp_frame::Frame;
+use dasp_signal::Signal;
+
+use crate::utils::{Sample, Samples};
+use crate::{DRMeter, Error};
+
+/// Number of frames of signal buffered before processing
+const SIGNAL_CHUNK: usize = 4096;
+
+/// Slice of dasp frames as [`Samples`]
+#[derive(Debug, Clone, Copy)]
+pub struct Frames<'a, F> {
+    data: &'a [F],
+}
+
+impl<'a, F: Frame> Frames<'a, F> {
+    /// Create a new wrapper around frames.
+    pub fn new(data: &'a [F]) -> Self {
+        Self { data }
+    }
+}
+
+impl<'a, F: Frame> Samples<'a, F::Sample> for Frames<'a, F>
+where
+    F::Sample: Sample,
+{
+    const INTERLEAVED: bool = F::CHANNELS > 1;
+
+    #[inline]
+    fn foreach_sample(&self, channel: usize, mut func: impl FnMut(&'a F::Sample)) {
+        assert!(channel < F::CHANNELS);
+
+        for f in self.data {
+            func(f.channel(channel).expect("channel of frame"))
+        }
+    }
+
+    #[inline]
+    fn foreach_sample_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        mut func: impl FnMut(&'a F::Sample, U),
+    ) {
+        assert!(channel < F::CHANNELS);
+
+        for (f, u) in Iterator::zip(self.data.iter(), iter) {
+            func(f.channel(channel).expect("channel of frame"), u)
+        }
+    }
+
+    #[inline]
+    fn foreach_frame<G: Frame<Sample = F::Sample>>(&self, mut func: impl FnMut(G)) {
+        assert_eq!(G::CHANNELS, F::CHANNELS);
+        for f in self.data {
+            func(G::from_samples(&mut f.channels()).unwrap());
+        }
+    }
+
+    #[inline]
+    fn frames(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    fn channels(&self) -> usize {
+        F::CHANNELS
+    }
+
+    #[inline]
+    fn split_at(self, sample: usize) -> (Self, Self) {
+        let (fst, snd) = self.data.split_at(sample);
+        (Self { data: fst }, Self { data: snd })
+    }
+}
+
+impl DRMeter {
+    /// Add dasp frames to be processed.
+    ///
+    /// Channels of frame must match the meter.
+    pub fn add_dasp_frames<F: Frame>(&mut self, frames: &[F]) -> Result<(), Error>
+    where
+        F::Sample: Sample,
+    {
+        self.add_samples(Frames::new(frames))
+    }
+
+    /// Add up to `frames` frames of signal to be processed.
+    ///
+    /// Frames are processed in chunks as they are pulled from the signal,
+    /// exhausted signal ends early. Pass `&mut signal` to continue it later.
+    pub fn add_signal<S: Signal>(&mut self, mut signal: S, frames: usize) -> Result<(), Error>
+    where
+        <S::Frame as Frame>::Sample: Sample,
+    {
+        let mut remaining = frames;
+        let mut chunk = Vec::with_capacity(SIGNAL_CHUNK.min(frames));
+        while remaining > 0 && !signal.is_exhausted() {
+            chunk.clear();
+            while chunk.len() < SIGNAL_CHUNK.min(remaining) && !signal.is_exhausted() {
+                chunk.push(signal.next());
+            }
+            remaining -= chunk.len();
+            self.add_dasp_frames(&chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames() {
+        let data: Vec<[f32; 2]> = (0..48_000 * 5)
+            .map(|i| f32::sin(i as f32 * 0.01) * (1 + i / 30_000 % 3) as f32 / 3.0)
+            .map(|s| [s, s * 0.5])
+            .collect();
+        let interleaved: Vec<f32> = data.iter().flatten().copied().collect();
+        let mut expected = DRMeter::new(2, 48_000).unwrap();
+        expected.add_frames_f32(&interleaved).unwrap();
+        expected.finalize().unwrap();
+
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.add_dasp_frames(&data).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+
+        // endless signal continued over two adds
+        let mut signal = dasp_signal::from_iter(data.iter().copied().cycle());
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.add_signal(&mut signal, 10_000).unwrap();
+        dr.add_signal(&mut signal, data.len() - 10_000).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+
+        // exhausted signal
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.add_signal(dasp_signal::from_iter(data.iter().copied()), usize::MAX)
+            .unwrap();
+        assert_eq!(dr.frames(), data.len() as u64);
+
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        assert_eq!(
+            dr.add_dasp_frames(&[0.5f32; 10]),
+            Err(Error::InvalidInput { frame: 0 })
+        );
+    }
+}
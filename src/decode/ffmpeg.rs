@@ -0,0 +1,222 @@
+//! Decoding with [FFmpeg](https://ffmpeg.org) through [ffmpeg-next](https://github.com/zmwangx/rust-ffmpeg)
+//!
+//! Decoded frames of every FFmpeg sample format (packed or planar)
+//! are fed to the meter as they are, without resampling.
+
+use std::path::Path;
+use std::time::Duration;
+use std::{error, fmt};
+
+use ffmpeg::format::Sample;
+use ffmpeg::util::frame::audio::Audio;
+use ffmpeg_next as ffmpeg;
+
+use crate::{DRMeter, DRResults, DrReport, Error, TrackMetadata};
+
+/// Error of decoding and measuring audio with FFmpeg
+#[derive(Debug)]
+pub enum FfmpegError {
+    /// Container or codec error
+    Ffmpeg(ffmpeg::Error),
+    /// Meter rejected the audio (e.g. unsupported sample rate)
+    Meter(Error),
+}
+
+impl error::Error for FfmpegError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FfmpegError::Ffmpeg(e) => Some(e),
+            FfmpegError::Meter(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for FfmpegError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FfmpegError::Ffmpeg(e) => write!(f, "FFmpeg error: {e}"),
+            FfmpegError::Meter(e) => write!(f, "Meter error: {e}"),
+        }
+    }
+}
+
+impl From<ffmpeg::Error> for FfmpegError {
+    fn from(e: ffmpeg::Error) -> Self {
+        FfmpegError::Ffmpeg(e)
+    }
+}
+
+impl From<Error> for FfmpegError {
+    fn from(e: Error) -> Self {
+        FfmpegError::Meter(e)
+    }
+}
+
+/// Samples of plane of decoded frame
+///
+/// Fix from <https://github.com/zmwangx/rust-ffmpeg/pull/104>
+#[inline]
+fn plane<T: ffmpeg::frame::audio::Sample>(frame: &Audio, index: usize) -> &[T] {
+    if index >= frame.planes() {
+        panic!("out of bounds");
+    }
+    if !<T as ffmpeg::frame::audio::Sample>::is_valid(frame.format(), frame.channels()) {
+        panic!("unsupported type");
+    }
+
+    // SAFETY: type and number of samples were checked against format of frame
+    if frame.is_planar() {
+        unsafe {
+            std::slice::from_raw_parts((*frame.as_ptr()).data[index] as *const T, frame.samples())
+        }
+    } else {
+        unsafe {
+            std::slice::from_raw_parts(
+                (*frame.as_ptr()).data[0] as *const T,
+                frame.samples() * usize::from(frame.channels()),
+            )
+        }
+    }
+}
+
+/// Add packed or planar frame of samples of type `T`
+fn add<T: ffmpeg::frame::audio::Sample>(
+    dr: &mut DRMeter,
+    frame: &Audio,
+    packed: fn(&mut DRMeter, &[T]) -> Result<(), Error>,
+    planar: fn(&mut DRMeter, &[&[T]]) -> Result<(), Error>,
+) -> Result<(), Error> {
+    if frame.is_planar() {
+        let planes: Vec<&[T]> = (0..frame.planes()).map(|i| plane(frame, i)).collect();
+        planar(dr, &planes)
+    } else {
+        packed(dr, plane(frame, 0))
+    }
+}
+
+/// Add decoded frame to meter
+fn add_frame(dr: &mut DRMeter, frame: &Audio) -> Result<(), FfmpegError> {
+    match frame.format() {
+        Sample::U8(_) => add(
+            dr,
+            frame,
+            DRMeter::add_frames_u8,
+            DRMeter::add_frames_planar_u8,
+        ),
+        Sample::I16(_) => add(
+            dr,
+            frame,
+            DRMeter::add_frames_i16,
+            DRMeter::add_frames_planar_i16,
+        ),
+        Sample::I32(_) => add(
+            dr,
+            frame,
+            DRMeter::add_frames_i32,
+            DRMeter::add_frames_planar_i32,
+        ),
+        Sample::I64(_) => add(
+            dr,
+            frame,
+            DRMeter::add_frames_i64,
+            DRMeter::add_frames_planar_i64,
+        ),
+        Sample::F32(_) => add(
+            dr,
+            frame,
+            DRMeter::add_frames_f32,
+            DRMeter::add_frames_planar_f32,
+        ),
+        Sample::F64(_) => add(
+            dr,
+            frame,
+            DRMeter::add_frames_f64,
+            DRMeter::add_frames_planar_f64,
+        ),
+        Sample::None => return Err(ffmpeg::Error::InvalidData.into()),
+    }?;
+    Ok(())
+}
+
+/// Decode best audio stream of file and measure it
+///
+/// `on_frames` is called after every decoded frame, e.g. to follow
+/// [`DRMeter::last_block`] while the file is measured.
+/// Tags and stream parameters are attached to results as [`TrackMetadata`].
+pub fn measure_path_ffmpeg(
+    path: impl AsRef<Path>,
+    mut on_frames: impl FnMut(&DRMeter),
+) -> Result<DRResults, FfmpegError> {
+    ffmpeg::init()?;
+
+    let mut ictx = ffmpeg::format::input(&path.as_ref())?;
+    let input = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Audio)
+        .ok_or(ffmpeg::Error::StreamNotFound)?;
+    let idx = input.index();
+    let tags = ictx.metadata();
+    let tag = |key: &str| tags.get(key).map(str::to_owned);
+    let mut metadata = TrackMetadata {
+        artist: tag("artist"),
+        title: tag("title"),
+        album: tag("album"),
+        album_artist: tag("album_artist"),
+        disc: tags.get("disc").and_then(TrackMetadata::parse_disc),
+        duration: u64::try_from(ictx.duration())
+            .ok()
+            .map(Duration::from_micros),
+        ..Default::default()
+    };
+    let context = ffmpeg::codec::context::Context::from_parameters(input.parameters())?;
+    let mut decoder = context.decoder().audio()?;
+    decoder.set_parameters(input.parameters())?;
+    metadata.codec = decoder.codec().map(|c| c.name().to_uppercase());
+    metadata.sample_rate = Some(decoder.rate());
+    if decoder.format() == Sample::None {
+        return Err(ffmpeg::Error::InvalidData.into());
+    }
+
+    let mut dr = DRMeter::new(u32::from(decoder.channels()), decoder.rate())?;
+    let mut decoded = Audio::empty();
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != idx {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut decoded).is_ok() {
+            add_frame(&mut dr, &decoded)?;
+            on_frames(&dr);
+        }
+    }
+    // drain frames buffered by decoder
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut decoded).is_ok() {
+        add_frame(&mut dr, &decoded)?;
+        on_frames(&dr);
+    }
+
+    dr.finalize()?;
+    Ok(dr.results()?.with_metadata(metadata))
+}
+
+/// Decode best audio stream of file, measure it and return report
+///
+/// Any container and codec supported by installed FFmpeg can be measured.
+pub fn analyze_path_ffmpeg(path: impl AsRef<Path>) -> Result<DrReport, FfmpegError> {
+    Ok(DrReport::from(&measure_path_ffmpeg(path, |_| {})?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_file() {
+        let path = std::env::temp_dir().join("drmeter-missing.wav");
+        assert!(matches!(
+            analyze_path_ffmpeg(path),
+            Err(FfmpegError::Ffmpeg(_))
+        ));
+    }
+}
@@ -0,0 +1,6 @@
+//! Decoding of audio files with system libraries
+//!
+//! Unlike the pure Rust [symphonia](https://github.com/pdeljanov/Symphonia)
+//! decoder these need the libraries installed to build.
+
+pub mod ffmpeg;
@@ -0,0 +1,448 @@
+//! Decoding of audio containers with [symphonia](https://github.com/pdeljanov/Symphonia)
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+use std::{error, fmt};
+
+use symphonia::core::audio::{SampleBuffer, SignalSpec};
+use symphonia::core::codecs::{CodecParameters, Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+use symphonia::core::probe::Hint;
+use symphonia::core::units::TimeBase;
+
+use crate::{DRMeter, DRResults, Error, TrackMetadata};
+
+/// Error of decoding and measuring audio
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Container or codec error
+    Decoder(SymphoniaError),
+    /// Meter rejected the audio (e.g. unsupported sample rate)
+    Meter(Error),
+    /// Container has no audio track
+    NoTrack,
+    /// Number of channels or sample rate changed mid-stream
+    FormatChange {
+        /// position of the change (in frames from start of measured audio)
+        frame: u64,
+    },
+}
+
+impl error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            DecodeError::Decoder(e) => Some(e),
+            DecodeError::Meter(e) => Some(e),
+            DecodeError::NoTrack | DecodeError::FormatChange { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::Decoder(e) => write!(f, "Decoder error: {e}"),
+            DecodeError::Meter(e) => write!(f, "Meter error: {e}"),
+            DecodeError::NoTrack => write!(f, "No audio track"),
+            DecodeError::FormatChange { frame } => {
+                write!(f, "Audio format changed at frame {frame}")
+            }
+        }
+    }
+}
+
+impl From<SymphoniaError> for DecodeError {
+    fn from(e: SymphoniaError) -> Self {
+        DecodeError::Decoder(e)
+    }
+}
+
+impl From<Error> for DecodeError {
+    fn from(e: Error) -> Self {
+        DecodeError::Meter(e)
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        DecodeError::Decoder(SymphoniaError::IoError(e))
+    }
+}
+
+/// Seekable reader as symphonia media source
+struct ReaderSource<R> {
+    reader: R,
+    len: Option<u64>,
+}
+
+impl<R: Read + Seek> ReaderSource<R> {
+    fn new(mut reader: R) -> io::Result<Self> {
+        let pos = reader.stream_position()?;
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(pos))?;
+        Ok(Self {
+            reader,
+            len: Some(end),
+        })
+    }
+}
+
+impl<R: Read> Read for ReaderSource<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for ReaderSource<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+impl<R: Read + Seek + Send + Sync> MediaSource for ReaderSource<R> {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.len
+    }
+}
+
+/// Fill missing metadata from tags of revision
+fn read_tags(metadata: &mut TrackMetadata, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        let field = match tag.std_key {
+            Some(StandardTagKey::Artist) => &mut metadata.artist,
+            Some(StandardTagKey::TrackTitle) => &mut metadata.title,
+            Some(StandardTagKey::Album) => &mut metadata.album,
+            Some(StandardTagKey::AlbumArtist) => &mut metadata.album_artist,
+            Some(StandardTagKey::DiscNumber) => {
+                if metadata.disc.is_none() {
+                    metadata.disc = TrackMetadata::parse_disc(&tag.value.to_string());
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        if field.is_none() {
+            *field = Some(tag.value.to_string());
+        }
+    }
+}
+
+/// Metadata of source stream from codec parameters
+fn stream_metadata(params: &CodecParameters) -> TrackMetadata {
+    TrackMetadata {
+        codec: symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map(|codec| codec.short_name.to_uppercase()),
+        bit_depth: params.bits_per_sample,
+        sample_rate: params.sample_rate,
+        duration: params
+            .n_frames
+            .zip(params.sample_rate)
+            .map(|(frames, rate)| Duration::from_secs_f64(frames as f64 / rate as f64)),
+        ..Default::default()
+    }
+}
+
+/// Number of frames spanned by timestamp difference
+fn ts_frames(time_base: Option<TimeBase>, rate: u32, ts: u64) -> usize {
+    match time_base {
+        Some(time_base) => {
+            let time = time_base.calc_time(ts);
+            ((time.seconds as f64 + time.frac) * rate as f64).round() as usize
+        }
+        // timestamps in frames
+        None => ts as usize,
+    }
+}
+
+/// Decoder of first audio track of media source
+pub(crate) struct AudioStream {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    /// timestamp of first frame to return, frames before it are pre-roll of seek
+    start_ts: u64,
+    buf: Option<SampleBuffer<f64>>,
+    /// tags and stream parameters
+    pub metadata: TrackMetadata,
+}
+
+impl AudioStream {
+    /// Probe container of source and open its first audio track
+    pub fn open(source: Box<dyn MediaSource>, hint: &Hint) -> Result<Self, DecodeError> {
+        let stream = MediaSourceStream::new(source, Default::default());
+        let mut probed = symphonia::default::get_probe().format(
+            hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+        let mut format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or(DecodeError::NoTrack)?;
+        let track_id = track.id;
+        let time_base = track.codec_params.time_base;
+        let mut metadata = stream_metadata(&track.codec_params);
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        if let Some(revision) = format.metadata().current() {
+            read_tags(&mut metadata, revision);
+        }
+        if let Some(revision) = probed.metadata.get().as_ref().and_then(|m| m.current()) {
+            read_tags(&mut metadata, revision);
+        }
+
+        Ok(Self {
+            format,
+            decoder,
+            track_id,
+            time_base,
+            start_ts: 0,
+            buf: None,
+            metadata,
+        })
+    }
+
+    /// Seek to offset from start of stream
+    ///
+    /// Seeking is frame accurate: decoder is primed with packets
+    /// preceding the offset, but their frames are not returned.
+    pub fn seek(&mut self, offset: Duration) -> Result<(), DecodeError> {
+        let seeked = self.format.seek(
+            SeekMode::Accurate,
+            SeekTo::Time {
+                time: offset.into(),
+                track_id: Some(self.track_id),
+            },
+        )?;
+        self.decoder.reset();
+        self.start_ts = seeked.required_ts;
+        Ok(())
+    }
+
+    /// Decode next packet into interleaved samples
+    ///
+    /// Returns `None` at the end of stream.
+    pub fn next_frames(&mut self) -> Result<Option<(SignalSpec, &[f64])>, DecodeError> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(None)
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let pre_roll = self.start_ts.saturating_sub(packet.ts());
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // corrupted packet, skip it
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let spec = *decoded.spec();
+            let skip = ts_frames(self.time_base, spec.rate, pre_roll);
+            if pre_roll > 0 && skip >= decoded.frames() {
+                continue;
+            }
+            let samples = decoded.capacity() * spec.channels.count();
+            if self.buf.as_ref().is_none_or(|buf| buf.capacity() < samples) {
+                self.buf = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+            }
+            let buf = self.buf.as_mut().unwrap();
+            buf.copy_interleaved_ref(decoded);
+
+            let skip = skip * spec.channels.count();
+            return Ok(Some((spec, &buf.samples()[skip..])));
+        }
+    }
+}
+
+/// Decode first audio track of media source and measure it
+pub(crate) fn analyze_source(
+    source: Box<dyn MediaSource>,
+    hint: &Hint,
+    offset: Duration,
+) -> Result<DRResults, DecodeError> {
+    let mut stream = AudioStream::open(source, hint)?;
+    if !offset.is_zero() {
+        stream.seek(offset)?;
+    }
+    measure(stream)
+}
+
+/// Measure all remaining audio of stream
+pub(crate) fn measure(mut stream: AudioStream) -> Result<DRResults, DecodeError> {
+    let mut meter: Option<DRMeter> = None;
+    while let Some((spec, samples)) = stream.next_frames()? {
+        let meter = match &mut meter {
+            Some(meter) => meter,
+            None => meter.insert(DRMeter::new(spec.channels.count() as u32, spec.rate)?),
+        };
+        if spec.channels.count() != meter.channels() as usize || spec.rate != meter.rate() {
+            return Err(DecodeError::FormatChange {
+                frame: meter.frames(),
+            });
+        }
+        meter.add_frames_f64(samples)?;
+    }
+
+    let mut meter = meter.ok_or(DecodeError::NoTrack)?;
+    meter.finalize()?;
+    Ok(meter.results()?.with_metadata(stream.metadata))
+}
+
+/// Probe container from reader, decode its first audio track and measure it
+///
+/// Tags and stream parameters are attached to results as [`TrackMetadata`].
+pub fn analyze_reader<R>(reader: R) -> Result<DRResults, DecodeError>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    analyze_reader_from(reader, Duration::ZERO)
+}
+
+/// Same as [`analyze_reader`], but measures audio starting at offset
+///
+/// The stream is seeked instead of decoding the audio preceding the offset.
+pub fn analyze_reader_from<R>(reader: R, offset: Duration) -> Result<DRResults, DecodeError>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    analyze_source(Box::new(ReaderSource::new(reader)?), &Hint::new(), offset)
+}
+
+/// Open file, decode its first audio track and measure it
+///
+/// Extension of the path is used as hint when probing the container.
+pub fn analyze_file(path: impl AsRef<Path>) -> Result<DRResults, DecodeError> {
+    let path = path.as_ref();
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let file = File::open(path)?;
+    analyze_source(Box::new(ReaderSource::new(file)?), &hint, Duration::ZERO)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// 16-bit PCM WAV file
+    pub(crate) fn wav(channels: u16, rate: u32, samples: &[i16]) -> Vec<u8> {
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend(b"RIFF");
+        wav.extend((36 + data_len).to_le_bytes());
+        wav.extend(b"WAVEfmt ");
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(channels.to_le_bytes());
+        wav.extend(rate.to_le_bytes());
+        wav.extend((rate * u32::from(channels) * 2).to_le_bytes());
+        wav.extend((channels * 2).to_le_bytes());
+        wav.extend(16u16.to_le_bytes());
+        wav.extend(b"data");
+        wav.extend(data_len.to_le_bytes());
+        for s in samples {
+            wav.extend(s.to_le_bytes());
+        }
+        wav
+    }
+
+    #[test]
+    fn analyze_wav() {
+        let samples: Vec<i16> = (0..8000 * 2 * 6)
+            .map(|i| ((i * 37) % 20_000) as i16)
+            .collect();
+        let results = analyze_reader(Cursor::new(wav(2, 8000, &samples))).unwrap();
+
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        dr.add_frames_i16(&samples).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(results.exact_dr(), dr.exact_dr().unwrap());
+        assert_eq!(results.frames(), 8000 * 6);
+        assert_eq!(results.metadata().sample_rate, Some(8000));
+        assert_eq!(results.metadata().bit_depth, Some(16));
+    }
+
+    #[test]
+    fn offset() {
+        let samples: Vec<i16> = (0..8000 * 2 * 6)
+            .map(|i| ((i * 37) % (1000 + i / 100)) as i16)
+            .collect();
+        let offset = 8000 * 2 * 2 + 2 * 1234;
+        let results = analyze_reader_from(
+            Cursor::new(wav(2, 8000, &samples)),
+            Duration::from_secs_f64(2.0 + 1234.0 / 8000.0),
+        )
+        .unwrap();
+
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        dr.add_frames_i16(&samples[offset..]).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(results.frames(), dr.frames());
+        assert_eq!(results.exact_dr(), dr.exact_dr().unwrap());
+    }
+
+    #[test]
+    fn file() {
+        let samples: Vec<i16> = (0..8000 * 4).map(|i| ((i * 91) % 12_000) as i16).collect();
+        let path = std::env::temp_dir().join(format!("drmeter-{}.wav", std::process::id()));
+        std::fs::write(&path, wav(1, 8000, &samples)).unwrap();
+        let results = analyze_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut dr = DRMeter::new(1, 8000).unwrap();
+        dr.add_frames_i16(&samples).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(results.unwrap().exact_dr(), dr.exact_dr().unwrap());
+        assert!(matches!(
+            analyze_file(path),
+            Err(DecodeError::Decoder(SymphoniaError::IoError(_)))
+        ));
+    }
+
+    #[test]
+    fn not_audio() {
+        assert!(analyze_reader(Cursor::new(vec![0u8; 1024])).is_err());
+    }
+
+    #[test]
+    fn album_tags() {
+        use symphonia::core::meta::{MetadataBuilder, Tag, Value};
+
+        let tag = |key, name, value: &str| Tag::new(Some(key), name, Value::from(value));
+        let mut builder = MetadataBuilder::new();
+        builder
+            .add_tag(tag(StandardTagKey::Album, "ALBUM", "Album"))
+            .add_tag(tag(StandardTagKey::AlbumArtist, "ALBUMARTIST", "Various"))
+            .add_tag(tag(StandardTagKey::DiscNumber, "DISCNUMBER", "2/3"));
+        let mut metadata = TrackMetadata::default();
+        read_tags(&mut metadata, &builder.metadata());
+        assert_eq!(metadata.album.as_deref(), Some("Album"));
+        assert_eq!(metadata.album_artist.as_deref(), Some("Various"));
+        assert_eq!(metadata.disc, Some(2));
+    }
+}
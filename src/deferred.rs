@@ -0,0 +1,176 @@
+//! Meter taking its channel count from the first planar input
+
+use crate::utils::Sample;
+use crate::{ConfigOption, DRMeter, DRResults, Error};
+
+type Configure = dyn FnOnce(&mut DRMeter) -> Result<(), Error> + Send;
+
+/// Meter whose number of channels is taken from the first planar input
+///
+/// Useful when the channel layout is not known until the first frames
+/// are decoded. The meter is created on the first add of frames,
+/// later adds must have the same number of planes.
+pub struct DeferredMeter {
+    rate: u32,
+    /// window length in ms
+    window: usize,
+    /// configuration applied to meter once it is created
+    configure: Option<Box<Configure>>,
+    meter: Option<DRMeter>,
+}
+
+impl std::fmt::Debug for DeferredMeter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeferredMeter")
+            .field("rate", &self.rate)
+            .field("window", &self.window)
+            .field("configured", &self.configure.is_some())
+            .field("meter", &self.meter)
+            .finish()
+    }
+}
+
+impl DeferredMeter {
+    /// Create a new instance with default window of 3s.
+    pub fn new(rate: u32) -> Self {
+        Self::new_with_window(rate, 3000)
+    }
+
+    /// Create a new instance with the given window.
+    ///
+    /// Rate and window are validated once the meter is created.
+    pub fn new_with_window(rate: u32, window: usize) -> Self {
+        Self {
+            rate,
+            window,
+            configure: None,
+            meter: None,
+        }
+    }
+
+    /// Configure the meter once it is created, before frames are added to it.
+    pub fn with_config(
+        mut self,
+        configure: impl FnOnce(&mut DRMeter) -> Result<(), Error> + Send + 'static,
+    ) -> Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// Returns the number of channels, if already known.
+    pub fn channels(&self) -> Option<u32> {
+        self.meter.as_ref().map(DRMeter::channels)
+    }
+
+    /// Returns the meter, if frames were already added.
+    pub const fn meter(&self) -> Option<&DRMeter> {
+        self.meter.as_ref()
+    }
+
+    /// Returns the meter, if frames were already added.
+    pub fn into_meter(self) -> Option<DRMeter> {
+        self.meter
+    }
+
+    /// Returns the meter, creating it with channels of input if needed.
+    fn meter_for<T>(&mut self, frames: &[&[T]]) -> Result<&mut DRMeter, Error> {
+        if self.meter.is_none() {
+            let channels = u32::try_from(frames.len()).map_err(|_| Error::InvalidConfig {
+                option: ConfigOption::Channels,
+            })?;
+            let mut meter = DRMeter::new_with_window(channels, self.rate, self.window)?;
+            if let Some(configure) = self.configure.take() {
+                configure(&mut meter)?;
+            }
+            self.meter = Some(meter);
+        }
+        Ok(self.meter.as_mut().expect("meter was created"))
+    }
+
+    fn add_planar<T: Sample>(&mut self, frames: &[&[T]]) -> Result<(), Error> {
+        self.meter_for(frames)?.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Finalize the meter (marking end of stream).
+    ///
+    /// Returns [`Error::NotFinalized`] if no frames were added,
+    /// as the number of channels is not known.
+    pub fn finalize(&mut self) -> Result<(), Error> {
+        self.meter.as_mut().ok_or(Error::NotFinalized)?.finalize()
+    }
+
+    /// Returns results of finalized meter.
+    pub fn results(&self) -> Result<DRResults, Error> {
+        self.meter.as_ref().ok_or(Error::NotFinalized)?.results()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Weighting;
+
+    #[test]
+    fn channels_from_planes() {
+        let left: Vec<f32> = (0..48_000 * 4).map(|i| f32::sin(i as f32 * 0.01)).collect();
+        let right: Vec<f32> = left.iter().map(|s| s * 0.25).collect();
+
+        let mut deferred =
+            DeferredMeter::new(48_000).with_config(|meter| meter.set_weighting(Weighting::A));
+        assert_eq!(deferred.channels(), None);
+        assert_eq!(deferred.finalize(), Err(Error::NotFinalized));
+        deferred
+            .add_frames_planar_f32(&[&left[..1000], &right[..1000]])
+            .unwrap();
+        assert_eq!(deferred.channels(), Some(2));
+        assert_eq!(
+            deferred.add_frames_planar_f32(&[&left[1000..]]),
+            Err(Error::InvalidInput { frame: 1000 })
+        );
+        deferred
+            .add_frames_planar_f32(&[&left[1000..], &right[1000..]])
+            .unwrap();
+        deferred.finalize().unwrap();
+
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.set_weighting(Weighting::A).unwrap();
+        dr.add_frames_planar_f32(&[&left, &right]).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(
+            deferred.results().unwrap().exact_dr(),
+            dr.exact_dr().unwrap()
+        );
+    }
+
+    #[test]
+    fn no_planes() {
+        let mut deferred = DeferredMeter::new(48_000);
+        assert_eq!(
+            deferred.add_frames_planar_i16(&[]),
+            Err(Error::InvalidConfig {
+                option: ConfigOption::Channels
+            })
+        );
+        assert!(deferred.meter().is_none());
+    }
+}
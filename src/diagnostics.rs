@@ -0,0 +1,50 @@
+//! Dump of internal [`DRMeter`](crate::DRMeter) state
+//!
+//! Useful for debugging discrepancies against other DR tools.
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+use crate::HistogramMode;
+
+/// Partial block that is still being filled
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct PartialBlock {
+    /// Number of consumed frames
+    pub consumed_frames: usize,
+    /// Number of frames needed to finish the block
+    pub needed_frames: usize,
+    /// Sample peak per channel (linear)
+    pub sample_peak: Vec<f64>,
+    /// Sum of squared samples per channel
+    pub sum2: Vec<f64>,
+}
+
+/// Internal state of a [`DRMeter`](crate::DRMeter)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Diagnostics {
+    /// The sample rate
+    pub rate: u32,
+    /// The number of channels
+    pub channels: u32,
+    /// Window length in ms
+    pub window: usize,
+    /// Storage used for histograms
+    pub histogram_mode: HistogramMode,
+    /// Headroom above 0 dBFS for peaks (in dB)
+    pub peak_headroom: f64,
+    /// Number of bins that represent full scale
+    pub bins: usize,
+    /// Number of finished blocks
+    pub block_number: usize,
+    /// Block that is being filled
+    pub partial_block: PartialBlock,
+    /// Populated peak bins per channel as `(bin, count)`
+    pub peak_bins: Vec<Vec<(usize, u32)>>,
+    /// Populated RMS bins per channel as `(bin, count)`
+    pub rms_bins: Vec<Vec<(usize, u32)>>,
+    /// Cached exact channel DR, set when instance is finalized
+    pub channel_dr: Option<Vec<f64>>,
+}
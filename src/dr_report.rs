@@ -0,0 +1,157 @@
+//! Plain summary of a finished measurement
+
+use std::fmt;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{DRMeter, DRResults, DRScore, Error};
+
+/// Levels and DR of one channel of [`DrReport`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChannelReport {
+    /// Highest block peak (linear)
+    pub peak: f64,
+    /// Second highest block peak (linear), which is used for DR
+    pub second_peak: f64,
+    /// Top 20% RMS (linear)
+    pub rms: f64,
+    /// Exact DR
+    pub dr: f64,
+}
+
+impl ChannelReport {
+    /// Returns DR score of channel
+    pub fn score(&self) -> DRScore {
+        DRScore::from_exact(self.dr)
+    }
+}
+
+/// Formats as `DR12 (12.34)`
+impl fmt::Display for ChannelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.2})", self.score(), self.dr)
+    }
+}
+
+/// Scores and levels of a finalized measurement
+///
+/// Unlike [`DRResults`] it holds no histograms, just a few values per channel.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DrReport {
+    /// Sample rate
+    pub rate: u32,
+    /// Duration of measured audio
+    pub duration: Duration,
+    /// Levels and DR per channel
+    pub channels: Vec<ChannelReport>,
+    /// Exact DR (average of channel DR)
+    pub dr: f64,
+}
+
+impl DrReport {
+    /// Returns DR score
+    pub fn score(&self) -> DRScore {
+        DRScore::from_exact(self.dr)
+    }
+}
+
+/// Formats as `DR12 (12.34)`
+impl fmt::Display for DrReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:.2})", self.score(), self.dr)
+    }
+}
+
+impl From<&DRResults> for DrReport {
+    fn from(results: &DRResults) -> Self {
+        Self {
+            rate: results.rate,
+            duration: results.duration(),
+            channels: results
+                .channels
+                .iter()
+                .map(|ch| ChannelReport {
+                    peak: ch.first_peak,
+                    second_peak: ch.second_peak,
+                    rms: ch.rms,
+                    dr: ch.dr,
+                })
+                .collect(),
+            dr: results.exact_dr(),
+        }
+    }
+}
+
+impl DRMeter {
+    /// Return report of finalized instance
+    pub fn report(&self) -> Result<DrReport, Error> {
+        Ok(DrReport::from(&self.results()?))
+    }
+
+    /// Finalize instance (unless already finalized) and return its report.
+    ///
+    /// The meter with its histograms is dropped right away, so only
+    /// the report is kept when scanning many files.
+    pub fn into_report(mut self) -> Result<DrReport, Error> {
+        if !self.finalized() {
+            self.finalize()?;
+        }
+        self.report()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let data: Vec<f32> = (0..48_000 * 9)
+            .map(|i| f32::sin(i as f32 * 0.01) * (1 + i / 48_000 % 4) as f32 / 4.0)
+            .flat_map(|s| [s, s / 2.0])
+            .collect();
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        assert_eq!(dr.report(), Err(Error::NotFinalized));
+        dr.finalize().unwrap();
+
+        let report = dr.report().unwrap();
+        assert_eq!(report.rate, 48_000);
+        assert_eq!(report.duration, Duration::from_secs(9));
+        assert_eq!(report.channels.len(), 2);
+        assert_eq!(report.channels[1].peak, dr.first_peak(1).unwrap());
+        assert_eq!(report.score(), dr.dr_score().unwrap());
+        assert_eq!(
+            report.to_string(),
+            format!("{} ({:.2})", dr.dr_score().unwrap(), dr.exact_dr().unwrap())
+        );
+        assert_eq!(
+            report.channels[0].to_string(),
+            format!(
+                "{} ({:.2})",
+                dr.channel_dr_score(0).unwrap(),
+                dr.exact_channel_dr(0).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn into_report() {
+        let data: Vec<f32> = (0..48_000 * 7)
+            .map(|i| f32::sin(i as f32 * 0.02) * (1 + i / 20_000 % 3) as f32 / 3.0)
+            .collect();
+        let mut finalized = DRMeter::new(1, 48_000).unwrap();
+        finalized.add_frames_f32(&data).unwrap();
+        let mut open = DRMeter::new(1, 48_000).unwrap();
+        open.add_frames_f32(&data).unwrap();
+        finalized.finalize().unwrap();
+
+        let report = finalized.report().unwrap();
+        assert_eq!(open.into_report(), Ok(report.clone()));
+        assert_eq!(finalized.into_report(), Ok(report));
+    }
+}
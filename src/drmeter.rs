@@ -1,23 +1,504 @@
 use std::fmt;
+use std::io;
+use std::ops::Range;
 
-use crate::block::Block;
-use crate::utils::{decibel, sqr, Interleaved, Planar, Sample, Samples};
-use crate::Error;
+use crate::block::{hann, Block};
+use crate::channel_pairs::ChannelPairs;
+use crate::histogram::{Histogram, HistogramMode, Quantization};
+use crate::math;
+use crate::overlap::Overlap;
+use crate::peak_hold::{PeakHold, PeakLevel};
+use crate::results::{BlockResult, ChannelResults, DRResults, WorstBlock};
+use crate::true_peak::Oversampler;
+use crate::units::{amplitude_to_dbfs, decibel, from_decibel};
+use crate::utils::{sqr, Interleaved, Planar, Remapped, Sample, Samples};
+use crate::weighting::{Weighting, WeightingFilter};
+use crate::{Aggregation, ConfigOption, DRAlbum, DRScore, Error};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-/// upper 20% histogram values
-const LOUD_FRACTION: f64 = 0.2;
+/// Default fraction of loudest blocks in RMS (upper 20% histogram values)
+pub const LOUD_FRACTION: f64 = 0.2;
 /// How many bins there are (2¹⁵)
 const BINS: usize = 32768;
 //const BINS: usize = 10_000;
 const MAX_RATE: u32 = 2_822_400;
 const MAX_CHANNELS: u32 = 64;
+/// Max headroom above full scale for peaks (in dB)
+const MAX_PEAK_HEADROOM: f64 = 20.0;
+
+/// Handling of non-finite (`NaN` or infinite) float samples
+///
+/// A single non-finite sample would otherwise spoil RMS of the whole block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NonFinitePolicy {
+    /// Reject the frames with [`Error::NonFinite`]
+    #[default]
+    Error,
+    /// Measure non-finite samples as silence
+    Zero,
+    /// Measure infinities as full scale and `NaN` as silence
+    Clamp,
+}
+
+/// Window function applied to energy of every block
+///
+/// Official DR uses rectangular window, tapered windows are meant
+/// for replicating studies that specify them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WindowFunction {
+    /// Every frame of block has the same weight
+    #[default]
+    Rectangular,
+    /// Hann window spanning the whole block
+    Hann,
+}
+
+/// Peak measured in every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PeakMode {
+    /// Highest sample (official)
+    #[default]
+    Sample,
+    /// Highest sample, and highest value of signal oversampled
+    /// as in ITU-R BS.1770 (dBTP) alongside it
+    ///
+    /// Sample peaks understate peaks between samples. DR is still computed
+    /// from sample peaks, true peaks are returned by [`DRMeter::true_peak`].
+    True,
+}
+
+/// Peak used as numerator of DR formula
+///
+/// DR is the ratio of second highest block peak to top 20% RMS,
+/// this selects which of the measured block peaks is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DrNumerator {
+    /// Sample peak of block (official)
+    #[default]
+    SamplePeak,
+    /// True peak of block, needs [`PeakMode::True`]
+    ///
+    /// True peaks can exceed full scale, so set peak headroom
+    /// to keep them from being clamped in the histogram.
+    TruePeak,
+}
+
+impl DrNumerator {
+    /// Returns name of numerator
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::SamplePeak => "sample_peak",
+            Self::TruePeak => "true_peak",
+        }
+    }
+}
+
+/// Handling of unfinished block at a discontinuity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PartialBlockPolicy {
+    /// Measure frames of unfinished block as a short block
+    #[default]
+    Finalize,
+    /// Drop frames of unfinished block
+    Discard,
+}
+
+/// Selection of blocks that make top 20% RMS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Percentile {
+    /// Whole bins are summed until top 20% of blocks is reached (official)
+    #[default]
+    WholeBins,
+    /// Blocks of cutoff bin are counted only partially, so exactly
+    /// top 20% of blocks is summed
+    ///
+    /// This reduces quantization bias of short programs with few blocks.
+    Interpolated,
+}
+
+/// Raw values of every block of channel, kept in exact mode
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct BlockValues {
+    /// position of block start (in frames)
+    starts: Vec<u64>,
+    peaks: Vec<f64>,
+    rms: Vec<f64>,
+}
+
+impl BlockValues {
+    /// DR of range of blocks
+    fn dr(&self, blocks: Range<usize>, fraction: f64) -> f64 {
+        let peaks = sorted_descending(&self.peaks[blocks.clone()]);
+        let rms = sorted_descending(&self.rms[blocks.clone()]);
+        let (rms_sum, _) = loud_rms_sum_exact(&rms, blocks.len(), fraction);
+        let loud_rms = math::sqrt(rms_sum / (fraction * blocks.len() as f64));
+        decibel(peaks.get(1).copied().unwrap_or(0.0) / loud_rms)
+    }
+}
+
+/// Fraction of loudest blocks must select some blocks, but at most all of them
+fn valid_loud_fraction(fraction: f64) -> bool {
+    fraction > 0.0 && fraction <= 1.0
+}
+
+/// Values in descending order
+fn sorted_descending(values: &[f64]) -> Vec<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable_by(|a, b| b.total_cmp(a));
+    sorted
+}
+
+/// Sub-meter of channel subset (stem) of the input
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Stem {
+    name: String,
+    /// input channels of stem
+    channels: Box<[usize]>,
+    meter: DRMeter,
+}
+
+/// Blocks that contributed to top 20% RMS of channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LoudBlocks {
+    /// Number of blocks that make top 20% of all blocks
+    pub target: u32,
+    /// Number of blocks that were summed
+    ///
+    /// Whole bins are summed, so this can be above target.
+    pub count: u32,
+    /// Lowest RMS bin that was summed
+    pub cutoff_bin: usize,
+    /// RMS of lowest bin that was summed (linear)
+    pub cutoff_rms: f64,
+}
+
+/// Index of first frame with non-finite sample
+pub(crate) fn first_non_finite<'a, T: Sample + 'a, S: Samples<'a, T>>(src: &S) -> Option<usize> {
+    if T::FINITE {
+        return None;
+    }
+
+    let mut first = None;
+    for ch in 0..src.channels() {
+        src.foreach_sample_zipped(ch, 0..first.unwrap_or(usize::MAX), |s, i| {
+            if first.is_none() && !s.is_finite() {
+                first = Some(i);
+            }
+        });
+    }
+    first
+}
+
+/// Copy of samples as planes with non-finite samples replaced as per policy
+fn sanitized<'a, T: Sample + 'a, S: Samples<'a, T>>(
+    src: &S,
+    policy: NonFinitePolicy,
+) -> Vec<Vec<T>> {
+    (0..src.channels())
+        .map(|ch| {
+            let mut plane = Vec::with_capacity(src.frames());
+            src.foreach_sample(ch, |&s| {
+                plane.push(match (s.is_finite(), policy) {
+                    (true, _) => s,
+                    (false, NonFinitePolicy::Clamp) if !s.to_sample::<f64>().is_nan() => {
+                        T::from_sample(s.to_sample::<f64>().signum())
+                    }
+                    (false, _) => T::EQUILIBRIUM,
+                })
+            });
+            plane
+        })
+        .collect()
+}
+
+/// Sum squared RMS of `fraction` of loudest blocks
+///
+/// `populated` are populated RMS bins in descending order,
+/// with `bins` bins per full scale.
+pub(crate) fn loud_rms_sum(
+    populated: impl Iterator<Item = (usize, u32)>,
+    block_number: usize,
+    bins: usize,
+    fraction: f64,
+) -> (f64, LoudBlocks) {
+    let mut j: u32 = 0;
+    let n = (fraction * block_number as f64) as u32;
+    let mut rms_sum = 0.0;
+    let mut cutoff_bin = 0;
+    for (i, rms) in populated {
+        rms_sum += sqr(i as f64 / bins as f64);
+        j += rms;
+        cutoff_bin = i;
+
+        if j > n {
+            break;
+        }
+    }
+
+    (
+        rms_sum,
+        LoudBlocks {
+            target: n,
+            count: j,
+            cutoff_bin,
+            cutoff_rms: cutoff_bin as f64 / bins as f64,
+        },
+    )
+}
+
+/// Sum squared RMS of exactly `fraction` of loudest blocks from raw block values
+///
+/// `rms` are block values in descending order.
+/// The last summed block is counted only partially.
+fn loud_rms_sum_exact(rms: &[f64], block_number: usize, fraction: f64) -> (f64, LoudBlocks) {
+    let target = fraction * block_number as f64;
+    let mut remaining = target;
+    let mut rms_sum = 0.0;
+    let mut count = 0;
+    let mut cutoff_rms = 0.0;
+    for &value in rms {
+        if remaining <= 0.0 {
+            break;
+        }
+        let weight = remaining.min(1.0);
+        rms_sum += weight * sqr(value);
+        remaining -= weight;
+        count += 1;
+        cutoff_rms = value;
+    }
+
+    (
+        rms_sum,
+        LoudBlocks {
+            target: target as u32,
+            count,
+            cutoff_bin: math::round(cutoff_rms * BINS as f64) as usize,
+            cutoff_rms,
+        },
+    )
+}
+
+/// Sum squared RMS of exactly `fraction` of loudest blocks
+///
+/// `populated` are populated RMS bins in descending order,
+/// with `bins` bins per full scale.
+/// Blocks of cutoff bin are counted only partially.
+pub(crate) fn loud_rms_sum_interpolated(
+    populated: impl Iterator<Item = (usize, u32)>,
+    block_number: usize,
+    bins: usize,
+    fraction: f64,
+) -> f64 {
+    let mut remaining = fraction * block_number as f64;
+    let mut rms_sum = 0.0;
+    for (i, count) in populated {
+        let weight = (count as f64).min(remaining);
+        rms_sum += weight * sqr(i as f64 / bins as f64);
+        remaining -= weight;
+
+        if remaining <= 0.0 {
+            break;
+        }
+    }
+
+    rms_sum
+}
 
 // There are apparently two possibilities for implementation
 // one is like in ffmpeg where we do not know full number of blocks
 // when starting as we are streaming data and other is like
 // [DeaDBeeF DR Meter](https://github.com/dakeryas/deadbeef-dr-meter)
-// which does know final number of blocks, but we do not
+// which does know final number of blocks. We stream by default,
+// but exact duration can be declared (see `set_exact_duration`).
+/// Validated configuration of [`DRMeter`]
+///
+/// Channels and rate are required, other options have the defaults
+/// of [`DRMeter::new`]. Invalid options are reported by [`build`](Self::build)
+/// as [`Error::InvalidConfig`] naming the option.
+#[derive(Debug, Clone, Default)]
+pub struct DRMeterBuilder {
+    channels: Option<u32>,
+    rate: Option<u32>,
+    window: Option<usize>,
+    peak_mode: PeakMode,
+    dr_numerator: DrNumerator,
+    peak_headroom: f64,
+    weighting: Weighting,
+    window_function: WindowFunction,
+    histogram_mode: HistogramMode,
+    quantization: Quantization,
+    percentile: Percentile,
+    loud_fraction: Option<f64>,
+    non_finite_policy: NonFinitePolicy,
+    exact_mode: bool,
+    hop: Option<usize>,
+}
+
+impl DRMeterBuilder {
+    /// Create a new builder with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set number of channels (1 to 64).
+    pub const fn channels(mut self, channels: u32) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Set sample rate (16 Hz to 2_822_400 Hz).
+    pub const fn rate(mut self, rate: u32) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Set window in ms (at least 10 ms, default 3000 ms).
+    pub const fn window(mut self, window: usize) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    /// Set peak measured in blocks.
+    pub const fn peak_mode(mut self, mode: PeakMode) -> Self {
+        self.peak_mode = mode;
+        self
+    }
+
+    /// Set peak used in DR formula, true peak needs [`PeakMode::True`].
+    pub const fn dr_numerator(mut self, numerator: DrNumerator) -> Self {
+        self.dr_numerator = numerator;
+        self
+    }
+
+    /// Set headroom above full scale for peaks (0 to 20 dB).
+    pub const fn peak_headroom(mut self, headroom: f64) -> Self {
+        self.peak_headroom = headroom;
+        self
+    }
+
+    /// Set frequency weighting of RMS path.
+    pub const fn weighting(mut self, weighting: Weighting) -> Self {
+        self.weighting = weighting;
+        self
+    }
+
+    /// Set window function of block energy.
+    pub const fn window_function(mut self, function: WindowFunction) -> Self {
+        self.window_function = function;
+        self
+    }
+
+    /// Set storage used for histograms.
+    pub const fn histogram_mode(mut self, mode: HistogramMode) -> Self {
+        self.histogram_mode = mode;
+        self
+    }
+
+    /// Set rule of RMS binning.
+    pub const fn quantization(mut self, quantization: Quantization) -> Self {
+        self.quantization = quantization;
+        self
+    }
+
+    /// Set selection of top 20% RMS blocks.
+    pub const fn percentile(mut self, percentile: Percentile) -> Self {
+        self.percentile = percentile;
+        self
+    }
+
+    /// Set fraction of loudest blocks in RMS (above 0, at most 1, default 0.2).
+    pub const fn loud_fraction(mut self, fraction: f64) -> Self {
+        self.loud_fraction = Some(fraction);
+        self
+    }
+
+    /// Set handling of non-finite samples.
+    pub const fn non_finite_policy(mut self, policy: NonFinitePolicy) -> Self {
+        self.non_finite_policy = policy;
+        self
+    }
+
+    /// Set keeping of raw block values instead of only histograms.
+    pub const fn exact_mode(mut self, enabled: bool) -> Self {
+        self.exact_mode = enabled;
+        self
+    }
+
+    /// Set hop of overlapping blocks in ms.
+    pub const fn hop(mut self, hop: usize) -> Self {
+        self.hop = Some(hop);
+        self
+    }
+
+    /// Validate configuration and create the meter.
+    pub fn build(&self) -> Result<DRMeter, Error> {
+        let invalid = |option| Error::InvalidConfig { option };
+
+        let channels = self
+            .channels
+            .filter(|channels| (1..=MAX_CHANNELS).contains(channels))
+            .ok_or(invalid(ConfigOption::Channels))?;
+        let rate = self
+            .rate
+            .filter(|rate| (16..=MAX_RATE).contains(rate))
+            .ok_or(invalid(ConfigOption::Rate))?;
+        let window = self.window.unwrap_or(3000);
+        // block must have at least one frame
+        if window < 10 || (rate as usize).checked_mul(window).is_none_or(|n| n < 1000) {
+            return Err(invalid(ConfigOption::Window));
+        }
+        if !(0.0..=MAX_PEAK_HEADROOM).contains(&self.peak_headroom) {
+            return Err(invalid(ConfigOption::PeakHeadroom));
+        }
+        if self.dr_numerator == DrNumerator::TruePeak && self.peak_mode != PeakMode::True {
+            return Err(invalid(ConfigOption::DrNumerator));
+        }
+        let loud_fraction = self.loud_fraction.unwrap_or(LOUD_FRACTION);
+        if !valid_loud_fraction(loud_fraction) {
+            return Err(invalid(ConfigOption::LoudFraction));
+        }
+        if let Some(hop) = self.hop {
+            if self.window_function != WindowFunction::Rectangular
+                || Overlap::new(hop, window, rate).is_none()
+            {
+                return Err(invalid(ConfigOption::Hop));
+            }
+        }
+
+        let mut meter = DRMeter::allocate(channels, rate, window)?;
+        meter.set_histogram_mode(self.histogram_mode)?;
+        meter.set_quantization(self.quantization)?;
+        meter.set_percentile(self.percentile)?;
+        meter.set_loud_fraction(loud_fraction)?;
+        meter.set_peak_headroom(self.peak_headroom)?;
+        meter.set_peak_mode(self.peak_mode)?;
+        meter.set_dr_numerator(self.dr_numerator)?;
+        meter.set_weighting(self.weighting)?;
+        meter.set_window_function(self.window_function)?;
+        meter.set_non_finite_policy(self.non_finite_policy);
+        meter.set_exact_mode(self.exact_mode)?;
+        meter.set_hop(self.hop)?;
+        Ok(meter)
+    }
+}
+
+type BlockCallback = dyn FnMut(BlockResult) + Send;
+
 /// DR Meter instance
+///
+/// With `serde` feature the whole state can be serialized,
+/// so a long analysis can be checkpointed and resumed later.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DRMeter {
     /* user passed options */
     /// The sample rate.
@@ -46,11 +527,118 @@ pub struct DRMeter {
     /// number of blocks that are scanned
     block_number: usize,
 
+    /// number of frames that were added
+    frames: u64,
+
+    /// position (in frames) where unfinished block (or hop) starts
+    ///
+    /// Blocks cut short at boundaries and dropped frames move it,
+    /// so it cannot be derived from the number of blocks.
+    block_start: u64,
+
+    /// total number of frames declared by caller
+    expected_frames: Option<u64>,
+
+    /// number of frames after which frames are not accepted
+    max_frames: Option<u64>,
+
+    /// total number of blocks, if exact duration was declared
+    known_blocks: Option<usize>,
+
+    /// peak hold for live meters
+    peak_hold: Option<PeakHold>,
+
+    /// Energy and correlation of channel pairs
+    ///
+    /// Tracked only when dual-mono or polarity detection is enabled.
+    channel_pairs: Option<ChannelPairs>,
+
+    /// detection of identical channels
+    dual_mono_detection: bool,
+
+    /// detection of polarity inverted channels
+    polarity_detection: bool,
+
+    /// Handling of non-finite samples
+    non_finite_policy: NonFinitePolicy,
+
+    /// Frequency weighting of RMS path
+    weighting: Weighting,
+
+    /// Window function of block energy
+    window_function: WindowFunction,
+
+    /// Position (in frames) of first non-finite sample
+    first_non_finite: Option<u64>,
+
+    /// Positions (in frames) of marked discontinuities
+    discontinuities: Vec<u64>,
+
+    /// Storage used for histograms
+    histogram_mode: HistogramMode,
+
+    /// Rule of RMS binning
+    quantization: Quantization,
+
+    /// Selection of top 20% RMS blocks
+    percentile: Percentile,
+
+    /// Fraction of loudest blocks in RMS
+    loud_fraction: f64,
+
+    /// Input channel of every meter channel
+    channel_map: Option<Box<[usize]>>,
+
+    /// Sub-meters of channel subsets
+    stems: Vec<Stem>,
+
+    /// Headroom above 0 dBFS for peaks (in dB)
+    peak_headroom: f64,
+
+    /// Peak measured in blocks
+    peak_mode: PeakMode,
+
+    /// Highest true peak per channel, if measured
+    true_peaks: Option<Box<[f64]>>,
+
+    /// Peak used in DR formula
+    dr_numerator: DrNumerator,
+
+    /// Highest peak bin, peaks above it are clamped into it
+    ///
+    /// Without headroom this equals `BINS` (full scale).
+    peak_bins: usize,
+
     /// Peak bins per channel
-    peaks: Box<[Box<[u32]>]>,
+    peaks: Box<[Histogram]>,
 
     /// RMS bins per channel
-    rms: Box<[Box<[u32]>]>,
+    rms: Box<[Histogram]>,
+
+    /// Block with the lowest crest factor per channel
+    worst_blocks: Box<[Option<WorstBlock>]>,
+
+    /// Levels of last finished block
+    last_block: Option<BlockResult>,
+
+    /// Raw values of every block per channel, if exact mode is enabled
+    block_values: Option<Box<[BlockValues]>>,
+
+    /// Last hops, if blocks overlap
+    overlap: Option<Overlap>,
+
+    /// Positions (in frames from start) of marked track boundaries
+    track_boundaries: Vec<u64>,
+
+    /// Blocks of track in progress, once a track boundary was marked
+    track: Option<Box<DRMeter>>,
+
+    /// Results of finished tracks
+    tracks: Vec<DRResults>,
+
+    /// Observer of finished blocks
+    #[cfg_attr(feature = "serde", serde(skip))]
+    block_callback: Option<Box<BlockCallback>>,
 
     /// cached exact dr scores per channel
     /// that are generated when the instance is finalized
@@ -58,6 +646,10 @@ pub struct DRMeter {
     /// Other values are pretty easy to calculate from these,
     /// so these ones are the only we cache
     channel_dr: Option<Box<[f64]>>,
+
+    /// Time spent in conversion and finalize phases
+    #[cfg(feature = "profiling")]
+    profile: crate::Profile,
 }
 
 impl fmt::Debug for DRMeter {
@@ -69,6 +661,38 @@ impl fmt::Debug for DRMeter {
             .field("needed_frames", &self.needed_frames)
             .field("block", &self.block)
             .field("block_number", &self.block_number)
+            .field("frames", &self.frames)
+            .field("block_start", &self.block_start)
+            .field("expected_frames", &self.expected_frames)
+            .field("max_frames", &self.max_frames)
+            .field("known_blocks", &self.known_blocks)
+            .field("peak_hold", &self.peak_hold)
+            .field("channel_pairs", &self.channel_pairs)
+            .field("dual_mono_detection", &self.dual_mono_detection)
+            .field("polarity_detection", &self.polarity_detection)
+            .field("non_finite_policy", &self.non_finite_policy)
+            .field("weighting", &self.weighting)
+            .field("window_function", &self.window_function)
+            .field("first_non_finite", &self.first_non_finite)
+            .field("discontinuities", &self.discontinuities)
+            .field("histogram_mode", &self.histogram_mode)
+            .field("quantization", &self.quantization)
+            .field("percentile", &self.percentile)
+            .field("loud_fraction", &self.loud_fraction)
+            .field("channel_map", &self.channel_map)
+            .field("stems", &self.stems)
+            .field("peak_headroom", &self.peak_headroom)
+            .field("peak_mode", &self.peak_mode)
+            .field("true_peaks", &self.true_peaks)
+            .field("dr_numerator", &self.dr_numerator)
+            .field("worst_blocks", &self.worst_blocks)
+            .field("last_block", &self.last_block)
+            .field("block_values", &self.block_values)
+            .field("overlap", &self.overlap)
+            .field("track_boundaries", &self.track_boundaries)
+            .field("track", &self.track)
+            .field("tracks", &self.tracks)
+            .field("block_callback", &self.block_callback.is_some())
             .field("channel_dr", &self.channel_dr)
             .finish()
     }
@@ -77,10 +701,16 @@ impl fmt::Debug for DRMeter {
 impl DRMeter {
     /// Allocate audio data buffer used by the filter and check if we can allocate enough memory
     /// for it.
-    fn allocate_bin(channels: usize) -> Result<Box<[Box<[u32]>]>, Error> {
-        let _total_mem = (BINS + 1).checked_mul(channels).ok_or(Error::NoMem)?;
+    ///
+    /// Sparse histograms start empty and grow as bins get populated.
+    fn allocate_bin(
+        channels: usize,
+        mode: HistogramMode,
+        bins: usize,
+    ) -> Result<Box<[Histogram]>, Error> {
+        let _total_mem = (bins + 1).checked_mul(channels).ok_or(Error::NoMem)?;
 
-        Ok(vec![vec![0; BINS + 1].into_boxed_slice(); channels].into_boxed_slice())
+        Ok(vec![Histogram::new(mode, bins + 1); channels].into_boxed_slice())
     }
 
     /// Check channel number (index)
@@ -100,36 +730,74 @@ impl DRMeter {
 
     /// Create a new instance with the given configuration.
     ///
-    /// Max channels is 64, rate limit is 2_822_400 and min window is 10 (ms)
+    /// Max channels is 64, rate limit is 2_822_400 and min window is 10 (ms),
+    /// see [`DRMeterBuilder`] for other options.
     pub fn new_with_window(channels: u32, rate: u32, window: usize) -> Result<Self, Error> {
-        if channels == 0 || channels > MAX_CHANNELS {
-            return Err(Error::ArgOutside);
-        }
-
-        if !(16..=MAX_RATE).contains(&rate) {
-            return Err(Error::ArgOutside);
-        }
+        DRMeterBuilder::new()
+            .channels(channels)
+            .rate(rate)
+            .window(window)
+            .build()
+    }
 
-        if window < 10 {
-            return Err(Error::ArgOutside);
-        }
+    /// Returns a builder of new instance.
+    pub fn builder() -> DRMeterBuilder {
+        DRMeterBuilder::new()
+    }
 
+    /// Create a new instance of validated configuration
+    fn allocate(channels: u32, rate: u32, window: usize) -> Result<Self, Error> {
         // TODO: some pushover +5
         // FFMPEG: samples = time_constant * sample_rate + .5
         let needed_frames = (rate as usize).checked_mul(window).ok_or(Error::NoMem)? / 1000;
 
-        let data = Self::allocate_bin(channels as usize)?;
+        let data = Self::allocate_bin(channels as usize, HistogramMode::default(), BINS)?;
 
         Ok(Self {
             rate,
             channels,
             needed_frames,
+            histogram_mode: HistogramMode::default(),
+            quantization: Quantization::default(),
+            percentile: Percentile::default(),
+            loud_fraction: LOUD_FRACTION,
+            channel_map: None,
+            stems: Vec::new(),
+            peak_headroom: 0.0,
+            peak_mode: PeakMode::default(),
+            true_peaks: None,
+            dr_numerator: DrNumerator::default(),
+            peak_bins: BINS,
             peaks: data.clone(),
             rms: data,
+            worst_blocks: vec![None; channels as usize].into_boxed_slice(),
+            last_block: None,
+            block_values: None,
+            overlap: None,
+            track_boundaries: Vec::new(),
+            track: None,
+            tracks: Vec::new(),
+            block_callback: None,
             block_number: 0,
+            frames: 0,
+            block_start: 0,
+            expected_frames: None,
+            max_frames: None,
+            known_blocks: None,
+            peak_hold: None,
+            channel_pairs: None,
+            dual_mono_detection: false,
+            polarity_detection: false,
+            non_finite_policy: NonFinitePolicy::default(),
+            weighting: Weighting::default(),
+            window_function: WindowFunction::default(),
+            first_non_finite: None,
+            discontinuities: Vec::new(),
             window,
             block: Block::new(channels),
             channel_dr: None,
+            #[cfg(feature = "profiling")]
+            profile: crate::Profile::default(),
         })
     }
 
@@ -154,143 +822,1483 @@ impl DRMeter {
         self.window
     }
 
-    /// Returns `true` if this instance is finalized.
-    pub const fn finalized(&self) -> bool {
-        // instance is finalized if we have cached values
-        self.channel_dr.is_some()
+    /// Returns the number of frames that were added.
+    pub const fn frames(&self) -> u64 {
+        self.frames
     }
 
-    /// Finalize current block
-    fn finalize_block(&mut self) {
-        debug_assert_ne!(self.block.consumed_frames(), 0);
-        let (peak, rms) = self.block.finish();
-        for ch in 0..(self.channels as usize) {
-            let rms_bin = ((rms[ch] * BINS as f64).round() as usize).clamp(0, BINS);
-            let peak_bin = ((peak[ch] * BINS as f64).round() as usize).clamp(0, BINS);
-            self.rms[ch][rms_bin] += 1;
-            self.peaks[ch][peak_bin] += 1;
-        }
-        self.block_number += 1;
-        // finalize block
-        self.block.reset();
+    /// Returns the declared total number of frames.
+    pub const fn expected_frames(&self) -> Option<u64> {
+        self.expected_frames
     }
 
-    /// Finalize instance (marking end of stream)
-    ///
-    /// For streaming, DR values are computed using only fully finished blocks,
-    /// but if you reached the end of stream you can use this function
-    /// to forces finalization of half block if such block even exist.
+    /// Returns the fraction (`0.0..=1.0`) of expected frames that were added.
     ///
-    /// As part of finalization exact channel DR scores are also calculated and cached
+    /// This is `None` if expected frames were not declared.
+    pub fn progress(&self) -> Option<f64> {
+        self.expected_frames
+            .map(|expected| (self.frames as f64 / expected as f64).min(1.0))
+    }
+
+    /// Returns how many of the expected frames did not arrive (yet).
     ///
-    /// After finalization you cannot add frames to the instance.
-    pub fn finalize(&mut self) -> Result<(), Error> {
-        if self.finalized() {
-            return Err(Error::Finalized);
-        }
+    /// Check this after finalization to detect truncated streams.
+    pub fn missing_frames(&self) -> Option<u64> {
+        self.expected_frames
+            .map(|expected| expected.saturating_sub(self.frames))
+    }
 
-        // finalize half block if exist
-        if self.block.consumed_frames() != 0 {
-            self.finalize_block()
-        };
+    /// Returns current and held peak per channel, if peak hold is enabled.
+    pub fn peak_levels(&self) -> Option<&[PeakLevel]> {
+        self.peak_hold.as_ref().map(PeakHold::levels)
+    }
 
-        // calculate and cache exact channel values
-        self.channel_dr = Some(
-            (0..self.channels)
-                .map(|ch| self.exact_channel_dr(ch))
-                .collect::<Result<Box<[f64]>, Error>>()?,
-        );
+    /// Returns the handling of non-finite samples.
+    pub const fn non_finite_policy(&self) -> NonFinitePolicy {
+        self.non_finite_policy
+    }
 
-        Ok(())
+    /// Returns position (in frames from start) of the first non-finite sample.
+    ///
+    /// This is recorded with every policy, with [`NonFinitePolicy::Error`]
+    /// it locates the sample of the rejected frames.
+    pub const fn first_non_finite(&self) -> Option<u64> {
+        self.first_non_finite
     }
 
-    /***********************
-     *
-     *  Fill with data
-     *
-     ***********************/
-    /// Process frames. This is the generic variant of the different public add_frames() functions
-    /// that are defined below.
-    fn add_frames<'a, T: Sample + 'a, S: Samples<'a, T>>(
-        &mut self,
-        mut src: S,
-    ) -> Result<(), Error> {
-        if self.finalized() {
-            return Err(Error::Finalized);
-        }
+    /// Returns positions (in frames from start) of marked discontinuities.
+    pub fn discontinuities(&self) -> &[u64] {
+        &self.discontinuities
+    }
 
-        if src.frames() == 0 {
-            return Ok(());
-        }
+    /// Returns positions (in frames from start) of marked track boundaries.
+    pub fn track_boundaries(&self) -> &[u64] {
+        &self.track_boundaries
+    }
 
-        if self.channels == 0 {
-            return Err(Error::NoMem);
-        }
+    /// Returns the frequency weighting of RMS.
+    pub const fn weighting(&self) -> Weighting {
+        self.weighting
+    }
 
-        while src.frames() > 0 {
-            let num_frames = src.frames();
+    /// Returns the window function of block energy.
+    pub const fn window_function(&self) -> WindowFunction {
+        self.window_function
+    }
 
-            let frames_still_needed = self.needed_frames - self.block.consumed_frames();
-            if num_frames >= frames_still_needed {
-                let (current, next) = src.split_at(frames_still_needed);
+    /// Returns the storage used for histograms.
+    pub const fn histogram_mode(&self) -> HistogramMode {
+        self.histogram_mode
+    }
 
-                self.block.process(current);
-                // one block is now finished
-                self.finalize_block();
+    /// Returns the rule of RMS binning.
+    pub const fn quantization(&self) -> Quantization {
+        self.quantization
+    }
 
-                src = next;
-            } else {
-                let (current, next) = src.split_at(num_frames);
-                // currently read frames for block processor
-                self.block.process(current);
-                // we get unfinished block
+    /// Returns the input channel of every meter channel, if channels are remapped.
+    pub fn channel_map(&self) -> Option<&[usize]> {
+        self.channel_map.as_deref()
+    }
 
-                // next is empty?
-                src = next;
-            }
-        }
+    /// Returns pairs of identical channels `(a, b)` with `a < b`,
+    /// if dual-mono detection is enabled.
+    pub fn dual_mono_pairs(&self) -> Option<Vec<(u32, u32)>> {
+        self.channel_pairs
+            .as_ref()
+            .filter(|_| self.dual_mono_detection)
+            .map(ChannelPairs::dual_mono)
+    }
 
-        Ok(())
+    /// Returns pairs of strongly negatively correlated channels `(a, b)`
+    /// with `a < b`, if polarity detection is enabled.
+    pub fn inverted_pairs(&self) -> Option<Vec<(u32, u32)>> {
+        self.channel_pairs
+            .as_ref()
+            .filter(|_| self.polarity_detection)
+            .map(ChannelPairs::inverted)
     }
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
-        self.add_frames(Interleaved::new(frames, self.channels as usize)?)
+    /// Returns the selection of top 20% RMS blocks.
+    pub const fn percentile(&self) -> Percentile {
+        self.percentile
     }
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
-        self.add_frames(Interleaved::new(frames, self.channels as usize)?)
+    /// Returns the fraction of loudest blocks in RMS.
+    pub const fn loud_fraction(&self) -> f64 {
+        self.loud_fraction
     }
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_f32(&mut self, frames: &[f32]) -> Result<(), Error> {
-        self.add_frames(Interleaved::new(frames, self.channels as usize)?)
+    /// Returns the hop of overlapping blocks in ms, if blocks overlap.
+    pub fn hop(&self) -> Option<usize> {
+        self.overlap.as_ref().map(Overlap::hop)
     }
 
-    /// Add interleaved frames to be processed.
-    pub fn add_frames_f64(&mut self, frames: &[f64]) -> Result<(), Error> {
-        self.add_frames(Interleaved::new(frames, self.channels as usize)?)
+    /// Returns `true` if raw block values are kept.
+    pub const fn exact_mode(&self) -> bool {
+        self.block_values.is_some()
     }
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
-        self.add_frames(Planar::new(frames)?)
+    /// Returns the configured headroom above 0 dBFS for peaks (in dB).
+    pub const fn peak_headroom(&self) -> f64 {
+        self.peak_headroom
     }
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
-        self.add_frames(Planar::new(frames)?)
+    /// Returns the peak measured in blocks.
+    pub const fn peak_mode(&self) -> PeakMode {
+        self.peak_mode
     }
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
-        self.add_frames(Planar::new(frames)?)
+    /// Returns the peak used in DR formula.
+    pub const fn dr_numerator(&self) -> DrNumerator {
+        self.dr_numerator
     }
 
-    /// Add planar frames to be processed.
-    pub fn add_frames_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
-        self.add_frames(Planar::new(frames)?)
+    /// Returns the highest true peak of channel (linear).
+    ///
+    /// This is `None` unless peak mode is [`PeakMode::True`].
+    pub fn true_peak(&self, channel_number: u32) -> Result<Option<f64>, Error> {
+        self.check_channel(channel_number)?;
+        Ok(self
+            .true_peaks
+            .as_ref()
+            .map(|peaks| peaks[channel_number as usize]))
+    }
+
+    /// Returns `true` if some block could not be counted
+    /// because its histogram bin was full.
+    ///
+    /// This can only happen with [`HistogramMode::Compact`],
+    /// results are not reliable in such case.
+    pub fn histogram_saturated(&self) -> bool {
+        self.peaks
+            .iter()
+            .chain(self.rms.iter())
+            .any(Histogram::saturated)
+    }
+
+    /// Returns the number of histogram bins that represent full scale.
+    ///
+    /// Adaptive histograms refine as blocks are added, so this can grow.
+    pub fn histogram_bins(&self) -> usize {
+        BINS << self.max_shift()
+    }
+
+    /// Returns linear value (full scale is `1.0`) of histogram bin,
+    /// use [`units::amplitude_to_dbfs`](crate::units::amplitude_to_dbfs) to get dBFS.
+    pub fn bin_value(&self, bin: usize) -> f64 {
+        bin as f64 / self.histogram_bins() as f64
+    }
+
+    /// Populated peak histogram bins of channel as `(bin, count)` pairs
+    ///
+    /// Bin value is [`bin_value`](Self::bin_value) of bin.
+    pub fn peak_histogram(&self, channel_number: u32) -> Result<Vec<(usize, u32)>, Error> {
+        self.check_channel(channel_number)?;
+        Ok(self.common_bins(&self.peaks[channel_number as usize]))
+    }
+
+    /// Populated RMS histogram bins of channel as `(bin, count)` pairs
+    ///
+    /// Bin value is [`bin_value`](Self::bin_value) of bin.
+    pub fn rms_histogram(&self, channel_number: u32) -> Result<Vec<(usize, u32)>, Error> {
+        self.check_channel(channel_number)?;
+        Ok(self.common_bins(&self.rms[channel_number as usize]))
+    }
+
+    /// Returns the number of blocks of channel with peak above
+    /// the histogram range (full scale plus headroom), which were clamped.
+    pub fn peak_overflows(&self, channel_number: u32) -> Result<u32, Error> {
+        self.check_channel(channel_number)?;
+        Ok(self.peaks[channel_number as usize].overflows())
+    }
+
+    /// Returns the number of blocks of channel with RMS above full scale,
+    /// which were clamped.
+    ///
+    /// RMS is normalized to sine, so full scale square wave is above it.
+    pub fn rms_overflows(&self, channel_number: u32) -> Result<u32, Error> {
+        self.check_channel(channel_number)?;
+        Ok(self.rms[channel_number as usize].overflows())
+    }
+
+    /// Returns the finished block of channel with the lowest crest factor
+    /// (the most compressed moment of program).
+    ///
+    /// Returns `None` if there is no finished block that is not silent.
+    pub fn worst_block(&self, channel_number: u32) -> Result<Option<WorstBlock>, Error> {
+        self.check_channel(channel_number)?;
+        Ok(self.worst_blocks[channel_number as usize])
+    }
+
+    /// Returns levels of the last finished block.
+    ///
+    /// Compare its index between adds of frames to follow blocks as they finish.
+    pub const fn last_block(&self) -> Option<&BlockResult> {
+        self.last_block.as_ref()
+    }
+
+    /// Returns DR over sliding window of the last `window_blocks` blocks
+    /// at every finished block, averaged over channels.
+    ///
+    /// Windows of first blocks are shorter, window of a single block has
+    /// no second peak and `-inf` DR. Needs raw block values, so this is
+    /// `None` unless [exact mode](Self::set_exact_mode) is enabled.
+    pub fn dr_timeline(&self, window_blocks: usize) -> Result<Option<Vec<f64>>, Error> {
+        if window_blocks == 0 {
+            return Err(Error::ArgOutside);
+        }
+        let Some(values) = &self.block_values else {
+            return Ok(None);
+        };
+
+        let timeline = (0..self.block_number)
+            .map(|block| {
+                let blocks = (block + 1).saturating_sub(window_blocks)..block + 1;
+                values
+                    .iter()
+                    .map(|v| v.dr(blocks.clone(), self.loud_fraction))
+                    .sum::<f64>()
+                    / values.len() as f64
+            })
+            .collect();
+        Ok(Some(timeline))
+    }
+
+    /// Write levels of every finished block as CSV.
+    ///
+    /// Every row has block index, its start time in seconds and
+    /// peak and RMS (in dBFS) per channel. Block values are kept only in
+    /// [exact mode](Self::set_exact_mode), otherwise this fails with
+    /// [`io::ErrorKind::Unsupported`].
+    pub fn write_blocks_csv<W: io::Write>(&self, mut writer: W) -> io::Result<()> {
+        let Some(values) = &self.block_values else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "block values are kept only in exact mode",
+            ));
+        };
+
+        write!(writer, "block,time")?;
+        for ch in 1..=self.channels {
+            write!(writer, ",peak_{ch},rms_{ch}")?;
+        }
+        writeln!(writer)?;
+        let starts = values.first().map_or(&[][..], |v| &v.starts);
+        for (block, start) in starts.iter().enumerate() {
+            write!(writer, "{block},{:.3}", *start as f64 / self.rate as f64)?;
+            for values in values.iter() {
+                write!(
+                    writer,
+                    ",{:.2},{:.2}",
+                    amplitude_to_dbfs(values.peaks[block]),
+                    amplitude_to_dbfs(values.rms[block])
+                )?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if this instance is finalized.
+    pub const fn finalized(&self) -> bool {
+        // instance is finalized if we have cached values
+        self.channel_dr.is_some()
+    }
+
+    /// Returns `true` if any frames were already processed.
+    fn started(&self) -> bool {
+        self.frames != 0
+    }
+
+    /************
+     *
+     *  setters
+     *
+     ************/
+
+    /// Set storage used for histograms
+    ///
+    /// This reallocates histograms, so it can only be done
+    /// before any frames are added.
+    pub fn set_histogram_mode(&mut self, mode: HistogramMode) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        self.peaks = Self::allocate_bin(self.channels as usize, mode, self.peak_bins)?;
+        self.rms = Self::allocate_bin(self.channels as usize, mode, BINS)?;
+        self.histogram_mode = mode;
+
+        Ok(())
+    }
+
+    /// Set rule of RMS binning (peaks are always rounded)
+    ///
+    /// [`Quantization::Floor`] matches tools that truncate RMS.
+    /// This can only be done before any frames are added.
+    pub fn set_quantization(&mut self, quantization: Quantization) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        self.quantization = quantization;
+        Ok(())
+    }
+
+    /// Set channel map that reorders input channels to the meter's layout
+    ///
+    /// Meter channel `i` is measured from input channel `map[i]`,
+    /// so for example `[1, 0]` swaps stereo channels.
+    /// Map must be a permutation of all channels, `None` keeps input order.
+    /// This can only be done before any frames are added.
+    pub fn set_channel_map(&mut self, map: Option<&[usize]>) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        if let Some(map) = map {
+            let mut seen = vec![false; self.channels as usize];
+            if map.len() != seen.len() {
+                return Err(Error::ArgOutside);
+            }
+            for &ch in map {
+                match seen.get_mut(ch) {
+                    Some(seen) if !*seen => *seen = true,
+                    _ => return Err(Error::ArgOutside),
+                }
+            }
+        }
+
+        self.channel_map = map.map(Into::into);
+        Ok(())
+    }
+
+    /// Enable detection of identical channels (dual-mono masquerading as stereo)
+    ///
+    /// Channels are identical when energy of their difference is
+    /// [`DUAL_MONO_THRESHOLD`](crate::DUAL_MONO_THRESHOLD) below their energy.
+    /// This can only be done before any frames are added.
+    pub fn set_dual_mono_detection(&mut self, enabled: bool) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        self.dual_mono_detection = enabled;
+        self.update_channel_pairs();
+        Ok(())
+    }
+
+    /// Enable detection of polarity inverted channels (phase-inverted pairs)
+    ///
+    /// Channels are inverted when their correlation is at or below
+    /// [`POLARITY_INVERSION_THRESHOLD`](crate::POLARITY_INVERSION_THRESHOLD).
+    /// This can only be done before any frames are added.
+    pub fn set_polarity_detection(&mut self, enabled: bool) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        self.polarity_detection = enabled;
+        self.update_channel_pairs();
+        Ok(())
+    }
+
+    /// Track channel pairs if any detection needs them
+    fn update_channel_pairs(&mut self) {
+        self.channel_pairs = (self.dual_mono_detection || self.polarity_detection)
+            .then(|| ChannelPairs::new(self.channels as usize));
+    }
+
+    /// Attach sub-meter to subset of input channels (stem)
+    ///
+    /// Stems are measured from the same frames simultaneously, so for example
+    /// dialogue, music and effects of a film mix feed get their own DR.
+    /// Channels are indices of input (before channel map) and stem meter
+    /// has the same configuration as this meter at the time of attaching.
+    /// This can only be done before any frames are added.
+    pub fn add_stem(&mut self, name: impl Into<String>, channels: &[u32]) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        let mut used = vec![false; self.channels as usize];
+        for &ch in channels {
+            match used.get_mut(ch as usize) {
+                Some(used) if !*used => *used = true,
+                _ => return Err(Error::ArgOutside),
+            }
+        }
+
+        let mut meter = Self::new_with_window(channels.len() as u32, self.rate, self.window)?;
+        meter.set_histogram_mode(self.histogram_mode)?;
+        meter.set_quantization(self.quantization)?;
+        meter.set_percentile(self.percentile)?;
+        meter.set_loud_fraction(self.loud_fraction)?;
+        meter.set_peak_headroom(self.peak_headroom)?;
+        meter.set_peak_mode(self.peak_mode)?;
+        meter.set_dr_numerator(self.dr_numerator)?;
+        meter.set_weighting(self.weighting)?;
+        meter.set_window_function(self.window_function)?;
+        meter.set_exact_mode(self.exact_mode())?;
+        meter.set_hop(self.hop())?;
+        meter.non_finite_policy = self.non_finite_policy;
+        if let Some(frames) = self.known_blocks.and(self.expected_frames) {
+            meter.set_exact_duration(frames)?;
+        }
+
+        self.stems.push(Stem {
+            name: name.into(),
+            channels: channels.iter().map(|&ch| ch as usize).collect(),
+            meter,
+        });
+        Ok(())
+    }
+
+    /// Returns names and meters of stems in order they were attached.
+    pub fn stems(&self) -> impl Iterator<Item = (&str, &DRMeter)> {
+        self.stems
+            .iter()
+            .map(|stem| (stem.name.as_str(), &stem.meter))
+    }
+
+    /// Returns meter of stem with given name.
+    pub fn stem(&self, name: &str) -> Option<&DRMeter> {
+        self.stems()
+            .find(|(n, _)| *n == name)
+            .map(|(_, meter)| meter)
+    }
+
+    /// Returns `true` if stems are attached.
+    pub(crate) fn has_stems(&self) -> bool {
+        !self.stems.is_empty()
+    }
+
+    /// Call `callback` with levels of every block as it finishes.
+    ///
+    /// Blocks added by [`merge`](Self::merge) are not reported.
+    /// The callback is not part of serialized state.
+    pub fn set_block_callback(&mut self, callback: impl FnMut(BlockResult) + Send + 'static) {
+        self.block_callback = Some(Box::new(callback));
+    }
+
+    /// Remove callback of finished blocks.
+    pub fn clear_block_callback(&mut self) {
+        self.block_callback = None;
+    }
+
+    pub(crate) fn has_block_callback(&self) -> bool {
+        self.block_callback.is_some()
+    }
+
+    /// Set selection of top 20% RMS blocks
+    ///
+    /// This only affects computation of results, so it can be changed
+    /// until the instance is finalized.
+    pub fn set_percentile(&mut self, percentile: Percentile) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+
+        self.percentile = percentile;
+        Ok(())
+    }
+
+    /// Set fraction of loudest blocks in RMS (0.2 for official top 20%)
+    ///
+    /// Like the percentile this only affects computation of results,
+    /// so it can be changed until the instance is finalized.
+    ///
+    /// Returns [`Error::ArgOutside`] unless fraction is above 0 and at most 1.
+    pub fn set_loud_fraction(&mut self, fraction: f64) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+        if !valid_loud_fraction(fraction) {
+            return Err(Error::ArgOutside);
+        }
+
+        self.loud_fraction = fraction;
+        Ok(())
+    }
+
+    /// Measure overlapping blocks, a new one every `hop` ms
+    ///
+    /// Every block still spans the whole window, but it starts a hop after
+    /// the previous one, so DR is less sensitive to where block boundaries
+    /// fall relative to transients. Hop must divide the window into blocks
+    /// of whole hop frames (e.g. 1000 ms of 3000 ms window), it cannot be
+    /// combined with tapered window function or exact duration.
+    /// Overlapped DR is not comparable with the official DR.
+    /// This can only be done before any frames are added.
+    pub fn set_hop(&mut self, hop: Option<usize>) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        self.overlap = match hop {
+            Some(hop) => {
+                if self.window_function != WindowFunction::Rectangular || self.exact_duration() {
+                    return Err(Error::ArgOutside);
+                }
+                Some(Overlap::new(hop, self.window, self.rate).ok_or(Error::ArgOutside)?)
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Keep raw peak and RMS of every block besides histograms
+    ///
+    /// Peaks and top 20% RMS are then computed from exact block values,
+    /// without quantization of histogram bins (like DeaDBeeF DR Meter).
+    /// Exactly top 20% of blocks are summed regardless of
+    /// [`Percentile`], the last one only partially. This costs 24 bytes
+    /// per block and channel. This can only be done before any frames are added.
+    pub fn set_exact_mode(&mut self, enabled: bool) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        self.block_values = enabled
+            .then(|| vec![BlockValues::default(); self.channels as usize].into_boxed_slice());
+        Ok(())
+    }
+
+    /// Set frequency weighting of RMS (peaks are not weighted)
+    ///
+    /// Weighted DR is not comparable with the official (unweighted) DR.
+    /// This can only be done before any frames are added.
+    pub fn set_weighting(&mut self, weighting: Weighting) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        self.block
+            .set_weighting(WeightingFilter::new(weighting, self.rate, self.channels));
+        self.weighting = weighting;
+
+        Ok(())
+    }
+
+    /// Set window function of block energy (peaks are not windowed)
+    ///
+    /// Window spans the planned block, so partial last block
+    /// uses only the leading part of it.
+    /// This can only be done before any frames are added.
+    pub fn set_window_function(&mut self, function: WindowFunction) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        if function != WindowFunction::Rectangular && self.overlap.is_some() {
+            return Err(Error::ArgOutside);
+        }
+
+        self.block.set_window(match function {
+            WindowFunction::Rectangular => None,
+            WindowFunction::Hann => Some(hann(self.needed_frames)),
+        });
+        self.window_function = function;
+
+        Ok(())
+    }
+
+    /// Set handling of non-finite float samples
+    pub fn set_non_finite_policy(&mut self, policy: NonFinitePolicy) {
+        self.non_finite_policy = policy;
+    }
+
+    /// Declare expected total number of frames (as known from container)
+    ///
+    /// This is only a hint used for [`progress`](Self::progress)
+    /// and [`missing_frames`](Self::missing_frames).
+    pub fn set_expected_frames(&mut self, frames: u64) {
+        self.expected_frames = Some(frames);
+    }
+
+    /// Stop accepting frames after the given duration (`None` removes the limit)
+    ///
+    /// Frames above the limit are dropped and adding them returns
+    /// [`Error::DurationLimit`], while frames up to the limit are still measured.
+    /// This is useful for previews and for bounding the runtime.
+    pub fn set_max_duration(&mut self, duration: Option<std::time::Duration>) {
+        self.max_frames = duration.map(|d| (d.as_secs_f64() * self.rate as f64) as u64);
+    }
+
+    /// Returns number of frames after which frames are not accepted.
+    pub const fn max_frames(&self) -> Option<u64> {
+        self.max_frames
+    }
+
+    /// Declare exact total number of frames, so the whole block schedule is known up front
+    ///
+    /// Like in non-streaming implementations, the stream is split into
+    /// `frames / window` blocks (at least one) and the remainder is
+    /// part of the last block instead of forming a short block of its own.
+    /// Frames above the declared duration are streamed as usual.
+    ///
+    /// This also sets [`expected_frames`](Self::expected_frames),
+    /// and can only be done before any frames are added.
+    pub fn set_exact_duration(&mut self, frames: u64) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        if frames == 0 || self.overlap.is_some() {
+            return Err(Error::ArgOutside);
+        }
+
+        let blocks = (frames / self.needed_frames as u64).max(1);
+        self.known_blocks = Some(usize::try_from(blocks).map_err(|_| Error::ArgOutside)?);
+        self.expected_frames = Some(frames);
+
+        Ok(())
+    }
+
+    /// Returns `true` if exact duration was declared.
+    pub const fn exact_duration(&self) -> bool {
+        self.known_blocks.is_some()
+    }
+
+    /// Number of frames needed to finish current block
+    fn current_block_frames(&self) -> usize {
+        match (self.known_blocks, self.expected_frames) {
+            (Some(blocks), Some(expected)) if self.block_number + 1 == blocks => {
+                // last block takes the remainder
+                (expected - (self.needed_frames * (blocks - 1)) as u64) as usize
+            }
+            _ => self.step_frames(),
+        }
+    }
+
+    /// Frames between starts of consecutive blocks
+    fn step_frames(&self) -> usize {
+        self.overlap
+            .as_ref()
+            .map_or(self.needed_frames, Overlap::hop_frames)
+    }
+
+    /// Enable peak hold with the given decay time (in seconds)
+    ///
+    /// Held peak falls by 20 dB over decay time. Levels are updated
+    /// with every add of frames, see [`peak_levels`](Self::peak_levels).
+    pub fn set_peak_hold(&mut self, decay: f64) -> Result<(), Error> {
+        if !(decay > 0.0 && decay.is_finite()) {
+            return Err(Error::ArgOutside);
+        }
+
+        self.peak_hold = Some(PeakHold::new(self.channels as usize, decay));
+        Ok(())
+    }
+
+    /// Set headroom above 0 dBFS for peaks (in dB)
+    ///
+    /// Float samples can legitimately exceed full scale. By default such peaks
+    /// are clamped to 0 dBFS, with headroom they are measured up to
+    /// the given level, so [`second_peak`](Self::second_peak) can report values above `1.0`.
+    ///
+    /// Max headroom is 20 dB. This reallocates peak histograms,
+    /// so it can only be done before any frames are added.
+    pub fn set_peak_headroom(&mut self, headroom: f64) -> Result<(), Error> {
+        if !(0.0..=MAX_PEAK_HEADROOM).contains(&headroom) {
+            return Err(Error::ArgOutside);
+        }
+
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+
+        let peak_bins = math::round(BINS as f64 * from_decibel(headroom)) as usize;
+        self.peaks = Self::allocate_bin(self.channels as usize, self.histogram_mode, peak_bins)?;
+        self.peak_bins = peak_bins;
+        self.peak_headroom = headroom;
+
+        Ok(())
+    }
+
+    /// Set peak measured in blocks
+    ///
+    /// With [`PeakMode::True`] true peaks are measured alongside sample peaks,
+    /// DR and block peaks are not affected by it unless selected
+    /// by [`set_dr_numerator`](Self::set_dr_numerator).
+    /// This can only be done before any frames are added.
+    ///
+    /// Returns [`Error::ArgOutside`] when leaving [`PeakMode::True`]
+    /// with [`DrNumerator::TruePeak`] selected.
+    pub fn set_peak_mode(&mut self, mode: PeakMode) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+        if mode != PeakMode::True && self.dr_numerator == DrNumerator::TruePeak {
+            return Err(Error::ArgOutside);
+        }
+
+        let oversampler =
+            (mode == PeakMode::True).then(|| Oversampler::new(self.rate, self.channels as usize));
+        self.block.set_oversampler(oversampler);
+        self.true_peaks =
+            (mode == PeakMode::True).then(|| vec![0.0; self.channels as usize].into_boxed_slice());
+        self.peak_mode = mode;
+
+        Ok(())
+    }
+
+    /// Set peak used in DR formula
+    ///
+    /// With [`DrNumerator::TruePeak`] block peaks, and so histograms,
+    /// highest peaks and DR, are true peaks.
+    /// This can only be done before any frames are added.
+    ///
+    /// Returns [`Error::ArgOutside`] for [`DrNumerator::TruePeak`]
+    /// unless peak mode is [`PeakMode::True`].
+    pub fn set_dr_numerator(&mut self, numerator: DrNumerator) -> Result<(), Error> {
+        if self.started() {
+            return Err(Error::AlreadyStarted);
+        }
+        if numerator == DrNumerator::TruePeak && self.peak_mode != PeakMode::True {
+            return Err(Error::ArgOutside);
+        }
+
+        self.dr_numerator = numerator;
+
+        Ok(())
+    }
+
+    /// Create a new empty instance with the same configuration.
+    pub(crate) fn empty_clone(&self) -> Result<Self, Error> {
+        let mut meter = Self::new_with_window(self.channels, self.rate, self.window)?;
+        meter.set_histogram_mode(self.histogram_mode)?;
+        meter.set_quantization(self.quantization)?;
+        meter.set_percentile(self.percentile)?;
+        meter.set_loud_fraction(self.loud_fraction)?;
+        meter.set_channel_map(self.channel_map())?;
+        meter.set_dual_mono_detection(self.dual_mono_detection)?;
+        meter.set_polarity_detection(self.polarity_detection)?;
+        meter.set_peak_headroom(self.peak_headroom)?;
+        meter.set_peak_mode(self.peak_mode)?;
+        meter.set_dr_numerator(self.dr_numerator)?;
+        meter.set_weighting(self.weighting)?;
+        meter.set_window_function(self.window_function)?;
+        meter.set_exact_mode(self.exact_mode())?;
+        meter.set_hop(self.hop())?;
+        meter.non_finite_policy = self.non_finite_policy;
+        Ok(meter)
+    }
+
+    /// Clear measurement, so the instance (and its allocations) can be
+    /// reused for another stream with the same configuration.
+    ///
+    /// Works on finalized instances too. Declared expected frames
+    /// and exact duration are cleared, other options are kept.
+    pub fn reset(&mut self) {
+        self.block.clear();
+        self.block_number = 0;
+        self.frames = 0;
+        self.block_start = 0;
+        self.expected_frames = None;
+        self.known_blocks = None;
+        self.first_non_finite = None;
+        self.discontinuities.clear();
+        self.track_boundaries.clear();
+        self.track = None;
+        self.tracks.clear();
+        for histogram in self.peaks.iter_mut().chain(self.rms.iter_mut()) {
+            histogram.clear();
+        }
+        self.worst_blocks.fill(None);
+        self.last_block = None;
+        if let Some(true_peaks) = &mut self.true_peaks {
+            true_peaks.fill(0.0);
+        }
+        if let Some(overlap) = &mut self.overlap {
+            overlap.clear();
+        }
+        for values in self.block_values.iter_mut().flatten() {
+            values.starts.clear();
+            values.peaks.clear();
+            values.rms.clear();
+        }
+        if let Some(pairs) = &mut self.channel_pairs {
+            pairs.reset();
+        }
+        if let Some(peak_hold) = &mut self.peak_hold {
+            peak_hold.reset();
+        }
+        for stem in &mut self.stems {
+            stem.meter.reset();
+        }
+        self.channel_dr = None;
+        #[cfg(feature = "profiling")]
+        {
+            self.profile = crate::Profile::default();
+        }
+    }
+
+    /// Number of frames in a block
+    pub(crate) const fn block_frames(&self) -> usize {
+        self.needed_frames
+    }
+
+    /// Returns `true` if blocks of other instance are measured the same way
+    fn compatible(&self, other: &DRMeter) -> bool {
+        self.rate == other.rate
+            && self.channels == other.channels
+            && self.needed_frames == other.needed_frames
+            && self.weighting == other.weighting
+            && self.window_function == other.window_function
+            && self.quantization == other.quantization
+            && self.peak_headroom == other.peak_headroom
+            && self.peak_mode == other.peak_mode
+            && self.dr_numerator == other.dr_numerator
+            && self.exact_mode() == other.exact_mode()
+            && self.hop() == other.hop()
+            && self.channel_map == other.channel_map
+            && self.stems.len() == other.stems.len()
+            && Iterator::zip(self.stems.iter(), other.stems.iter())
+                .all(|(a, b)| a.channels == b.channels && a.meter.compatible(&b.meter))
+    }
+
+    /// Add measured blocks of other instance, that metered a later part of the stream.
+    ///
+    /// This allows metering chunks of a long stream separately (e.g. on other threads)
+    /// and combining them into one result. Both instances must have the same rate,
+    /// channels, window and measurement options, otherwise [`Error::Mismatch`] is returned.
+    ///
+    /// Other instance must be finalized or have no unfinished block
+    /// ([`Error::NotFinalized`] otherwise). Unfinished block of this instance
+    /// is measured as a block of its own, so results equal sequential metering
+    /// only if chunks are split on block boundaries.
+    pub fn merge(&mut self, other: &DRMeter) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+        if !self.compatible(other)
+            || !self.track_boundaries.is_empty()
+            || !other.track_boundaries.is_empty()
+        {
+            return Err(Error::Mismatch);
+        }
+        if !other.finalized() && other.block.consumed_frames() != 0 {
+            return Err(Error::NotFinalized);
+        }
+
+        self.merge_unchecked(other);
+        Ok(())
+    }
+
+    fn merge_unchecked(&mut self, other: &DRMeter) {
+        self.finish_blocks();
+        self.block.clear();
+        self.merge_blocks(other);
+        for (stem, o) in self.stems.iter_mut().zip(other.stems.iter()) {
+            stem.meter.merge_unchecked(&o.meter);
+        }
+    }
+
+    /// Add finished blocks of other instance with the same configuration.
+    ///
+    /// Other instance must not have partial block.
+    pub(crate) fn merge_blocks(&mut self, other: &DRMeter) {
+        debug_assert_eq!(other.block.consumed_frames(), 0);
+        debug_assert_eq!(self.block.consumed_frames(), 0);
+        if self.first_non_finite.is_none() {
+            self.first_non_finite = other.first_non_finite.map(|f| self.frames + f);
+        }
+        for (h, o) in self.peaks.iter_mut().zip(other.peaks.iter()) {
+            h.merge(o);
+        }
+        for (h, o) in self.rms.iter_mut().zip(other.rms.iter()) {
+            h.merge(o);
+        }
+        if let (Some(peaks), Some(o)) = (&mut self.true_peaks, &other.true_peaks) {
+            for (p, o) in peaks.iter_mut().zip(o.iter()) {
+                *p = f64::max(*p, *o);
+            }
+        }
+        let offset = self.frames;
+        if let (Some(values), Some(o)) = (&mut self.block_values, &other.block_values) {
+            for (v, o) in values.iter_mut().zip(o.iter()) {
+                v.starts.extend(o.starts.iter().map(|start| start + offset));
+                v.peaks.extend_from_slice(&o.peaks);
+                v.rms.extend_from_slice(&o.rms);
+            }
+        }
+        for (worst, o) in self.worst_blocks.iter_mut().zip(other.worst_blocks.iter()) {
+            if let Some(block) = o {
+                *worst = WorstBlock::worse(
+                    *worst,
+                    WorstBlock {
+                        start: block.start + offset,
+                        ..*block
+                    },
+                );
+            }
+        }
+        if let (Some(pairs), Some(o)) = (&mut self.channel_pairs, &other.channel_pairs) {
+            pairs.merge(o);
+        }
+        self.block_number += other.block_number;
+        let frames = self.frames;
+        self.discontinuities
+            .extend(other.discontinuities.iter().map(|d| frames + d));
+        self.frames += other.frames;
+        self.block_start = self.frames;
+    }
+
+    /// Finalize unfinished block and hops that are not part of any block yet
+    fn finish_blocks(&mut self) {
+        if self.block.consumed_frames() != 0 {
+            self.finalize_block();
+        }
+        if let Some((start, peak, rms)) = self.overlap.as_mut().and_then(Overlap::flush) {
+            self.record_block(start, peak, rms);
+        }
+    }
+
+    /// Finalize current block (or hop of overlapping blocks)
+    fn finalize_block(&mut self) {
+        debug_assert_ne!(self.block.consumed_frames(), 0);
+        #[cfg(feature = "profiling")]
+        let timer = crate::profile::Timer::start();
+        let frames = self.block.consumed_frames();
+        let (mut peak, rms) = self.block.finish();
+        if let Some(true_peaks) = &mut self.true_peaks {
+            for (true_peak, peak) in true_peaks.iter_mut().zip(self.block.true_peak()) {
+                *true_peak = f64::max(*true_peak, *peak);
+            }
+        }
+        if self.dr_numerator == DrNumerator::TruePeak {
+            peak.copy_from_slice(self.block.true_peak());
+        }
+        // finalize block
+        self.block.reset();
+        let start = self.block_start;
+        self.block_start += frames as u64;
+        let block = match &mut self.overlap {
+            Some(overlap) => overlap.push(start, peak, &rms, frames),
+            None => Some((start, peak, rms)),
+        };
+        if let Some((start, peak, rms)) = block {
+            self.record_block(start, peak, rms);
+        }
+        #[cfg(feature = "profiling")]
+        timer.stop(&mut self.profile.finalize);
+    }
+
+    /// Add measured block starting at frame `start` to results
+    fn record_block(&mut self, start: u64, peak: Box<[f64]>, rms: Box<[f64]>) {
+        for ch in 0..(self.channels as usize) {
+            self.rms[ch].add_value(rms[ch], BINS, self.quantization);
+            self.peaks[ch].add_value(peak[ch], BINS, Quantization::Round);
+            self.worst_blocks[ch] = WorstBlock::worse(
+                self.worst_blocks[ch],
+                WorstBlock {
+                    start,
+                    peak: peak[ch],
+                    rms: rms[ch],
+                },
+            );
+        }
+        trace!(
+            "block {} finalized, peak {:?}, rms {:?}",
+            self.block_number,
+            peak,
+            rms
+        );
+        if let Some(values) = &mut self.block_values {
+            for (ch, values) in values.iter_mut().enumerate() {
+                values.starts.push(start);
+                values.peaks.push(peak[ch]);
+                values.rms.push(rms[ch]);
+            }
+        }
+        if let Some(track) = &mut self.track {
+            let track_start = self.track_boundaries.last().copied().unwrap_or(0);
+            track.record_block(start - track_start, peak.clone(), rms.clone());
+        }
+        let block = BlockResult {
+            index: self.block_number,
+            start,
+            peak,
+            rms,
+        };
+        if let Some(callback) = &mut self.block_callback {
+            callback(block.clone());
+        }
+        self.last_block = Some(block);
+        self.block_number += 1;
+    }
+
+    /// Mark a discontinuity of audio (e.g. stream dropout or splice) at current position.
+    ///
+    /// No block spans the discontinuity, the unfinished block is finalized
+    /// or dropped by `partial` and the state of weighting filter is cleared.
+    /// Position is recorded in [`discontinuities`](Self::discontinuities).
+    pub fn mark_discontinuity(&mut self, partial: PartialBlockPolicy) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+
+        if partial == PartialBlockPolicy::Discard {
+            self.discard_partial_block()?;
+        }
+        self.finish_blocks();
+        self.block.clear();
+        if self.discontinuities.last() != Some(&self.frames) {
+            debug!("discontinuity at frame {}", self.frames);
+            self.discontinuities.push(self.frames);
+        }
+        for stem in &mut self.stems {
+            stem.meter.mark_discontinuity(partial)?;
+        }
+
+        Ok(())
+    }
+
+    /// Mark the end of a track (and start of the next one) at current position
+    /// of a gapless stream.
+    ///
+    /// No block spans the boundary, the unfinished block is finalized as
+    /// a block of its own. Unlike [`mark_discontinuity`](Self::mark_discontinuity)
+    /// the state of weighting filter is kept, as audio continues.
+    /// Results of the meter still cover the whole stream, results of every
+    /// track are returned by [`track_results`](Self::track_results).
+    ///
+    /// Tracks do not detect dual-mono or polarity-inverted channels.
+    /// Marking a boundary without any frames since the previous one does nothing.
+    pub fn mark_track_boundary(&mut self) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+
+        self.finish_blocks();
+        if self.frames == self.track_start() {
+            return Ok(());
+        }
+        self.finish_track()?;
+        debug!("track boundary at frame {}", self.frames);
+        self.track_boundaries.push(self.frames);
+        self.track = Some(Box::new(self.track_meter()?));
+
+        Ok(())
+    }
+
+    /// Returns position (in frames from start) of track in progress
+    fn track_start(&self) -> u64 {
+        self.track_boundaries.last().copied().unwrap_or(0)
+    }
+
+    /// Returns empty meter for blocks of one track
+    fn track_meter(&self) -> Result<DRMeter, Error> {
+        let mut meter = self.empty_clone()?;
+        meter.set_dual_mono_detection(false)?;
+        meter.set_polarity_detection(false)?;
+        Ok(meter)
+    }
+
+    /// Finalize track in progress and keep its results
+    fn finish_track(&mut self) -> Result<(), Error> {
+        let mut track = match self.track.take() {
+            Some(track) => track,
+            // first track holds all blocks so far
+            None => {
+                let mut track = self.track_meter()?;
+                track.merge_blocks(self);
+                Box::new(track)
+            }
+        };
+        track.frames = self.frames - self.track_start();
+        track.finalize()?;
+        self.tracks.push(track.results()?);
+        Ok(())
+    }
+
+    /// Returns results of tracks separated by [`mark_track_boundary`](Self::mark_track_boundary).
+    ///
+    /// Without any marked boundary the whole stream is one track.
+    pub fn track_results(&self) -> Result<Vec<DRResults>, Error> {
+        if !self.finalized() {
+            return Err(Error::NotFinalized);
+        }
+        if self.tracks.is_empty() {
+            return Ok(vec![self.results()?]);
+        }
+        Ok(self.tracks.clone())
+    }
+
+    /// Returns album of tracks separated by [`mark_track_boundary`](Self::mark_track_boundary),
+    /// named `Track 1`, `Track 2`, ...
+    pub fn track_album(&self) -> Result<DRAlbum, Error> {
+        let mut album = DRAlbum::new();
+        for (i, results) in self.track_results()?.into_iter().enumerate() {
+            album.add_track(format!("Track {}", i + 1), results);
+        }
+        Ok(album)
+    }
+
+    /// Drop frames of unfinished block, so they are not measured.
+    ///
+    /// Use this before [`finalize`](Self::finalize) if the stream ended
+    /// on a decode error or was stopped early, and the partial block
+    /// would not be representative. Dropped frames stay counted in
+    /// [`frames`](Self::frames).
+    ///
+    /// Returns the number of dropped frames.
+    pub fn discard_partial_block(&mut self) -> Result<usize, Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+
+        let dropped = self.block.consumed_frames();
+        if dropped != 0 {
+            debug!("discarding partial block of {} frames", dropped);
+            self.block.reset();
+            // next block starts after dropped frames
+            self.block_start += dropped as u64;
+        }
+        for stem in &mut self.stems {
+            stem.meter.discard_partial_block()?;
+        }
+
+        Ok(dropped)
+    }
+
+    /// Finalize instance (marking end of stream)
+    ///
+    /// For streaming, DR values are computed using only fully finished blocks,
+    /// but if you reached the end of stream you can use this function
+    /// to forces finalization of half block if such block even exist.
+    ///
+    /// As part of finalization exact channel DR scores are also calculated and cached
+    ///
+    /// After finalization you cannot add frames to the instance.
+    pub fn finalize(&mut self) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+
+        // finalize half block if exist
+        if self.block.consumed_frames() != 0 {
+            debug!(
+                "finalizing partial block of {} frames (block is {} frames)",
+                self.block.consumed_frames(),
+                self.current_block_frames()
+            );
+        }
+        self.finish_blocks();
+        if !self.track_boundaries.is_empty() && self.frames != self.track_start() {
+            self.finish_track()?;
+        }
+        self.track = None;
+
+        for stem in &mut self.stems {
+            stem.meter.finalize()?;
+        }
+
+        #[cfg(feature = "profiling")]
+        let timer = crate::profile::Timer::start();
+
+        // calculate and cache exact channel values
+        self.channel_dr = Some(
+            (0..self.channels)
+                .map(|ch| self.exact_channel_dr(ch))
+                .collect::<Result<Box<[f64]>, Error>>()?,
+        );
+
+        #[cfg(feature = "profiling")]
+        timer.stop(&mut self.profile.finalize);
+
+        debug!(
+            "finalized after {} frames in {} blocks, channel DR {:?}",
+            self.frames,
+            self.block_number,
+            self.channel_dr.as_deref().unwrap_or_default()
+        );
+
+        Ok(())
+    }
+
+    /***********************
+     *
+     *  Fill with data
+     *
+     ***********************/
+    /// Process frames. This is the generic variant of the different public add_frames() functions
+    /// that are defined below.
+    fn add_frames<'a, T: Sample + 'a, S: Samples<'a, T>>(
+        &mut self,
+        mut src: S,
+    ) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+
+        if src.frames() == 0 {
+            return Ok(());
+        }
+
+        if self.channels == 0 {
+            return Err(Error::NoMem);
+        }
+
+        if let Some(frame) = first_non_finite(&src) {
+            self.first_non_finite
+                .get_or_insert(self.frames + frame as u64);
+            debug!(
+                "non-finite sample at frame {}, policy {:?}",
+                self.frames + frame as u64,
+                self.non_finite_policy
+            );
+            if self.non_finite_policy == NonFinitePolicy::Error {
+                return Err(Error::NonFinite {
+                    frame: self.frames + frame as u64,
+                });
+            }
+            let planes = sanitized(&src, self.non_finite_policy);
+            let planes: Vec<&[T]> = planes.iter().map(Vec::as_slice).collect();
+            return self.add_frames(Planar::new(&planes)?);
+        }
+
+        if self.frames == 0 {
+            debug!(
+                "measuring {} channels at {} Hz, window {} ms, weighting {:?}, \
+                 window function {:?}, histogram {:?}, quantization {:?}, percentile {:?}",
+                self.channels,
+                self.rate,
+                self.window,
+                self.weighting,
+                self.window_function,
+                self.histogram_mode,
+                self.quantization,
+                self.percentile
+            );
+        }
+        self.frames += src.frames() as u64;
+
+        if let Some(peak_hold) = &mut self.peak_hold {
+            peak_hold.update(&src, self.rate);
+        }
+
+        if let Some(pairs) = &mut self.channel_pairs {
+            pairs.update(&src);
+        }
+
+        while src.frames() > 0 {
+            let num_frames = src.frames();
+
+            let block_frames = self.current_block_frames();
+            // window spans planned block (last block of exact duration is longer)
+            if self.window_function == WindowFunction::Hann
+                && self.block.consumed_frames() == 0
+                && self.block.window_len() != Some(block_frames)
+            {
+                self.block.set_window(Some(hann(block_frames)));
+            }
+            let frames_still_needed = block_frames - self.block.consumed_frames();
+            if num_frames >= frames_still_needed {
+                let (current, next) = src.split_at(frames_still_needed);
+
+                self.block.process(current);
+                // one block is now finished
+                self.finalize_block();
+
+                src = next;
+            } else {
+                let (current, next) = src.split_at(num_frames);
+                // currently read frames for block processor
+                self.block.process(current);
+                // we get unfinished block
+
+                // next is empty?
+                src = next;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add frames of any [`Samples`] layout to be processed.
+    ///
+    /// Number of channels of samples must match the meter.
+    pub fn add_samples<'a, T: Sample + 'a, S: Samples<'a, T> + Copy>(
+        &mut self,
+        src: S,
+    ) -> Result<(), Error> {
+        if src.channels() != self.channels as usize {
+            return Err(self.invalid_input());
+        }
+        let remaining = self.max_frames.map_or(u64::MAX, |max_frames| {
+            max_frames.saturating_sub(self.frames)
+        });
+        let limited = src.frames() as u64 > remaining;
+        if limited {
+            debug!(
+                "duration limit reached, dropping {} frames",
+                src.frames() as u64 - remaining
+            );
+        }
+        let src = if limited {
+            src.split_at(remaining as usize).0
+        } else {
+            src
+        };
+
+        self.add_samples_mapped(src)?;
+        // stems have no stems of their own
+        for stem in &mut self.stems {
+            stem.meter
+                .add_samples_mapped(Remapped::new(src, &stem.channels))?;
+        }
+
+        if limited {
+            Err(Error::DurationLimit)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns error of input not matching channels at current position.
+    pub(crate) const fn invalid_input(&self) -> Error {
+        Error::InvalidInput { frame: self.frames }
+    }
+
+    pub(crate) fn add_interleaved<T: Sample>(&mut self, frames: &[T]) -> Result<(), Error> {
+        let src =
+            Interleaved::new(frames, self.channels as usize).map_err(|_| self.invalid_input())?;
+        self.add_samples(src)
+    }
+
+    pub(crate) fn add_planar<T: Sample>(&mut self, frames: &[&[T]]) -> Result<(), Error> {
+        let src = Planar::new(frames).map_err(|_| self.invalid_input())?;
+        self.add_samples(src)
+    }
+
+    /// Reorder channels by channel map and process frames.
+    fn add_samples_mapped<'a, T: Sample + 'a, S: Samples<'a, T>>(
+        &mut self,
+        src: S,
+    ) -> Result<(), Error> {
+        match self.channel_map.take() {
+            Some(map) => {
+                let res = self.add_frames(Remapped::new(src, &map));
+                self.channel_map = Some(map);
+                res
+            }
+            None => self.add_frames(src),
+        }
+    }
+
+    /// Add interleaved frames of unsigned samples (silence is `128`) to be processed.
+    pub fn add_frames_u8(&mut self, frames: &[u8]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_i64(&mut self, frames: &[i64]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_f32(&mut self, frames: &[f32]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames to be processed.
+    pub fn add_frames_f64(&mut self, frames: &[f64]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add planar frames of unsigned samples (silence is `128`) to be processed.
+    pub fn add_frames_planar_u8(&mut self, frames: &[&[u8]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_i64(&mut self, frames: &[&[i64]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames to be processed.
+    pub fn add_frames_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    fn add_arrays<T: Sample, const N: usize>(&mut self, frames: &[[T; N]]) -> Result<(), Error> {
+        const { assert!(N > 0, "frames must have at least one channel") };
+        if N != self.channels as usize {
+            return Err(self.invalid_input());
+        }
+        self.add_interleaved(frames.as_flattened())
+    }
+
+    /// Add frames of `N` channels to be processed.
+    ///
+    /// Frames are used in place as interleaved samples.
+    /// `N` must match the number of channels of the meter.
+    pub fn add_frames_array_i16<const N: usize>(
+        &mut self,
+        frames: &[[i16; N]],
+    ) -> Result<(), Error> {
+        self.add_arrays(frames)
+    }
+
+    /// Add frames of `N` channels to be processed.
+    ///
+    /// Frames are used in place as interleaved samples.
+    /// `N` must match the number of channels of the meter.
+    pub fn add_frames_array_i32<const N: usize>(
+        &mut self,
+        frames: &[[i32; N]],
+    ) -> Result<(), Error> {
+        self.add_arrays(frames)
+    }
+
+    /// Add frames of `N` channels to be processed.
+    ///
+    /// Frames are used in place as interleaved samples.
+    /// `N` must match the number of channels of the meter.
+    pub fn add_frames_array_f32<const N: usize>(
+        &mut self,
+        frames: &[[f32; N]],
+    ) -> Result<(), Error> {
+        self.add_arrays(frames)
+    }
+
+    /// Add frames of `N` channels to be processed.
+    ///
+    /// Frames are used in place as interleaved samples.
+    /// `N` must match the number of channels of the meter.
+    pub fn add_frames_array_f64<const N: usize>(
+        &mut self,
+        frames: &[[f64; N]],
+    ) -> Result<(), Error> {
+        self.add_arrays(frames)
     }
 
     /************
@@ -299,38 +2307,104 @@ impl DRMeter {
      *
      ************/
 
-    /// Get second sample peak from all frames that have been processed for channel.
-    fn second_peak(&self, channel_index: usize) -> Result<f64, Error> {
-        let peaks = &self.peaks[channel_index];
-        let mut first = false;
-        for i in 0..=BINS {
-            if peaks[BINS - i] != 0 {
-                if first {
-                    return Ok((BINS - i) as f64 / BINS as f64);
-                }
-                first = true;
-            }
+    /// Get highest block peak for channel
+    ///
+    /// This is sample peak unless [`DrNumerator::TruePeak`] is selected.
+    /// Returned value is linear (full scale is `1.0`),
+    /// use [`first_peak_db`](Self::first_peak_db) to get dBFS.
+    pub fn first_peak(&self, channel_number: u32) -> Result<f64, Error> {
+        self.check_channel(channel_number)?;
+        if let Some(values) = &self.block_values {
+            let peaks = &values[channel_number as usize].peaks;
+            return Ok(peaks.iter().copied().fold(0.0, f64::max));
+        }
+        let peaks = &self.peaks[channel_number as usize];
+        Ok(peaks
+            .populated()
+            .next_back()
+            .map_or(0.0, |(i, _)| i as f64 / (BINS << peaks.shift()) as f64))
+    }
+
+    /// Get second highest block sample peak for channel, which is used for DR
+    ///
+    /// Returned value is linear (full scale is `1.0`),
+    /// use [`second_peak_db`](Self::second_peak_db) to get dBFS.
+    pub fn second_peak(&self, channel_number: u32) -> Result<f64, Error> {
+        self.check_channel(channel_number)?;
+        if let Some(values) = &self.block_values {
+            let peaks = sorted_descending(&values[channel_number as usize].peaks);
+            return Ok(peaks.get(1).copied().unwrap_or(0.0));
         }
+        let peaks = &self.peaks[channel_number as usize];
+        Ok(peaks
+            .populated()
+            .rev()
+            .nth(1)
+            .map_or(0.0, |(i, _)| i as f64 / (BINS << peaks.shift()) as f64))
+    }
+
+    /// Get highest block sample peak for channel in dBFS
+    ///
+    /// Silent channel has `-inf` dBFS.
+    pub fn first_peak_db(&self, channel_number: u32) -> Result<f64, Error> {
+        Ok(amplitude_to_dbfs(self.first_peak(channel_number)?))
+    }
 
-        Ok(0.0)
+    /// Get second highest block sample peak for channel in dBFS
+    ///
+    /// Channel with less than two non-silent blocks has `-inf` dBFS.
+    pub fn second_peak_db(&self, channel_number: u32) -> Result<f64, Error> {
+        Ok(amplitude_to_dbfs(self.second_peak(channel_number)?))
     }
 
     fn channel_rms_sum(&self, channel_index: usize) -> Result<f64, Error> {
-        let mut j: u32 = 0;
-        let n = (LOUD_FRACTION * self.block_number as f64) as u32;
-        let mut rms_sum = 0.0;
-        for (i, rms) in self.rms[channel_index].iter().enumerate().rev() {
-            if *rms != 0 {
-                rms_sum += sqr(i as f64 / BINS as f64);
-                j += rms;
+        if let Some(values) = &self.block_values {
+            let rms = sorted_descending(&values[channel_index].rms);
+            return Ok(loud_rms_sum_exact(&rms, self.block_number, self.loud_fraction).0);
+        }
+        let rms = &self.rms[channel_index];
+        let populated = rms.populated().rev();
+        let bins = BINS << rms.shift();
+        let fraction = self.loud_fraction;
+        Ok(match self.percentile {
+            Percentile::WholeBins => loud_rms_sum(populated, self.block_number, bins, fraction).0,
+            Percentile::Interpolated => {
+                loud_rms_sum_interpolated(populated, self.block_number, bins, fraction)
             }
+        })
+    }
 
-            if j > n {
-                break;
-            }
+    /// Return blocks that contributed to top 20% RMS of channel
+    pub fn loud_blocks(&self, channel_number: u32) -> Result<LoudBlocks, Error> {
+        self.check_channel(channel_number)?;
+        if let Some(values) = &self.block_values {
+            let rms = sorted_descending(&values[channel_number as usize].rms);
+            return Ok(loud_rms_sum_exact(&rms, self.block_number, self.loud_fraction).1);
         }
+        let rms = &self.rms[channel_number as usize];
+        Ok(loud_rms_sum(
+            rms.populated().rev(),
+            self.block_number,
+            BINS << rms.shift(),
+            self.loud_fraction,
+        )
+        .1)
+    }
+
+    /// Returns top 20% RMS of channel in dBFS
+    ///
+    /// This is the loudness peaks are compared to in DR (the "RMS" column
+    /// of DR logs). Silent channel has `-inf` dBFS.
+    pub fn channel_loud_rms_db(&self, channel_number: u32) -> Result<f64, Error> {
+        self.check_channel(channel_number)?;
+        Ok(amplitude_to_dbfs(self.loud_rms(channel_number as usize)?))
+    }
 
-        Ok(rms_sum)
+    /// Top 20% RMS (linear)
+    fn loud_rms(&self, channel_index: usize) -> Result<f64, Error> {
+        Ok(math::sqrt(
+            self.channel_rms_sum(channel_index)? / (self.loud_fraction * self.block_number as f64),
+        ))
     }
 
     /// Return exact channel DR
@@ -344,11 +2418,7 @@ impl DRMeter {
             Ok(channel_dr[channel_number as usize])
         } else {
             Ok(decibel(
-                self.second_peak(channel_number as usize)?
-                    / f64::sqrt(
-                        self.channel_rms_sum(channel_number as usize)?
-                            / (LOUD_FRACTION * self.block_number as f64),
-                    ),
+                self.second_peak(channel_number)? / self.loud_rms(channel_number as usize)?,
             ))
         }
     }
@@ -358,9 +2428,9 @@ impl DRMeter {
     /// NOTE: DR values are computed using only fully finished blocks,
     /// in case you reached the end of stream you should finalize instance
     /// before getting the results.
-    pub fn channel_dr_score(&self, channel_number: u32) -> Result<u8, Error> {
+    pub fn channel_dr_score(&self, channel_number: u32) -> Result<DRScore, Error> {
         self.check_channel(channel_number)?;
-        Ok(self.exact_channel_dr(channel_number)? as u8)
+        Ok(DRScore::from_exact(self.exact_channel_dr(channel_number)?))
     }
 
     /// Return exact DR
@@ -381,23 +2451,1068 @@ impl DRMeter {
     /// NOTE: DR values are computed using only fully finished blocks,
     /// in case you reached the end of stream you should finalize instance
     /// before getting the results.
-    pub fn dr_score(&self) -> Result<u8, Error> {
-        Ok(self.exact_dr()? as u8)
+    pub fn dr_score(&self) -> Result<DRScore, Error> {
+        Ok(DRScore::from_exact(self.exact_dr()?))
+    }
+
+    /// Return exact DR of blocks finished so far, while frames are still added
+    ///
+    /// The unfinished block is left as is and the instance is not finalized,
+    /// so this can be read periodically from a live stream.
+    /// Returns `None` until two blocks are finished, as DR needs the second highest peak.
+    pub fn snapshot_dr(&self) -> Option<f64> {
+        if self.block_number < 2 {
+            return None;
+        }
+        self.exact_dr().ok()
+    }
+
+    /// Returns accumulated processing time per phase
+    #[cfg(feature = "profiling")]
+    pub fn profile(&self) -> crate::Profile {
+        crate::Profile {
+            peak: self.block.profile().peak,
+            rms: self.block.profile().rms,
+            ..self.profile
+        }
+    }
+
+    /// Counter of conversion time
+    #[cfg(feature = "profiling")]
+    pub(crate) fn conversion_time(&mut self) -> &mut std::time::Duration {
+        &mut self.profile.conversion
+    }
+
+    /// Dump internal state
+    #[cfg(feature = "diagnostics")]
+    pub fn diagnostics(&self) -> crate::Diagnostics {
+        crate::Diagnostics {
+            rate: self.rate,
+            channels: self.channels,
+            window: self.window,
+            histogram_mode: self.histogram_mode,
+            peak_headroom: self.peak_headroom,
+            bins: self.histogram_bins(),
+            block_number: self.block_number,
+            partial_block: crate::PartialBlock {
+                consumed_frames: self.block.consumed_frames(),
+                needed_frames: self.needed_frames,
+                sample_peak: self.block.sample_peak().to_vec(),
+                sum2: self.block.sum2(),
+            },
+            peak_bins: self.peaks.iter().map(|h| self.common_bins(h)).collect(),
+            rms_bins: self.rms.iter().map(|h| self.common_bins(h)).collect(),
+            channel_dr: self.channel_dr.as_ref().map(|dr| dr.to_vec()),
+        }
+    }
+
+    /// Finest refinement of adaptive histograms
+    fn max_shift(&self) -> u32 {
+        self.peaks
+            .iter()
+            .chain(self.rms.iter())
+            .map(Histogram::shift)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Populated bins of histogram with `BINS << max_shift()` bins per full scale
+    fn common_bins(&self, histogram: &Histogram) -> Vec<(usize, u32)> {
+        let shift = self.max_shift() - histogram.shift();
+        histogram
+            .populated()
+            .map(|(bin, count)| (bin << shift, count))
+            .collect()
+    }
+
+    /// Return results of finalized instance
+    ///
+    /// Results hold scores and populated histogram bins,
+    /// but not the meter itself, so they can be stored for later.
+    pub fn results(&self) -> Result<DRResults, Error> {
+        if !self.finalized() {
+            return Err(Error::NotFinalized);
+        }
+
+        let channels = (0..self.channels)
+            .map(|ch| {
+                Ok(ChannelResults {
+                    dr: self.exact_channel_dr(ch)?,
+                    first_peak: self.first_peak(ch)?,
+                    second_peak: self.second_peak(ch)?,
+                    rms: self.loud_rms(ch as usize)?,
+                    peak_histogram: self.common_bins(&self.peaks[ch as usize]),
+                    rms_histogram: self.common_bins(&self.rms[ch as usize]),
+                    peak_overflows: self.peak_overflows(ch)?,
+                    rms_overflows: self.rms_overflows(ch)?,
+                    worst_block: self.worst_block(ch)?,
+                    // histograms of results cannot reproduce exact selection
+                    exact_loud_blocks: self
+                        .exact_mode()
+                        .then(|| self.loud_blocks(ch))
+                        .transpose()?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(DRResults {
+            rate: self.rate,
+            window: self.window,
+            blocks: self.block_number,
+            frames: self.frames,
+            bins: self.histogram_bins(),
+            channels,
+            dual_mono: self.dual_mono_pairs().unwrap_or_default(),
+            inverted: self.inverted_pairs().unwrap_or_default(),
+            dr_numerator: self.dr_numerator,
+            loud_fraction: self.loud_fraction,
+            metadata: Default::default(),
+        })
     }
 
     /// Get average exact DR score across multiple instances.
-    /// This can be used to calculate Albums DR score
+    /// This can be used to calculate Albums DR score,
+    /// see [`DRAlbum`](crate::DRAlbum) for track names and reports.
     pub fn exact_dr_multiple<'a>(iter: impl Iterator<Item = &'a Self>) -> Result<f64, Error> {
+        Self::exact_dr_multiple_with(iter, Aggregation::Mean)
+    }
+
+    /// Get exact DR score across multiple instances aggregated with given statistic
+    pub fn exact_dr_multiple_with<'a>(
+        iter: impl Iterator<Item = &'a Self>,
+        aggregation: Aggregation,
+    ) -> Result<f64, Error> {
         let h = iter
             .map(|d| d.exact_dr())
             .collect::<Result<Vec<f64>, _>>()?;
 
-        Ok(h.iter().sum::<f64>() / h.len() as f64)
+        Ok(aggregation.apply(h))
     }
 
     /// Get average DR score across multiple instances.
     /// This can be used to calculate Albums DR score
-    pub fn dr_score_multiple<'a>(iter: impl Iterator<Item = &'a Self>) -> Result<u8, Error> {
-        Ok(Self::exact_dr_multiple(iter)? as u8)
+    pub fn dr_score_multiple<'a>(iter: impl Iterator<Item = &'a Self>) -> Result<DRScore, Error> {
+        Ok(DRScore::from_exact(Self::exact_dr_multiple(iter)?))
+    }
+
+    /// Get DR score across multiple instances aggregated with given statistic
+    pub fn dr_score_multiple_with<'a>(
+        iter: impl Iterator<Item = &'a Self>,
+        aggregation: Aggregation,
+    ) -> Result<DRScore, Error> {
+        Ok(DRScore::from_exact(Self::exact_dr_multiple_with(
+            iter,
+            aggregation,
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    /// Frames of 10ms blocks (at 1kHz) with given peaks
+    fn blocks(peaks: &[f32]) -> Vec<f32> {
+        peaks
+            .iter()
+            .flat_map(|peak| {
+                let mut block = [peak / 2.0; 10];
+                block[5] = *peak;
+                block
+            })
+            .collect()
+    }
+
+    #[test]
+    fn builder() {
+        let invalid = |option| Err(Error::InvalidConfig { option });
+        assert_eq!(
+            DRMeter::builder().rate(48_000).build().map(|_| ()),
+            invalid(ConfigOption::Channels)
+        );
+        assert_eq!(
+            DRMeter::new(65, 48_000).map(|_| ()),
+            invalid(ConfigOption::Channels)
+        );
+        assert_eq!(DRMeter::new(2, 8).map(|_| ()), invalid(ConfigOption::Rate));
+        assert_eq!(
+            DRMeter::new_with_window(2, 48_000, 5).map(|_| ()),
+            invalid(ConfigOption::Window)
+        );
+        // block would have no frame
+        assert_eq!(
+            DRMeter::new_with_window(2, 16, 10).map(|_| ()),
+            invalid(ConfigOption::Window)
+        );
+        assert_eq!(
+            DRMeter::builder()
+                .channels(2)
+                .rate(48_000)
+                .peak_headroom(30.0)
+                .build()
+                .map(|_| ()),
+            invalid(ConfigOption::PeakHeadroom)
+        );
+        for fraction in [0.0, -0.2, 1.5, f64::NAN] {
+            assert_eq!(
+                DRMeter::builder()
+                    .channels(2)
+                    .rate(48_000)
+                    .loud_fraction(fraction)
+                    .build()
+                    .map(|_| ()),
+                invalid(ConfigOption::LoudFraction),
+                "{fraction}"
+            );
+        }
+
+        let dr = DRMeter::builder()
+            .channels(2)
+            .rate(48_000)
+            .window(1000)
+            .peak_mode(PeakMode::True)
+            .peak_headroom(3.0)
+            .weighting(Weighting::A)
+            .loud_fraction(1.0)
+            .build()
+            .unwrap();
+        assert_eq!((dr.channels(), dr.rate(), dr.window()), (2, 48_000, 1000));
+        assert_eq!(dr.loud_fraction(), 1.0);
+        assert_eq!(dr.peak_mode(), PeakMode::True);
+        assert_eq!(dr.peak_headroom(), 3.0);
+        assert_eq!(dr.weighting(), Weighting::A);
+    }
+
+    #[test]
+    fn expected_frames() {
+        let mut dr = DRMeter::new(1, 1000).unwrap();
+        assert_eq!(dr.progress(), None);
+        dr.set_expected_frames(400);
+        dr.add_frames_f32(&[0.5; 100]).unwrap();
+        assert_float_eq!(dr.progress().unwrap(), 0.25, abs <= 1e-9);
+        dr.finalize().unwrap();
+        assert_eq!(dr.missing_frames(), Some(300));
+    }
+
+    #[test]
+    fn exact_duration() {
+        let data = blocks(&[0.5, 0.5, 0.5]);
+
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&data[..25]).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 3);
+
+        // remainder of 5 frames is part of second block
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_exact_duration(25).unwrap();
+        dr.add_frames_f32(&data[..25]).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 2);
+        assert_eq!(dr.set_exact_duration(25), Err(Error::AlreadyStarted));
+    }
+
+    #[test]
+    fn frame_arrays() {
+        let frames: Vec<[f32; 2]> = (0..4000)
+            .map(|i| {
+                let s = f32::sin(i as f32 * 0.01);
+                [s, s * 0.5]
+            })
+            .collect();
+        let mut arrays = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        arrays.add_frames_array_f32(&frames).unwrap();
+        arrays.finalize().unwrap();
+        let mut interleaved = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        interleaved.add_frames_f32(frames.as_flattened()).unwrap();
+        interleaved.finalize().unwrap();
+        assert_eq!(arrays.exact_dr(), interleaved.exact_dr());
+
+        let mut dr = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        assert_eq!(
+            dr.add_frames_array_i16(&[[0i16; 3]]),
+            Err(Error::InvalidInput { frame: 0 })
+        );
+    }
+
+    #[test]
+    fn unsigned_input() {
+        let signed: Vec<i16> = (0..4000)
+            .map(|i| (f32::sin(i as f32 * 0.01) * (1 + i / 700) as f32 * 20.0) as i16 * 256)
+            .collect();
+        let unsigned: Vec<u8> = signed.iter().map(|s| (s / 256 + 128) as u8).collect();
+        let mut expected = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        expected.add_frames_i16(&signed).unwrap();
+        expected.finalize().unwrap();
+
+        let mut interleaved = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        interleaved.add_frames_u8(&unsigned).unwrap();
+        interleaved.finalize().unwrap();
+        let mut planar = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        planar.add_frames_planar_u8(&[&unsigned]).unwrap();
+        planar.finalize().unwrap();
+        for dr in [interleaved, planar] {
+            assert_eq!(dr.first_peak(0), expected.first_peak(0));
+            assert_float_eq!(
+                dr.exact_dr().unwrap(),
+                expected.exact_dr().unwrap(),
+                abs <= 1e-9
+            );
+        }
+
+        // silence is at the offset
+        let mut silent = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        silent.add_frames_u8(&[128; 30]).unwrap();
+        assert_eq!(silent.first_peak(0), Ok(0.0));
+    }
+
+    #[test]
+    fn i64_input() {
+        let narrow: Vec<i32> = (0..4000)
+            .map(|i| (f64::sin(i as f64 * 0.01) * (1 + i / 700) as f64 * 1e8) as i32)
+            .flat_map(|s| [s, s / 3])
+            .collect();
+        let wide: Vec<i64> = narrow.iter().map(|&s| (s as i64) << 32).collect();
+        let mut expected = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        expected.add_frames_i32(&narrow).unwrap();
+        expected.finalize().unwrap();
+
+        let mut interleaved = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        interleaved.add_frames_i64(&wide).unwrap();
+        interleaved.finalize().unwrap();
+        let planes: Vec<Vec<i64>> = (0..2)
+            .map(|ch| wide.iter().skip(ch).step_by(2).copied().collect())
+            .collect();
+        let mut planar = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        planar
+            .add_frames_planar_i64(&[&planes[0], &planes[1]])
+            .unwrap();
+        planar.finalize().unwrap();
+        for dr in [interleaved, planar] {
+            assert_eq!(dr.first_peak(1), expected.first_peak(1));
+            assert_float_eq!(
+                dr.exact_dr().unwrap(),
+                expected.exact_dr().unwrap(),
+                abs <= 1e-9
+            );
+        }
+    }
+
+    #[test]
+    fn samples() {
+        let left: Vec<f32> = (0..4000).map(|i| f32::sin(i as f32 * 0.01)).collect();
+        let right: Vec<f32> = left.iter().map(|s| s * 0.3).collect();
+        let interleaved: Vec<f32> = Iterator::zip(left.iter(), right.iter())
+            .flat_map(|(l, r)| [*l, *r])
+            .collect();
+        let mut expected = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        expected.add_frames_f32(&interleaved).unwrap();
+        expected.finalize().unwrap();
+
+        let mut dr = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        dr.add_samples(Interleaved::new(&interleaved[..2000], 2).unwrap())
+            .unwrap();
+        let planes = [&left[1000..], &right[1000..]];
+        dr.add_samples(Planar::new(&planes).unwrap()).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+
+        let mut dr = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        assert_eq!(
+            dr.add_samples(Planar::new(&[&left[..]]).unwrap()),
+            Err(Error::InvalidInput { frame: 0 })
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        let mut dr = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        dr.add_frames_f32(&[0.5; 30]).unwrap();
+        let err = dr.add_frames_f32(&[0.5; 5]).unwrap_err();
+        assert_eq!(err, Error::InvalidInput { frame: 15 });
+        assert_eq!(err.frame(), Some(15));
+        assert_eq!(
+            dr.add_frames_planar_f32(&[&[0.5; 3], &[0.5; 2]]),
+            Err(Error::InvalidInput { frame: 15 })
+        );
+        assert_eq!(Error::Finalized.frame(), None);
+    }
+
+    #[test]
+    fn non_finite() {
+        let mut data = blocks(&[0.5, 0.25]);
+        data[12] = f32::NAN;
+        data[13] = f32::INFINITY;
+
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&data[..5]).unwrap();
+        assert_eq!(
+            dr.add_frames_f32(&data[5..]),
+            Err(Error::NonFinite { frame: 12 })
+        );
+        assert_eq!(dr.first_non_finite(), Some(12));
+        assert_eq!(dr.frames(), 5);
+
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_non_finite_policy(NonFinitePolicy::Zero);
+        dr.add_frames_f32(&data).unwrap();
+        assert_float_eq!(dr.second_peak(0).unwrap(), 0.25, abs <= 1.0 / BINS as f64);
+
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_non_finite_policy(NonFinitePolicy::Clamp);
+        dr.add_frames_f32(&data).unwrap();
+        assert_float_eq!(dr.first_peak(0).unwrap(), 1.0, abs <= 1.0 / BINS as f64);
+        assert_float_eq!(dr.second_peak(0).unwrap(), 0.5, abs <= 1.0 / BINS as f64);
+        assert!(dr.exact_dr().unwrap().is_finite());
+    }
+
+    #[test]
+    fn peak_headroom() {
+        let data = blocks(&[1.5, 1.2, 0.5]);
+
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        assert_float_eq!(dr.first_peak(0).unwrap(), 1.0, abs <= 1e-9);
+
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_peak_headroom(6.0).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        assert_float_eq!(dr.first_peak(0).unwrap(), 1.5, abs <= 1.0 / BINS as f64);
+        assert_float_eq!(dr.second_peak(0).unwrap(), 1.2, abs <= 1.0 / BINS as f64);
+        assert_eq!(dr.set_peak_headroom(0.0), Err(Error::AlreadyStarted));
+    }
+
+    #[test]
+    fn peaks_db() {
+        let mut dr = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        let data: Vec<f32> = blocks(&[1.0, 0.5, 0.25])
+            .into_iter()
+            .flat_map(|s| [s, 0.0])
+            .collect();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        assert_float_eq!(dr.first_peak_db(0).unwrap(), 0.0, abs <= 1e-9);
+        assert_float_eq!(
+            dr.second_peak_db(0).unwrap(),
+            -20.0 * std::f64::consts::LOG10_2,
+            abs <= 1e-3
+        );
+        assert_eq!(dr.first_peak_db(1), Ok(f64::NEG_INFINITY));
+        assert_eq!(dr.second_peak_db(2), Err(Error::InvalidChannelIndex));
+    }
+
+    #[test]
+    fn adaptive_histogram() {
+        let peaks = [0.9, 0.7, 0.61, 0.5, 0.43, 0.3, 0.2, 0.1, 0.05, 0.01];
+        let measure = |mode, level: f32| {
+            let data = blocks(&peaks.map(|p| p * level));
+            let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+            dr.set_histogram_mode(mode).unwrap();
+            dr.add_frames_f32(&data).unwrap();
+            dr.finalize().unwrap();
+            let results = dr.results().unwrap();
+            assert_eq!(results.loud_blocks(0).unwrap(), dr.loud_blocks(0).unwrap());
+            dr.exact_dr().unwrap()
+        };
+
+        // DR does not depend on level, but resolution of quiet material does
+        let reference = measure(HistogramMode::Dense, 1.0);
+        let dense = measure(HistogramMode::Dense, 1e-3);
+        let adaptive = measure(HistogramMode::Adaptive, 1e-3);
+        assert!((adaptive - reference).abs() < (dense - reference).abs());
+        assert_float_eq!(adaptive, reference, abs <= 0.01);
+    }
+
+    #[test]
+    fn overflows() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&blocks(&[0.5, 1.0])).unwrap();
+        assert_eq!(dr.peak_overflows(0), Ok(0));
+        assert_eq!(dr.rms_overflows(0), Ok(0));
+
+        dr.add_frames_f32(&blocks(&[1.5])).unwrap();
+        // full scale square wave has RMS above full scale
+        dr.add_frames_f32(&[1.0; 10]).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.peak_overflows(0), Ok(1));
+        assert_eq!(dr.rms_overflows(0), Ok(2));
+        assert_eq!(dr.results().unwrap().peak_overflows(0), Ok(1));
+    }
+
+    #[test]
+    fn histograms() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&blocks(&[0.5, 0.25, 0.5])).unwrap();
+        assert_eq!(dr.peak_histogram(1), Err(Error::InvalidChannelIndex));
+        assert_eq!(dr.histogram_bins(), BINS);
+
+        let peaks = dr.peak_histogram(0).unwrap();
+        assert_eq!(peaks, [(BINS / 4, 1), (BINS / 2, 2)]);
+        assert_eq!(dr.bin_value(peaks[1].0), 0.5);
+        let rms = dr.rms_histogram(0).unwrap();
+        assert_eq!(rms.iter().map(|&(_, count)| count).sum::<u32>(), 3);
+
+        dr.finalize().unwrap();
+        let results = dr.results().unwrap();
+        assert_eq!(results.peak_histogram(0).unwrap(), peaks.as_slice());
+        assert_eq!(results.rms_histogram(0).unwrap(), rms.as_slice());
+        assert_eq!(results.bin_value(BINS / 4), 0.25);
+    }
+
+    #[test]
+    fn interpolated_percentile() {
+        let mut data = vec![0.4; 10];
+        data.extend([0.2; 60]);
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_percentile(Percentile::Interpolated).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(
+            dr.set_percentile(Percentile::WholeBins),
+            Err(Error::Finalized)
+        );
+
+        // top 1.4 blocks: one at 0.4 and 0.4 of block at 0.2 (RMS of constant is √2 higher)
+        let expected = f64::sqrt(2.0 * (0.4 * 0.4 + 0.4 * 0.2 * 0.2) / 1.4);
+        assert_float_eq!(dr.results().unwrap().rms(0).unwrap(), expected, abs <= 1e-4);
+    }
+
+    #[test]
+    fn channel_map() {
+        let mut dr = DRMeter::new_with_window(3, 1000, 10).unwrap();
+        assert_eq!(dr.set_channel_map(Some(&[0, 1])), Err(Error::ArgOutside));
+        assert_eq!(dr.set_channel_map(Some(&[0, 1, 1])), Err(Error::ArgOutside));
+        assert_eq!(dr.set_channel_map(Some(&[0, 1, 3])), Err(Error::ArgOutside));
+        dr.set_channel_map(Some(&[2, 0, 1])).unwrap();
+
+        let planes: [Vec<f32>; 3] = [blocks(&[0.2]), blocks(&[0.5]), blocks(&[0.8])];
+        let interleaved: Vec<f32> = (0..10)
+            .flat_map(|i| planes.iter().map(move |plane| plane[i]))
+            .collect();
+        let mut planar = dr.empty_clone().unwrap();
+        dr.add_frames_f32(&interleaved).unwrap();
+        assert_eq!(dr.set_channel_map(None), Err(Error::AlreadyStarted));
+        let planes: Vec<&[f32]> = planes.iter().map(Vec::as_slice).collect();
+        planar.add_frames_planar_f32(&planes).unwrap();
+
+        for dr in [dr, planar] {
+            assert_float_eq!(dr.first_peak(0).unwrap(), 0.8, abs <= 1e-4);
+            assert_float_eq!(dr.first_peak(1).unwrap(), 0.2, abs <= 1e-4);
+            assert_float_eq!(dr.first_peak(2).unwrap(), 0.5, abs <= 1e-4);
+        }
+    }
+
+    #[test]
+    fn max_duration() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_max_duration(Some(std::time::Duration::from_millis(25)));
+        assert_eq!(dr.max_frames(), Some(25));
+        dr.add_frames_f32(&blocks(&[0.5, 0.6])).unwrap();
+        assert_eq!(
+            dr.add_frames_f32(&blocks(&[0.7, 0.8])),
+            Err(Error::DurationLimit)
+        );
+        assert_eq!(dr.frames(), 25);
+        assert_eq!(dr.add_frames_f32(&[0.1]), Err(Error::DurationLimit));
+        assert_eq!(dr.add_frames_f32(&[]), Ok(()));
+        dr.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 3);
+        assert_float_eq!(dr.first_peak(0).unwrap(), 0.6, abs <= 1e-4);
+    }
+
+    #[test]
+    fn last_block() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&blocks(&[0.5])[..7]).unwrap();
+        assert_eq!(dr.last_block(), None);
+        dr.add_frames_f32(&blocks(&[0.5, 0.6])[7..]).unwrap();
+        let block = dr.last_block().unwrap();
+        assert_eq!((block.index, block.start), (1, 10));
+        assert_float_eq!(block.peak[0], 0.6, abs <= 1e-6);
+        // nine samples at 0.3 and one at 0.6
+        assert_float_eq!(block.rms[0], f64::sqrt(2.0 * 1.17 / 10.0), abs <= 1e-6);
+    }
+
+    #[test]
+    fn block_callback() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_block_callback(move |block| tx.send(block).unwrap());
+        dr.add_frames_f32(&blocks(&[0.5, 0.6, 0.7])[..25]).unwrap();
+        assert_eq!(rx.try_iter().map(|b| b.index).collect::<Vec<_>>(), [0, 1]);
+        dr.finalize().unwrap();
+        let last = rx.try_recv().unwrap();
+        assert_eq!((last.index, last.start), (2, 20));
+        assert_eq!(Some(&last), dr.last_block());
+
+        dr.clear_block_callback();
+        dr.reset();
+        dr.add_frames_f32(&blocks(&[0.5])).unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn block_starts_after_boundary() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_exact_mode(true).unwrap();
+        dr.set_block_callback(move |block| tx.send(block.start).unwrap());
+        // short block of 5 frames with high crest factor
+        dr.add_frames_f32(&[0.1, 0.1, 0.1, 0.1, 0.9]).unwrap();
+        dr.mark_track_boundary().unwrap();
+        dr.add_frames_f32(&blocks(&[0.5, 0.9])).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), [0, 5, 15]);
+        assert_eq!(dr.last_block().unwrap().start, 15);
+        assert_eq!(dr.worst_block(0).unwrap().unwrap().start, 5);
+
+        let mut csv = Vec::new();
+        dr.write_blocks_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let times: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .map(|row| row.split(',').nth(1).unwrap())
+            .collect();
+        assert_eq!(times, ["0.000", "0.005", "0.015"]);
+
+        // track blocks start at the start of track
+        let tracks = dr.track_results().unwrap();
+        assert_eq!(tracks[1].worst_block(0).unwrap().unwrap().start, 0);
+    }
+
+    #[test]
+    fn snapshot_dr() {
+        let data = blocks(&[0.5, 0.9, 0.3, 0.7]);
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&data[..15]).unwrap();
+        assert_eq!(dr.snapshot_dr(), None);
+        dr.add_frames_f32(&data[15..25]).unwrap();
+        let snapshot = dr.snapshot_dr().unwrap();
+        assert!(!dr.finalized());
+
+        let mut two_blocks = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        two_blocks.add_frames_f32(&data[..20]).unwrap();
+        two_blocks.finalize().unwrap();
+        assert_eq!(snapshot, two_blocks.exact_dr().unwrap());
+
+        // unfinished block is measured once the stream goes on
+        dr.add_frames_f32(&data[25..]).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 4);
+        assert_eq!(dr.snapshot_dr(), dr.exact_dr().ok());
+    }
+
+    #[test]
+    fn discontinuity() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&blocks(&[0.5, 0.6])[..16]).unwrap();
+        dr.mark_discontinuity(PartialBlockPolicy::Finalize).unwrap();
+        dr.mark_discontinuity(PartialBlockPolicy::Finalize).unwrap();
+        dr.add_frames_f32(&blocks(&[0.9])[..7]).unwrap();
+        dr.mark_discontinuity(PartialBlockPolicy::Discard).unwrap();
+        dr.add_frames_f32(&blocks(&[0.7])).unwrap();
+        assert_eq!(dr.discontinuities(), &[16, 23]);
+        dr.finalize().unwrap();
+        // partial block of 0.6 is measured, the one of 0.9 is not
+        assert_eq!(dr.results().unwrap().blocks(), 3);
+        assert_float_eq!(dr.first_peak(0).unwrap(), 0.7, abs <= 1e-4);
+        assert_float_eq!(dr.second_peak(0).unwrap(), 0.6, abs <= 1e-4);
+        assert_eq!(
+            dr.mark_discontinuity(PartialBlockPolicy::Finalize),
+            Err(Error::Finalized)
+        );
+
+        // blocks after a short block start where it ended
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&blocks(&[0.5, 0.6])[..15]).unwrap();
+        dr.mark_discontinuity(PartialBlockPolicy::Finalize).unwrap();
+        assert_eq!(dr.last_block().unwrap().start, 10);
+        dr.add_frames_f32(&blocks(&[0.9, 0.7])).unwrap();
+        assert_eq!(dr.last_block().unwrap().start, 25);
+    }
+
+    #[test]
+    fn track_boundaries() {
+        let tracks = [blocks(&[0.5, 0.6, 0.4]), blocks(&[0.9, 0.8, 0.2, 0.3])];
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.mark_track_boundary().unwrap();
+        dr.add_frames_f32(&tracks[0]).unwrap();
+        assert_eq!(dr.track_results(), Err(Error::NotFinalized));
+        dr.mark_track_boundary().unwrap();
+        dr.mark_track_boundary().unwrap();
+        dr.add_frames_f32(&tracks[1]).unwrap();
+        assert_eq!(dr.track_boundaries(), &[30]);
+        dr.finalize().unwrap();
+        assert_eq!(dr.frames(), 70);
+        assert_eq!(dr.mark_track_boundary(), Err(Error::Finalized));
+
+        let results = dr.track_results().unwrap();
+        assert_eq!(results.len(), 2);
+        for (results, track) in results.iter().zip(&tracks) {
+            let mut separate = DRMeter::new_with_window(1, 1000, 10).unwrap();
+            separate.add_frames_f32(track).unwrap();
+            separate.finalize().unwrap();
+            assert_eq!(results.frames(), separate.frames());
+            assert_eq!(results.exact_dr(), separate.exact_dr().unwrap());
+        }
+        let album = dr.track_album().unwrap();
+        assert_eq!(album.track(1).unwrap().name, "Track 2");
+        assert_float_eq!(
+            album.exact_dr(),
+            (results[0].exact_dr() + results[1].exact_dr()) / 2.0,
+            abs <= 1e-9
+        );
+
+        // without boundaries the stream is one track
+        let mut single = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        single.add_frames_f32(&tracks[1]).unwrap();
+        single.finalize().unwrap();
+        assert_eq!(
+            single.track_results().unwrap(),
+            vec![single.results().unwrap()]
+        );
+    }
+
+    #[test]
+    fn reset() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.set_peak_headroom(3.0).unwrap();
+        dr.set_exact_duration(25).unwrap();
+        dr.add_frames_f32(&blocks(&[1.2, 0.5, 0.5])).unwrap();
+        dr.finalize().unwrap();
+
+        dr.reset();
+        assert_eq!(dr.frames(), 0);
+        assert_eq!(dr.last_block(), None);
+        assert_eq!(dr.results().map(|r| r.blocks()), Err(Error::NotFinalized));
+        assert_eq!(dr.peak_headroom(), 3.0);
+        let data = blocks(&[0.5, 0.6, 0.4, 0.3]);
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+
+        let mut fresh = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        fresh.set_peak_headroom(3.0).unwrap();
+        fresh.add_frames_f32(&data).unwrap();
+        fresh.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 4);
+        assert_eq!(dr.exact_dr(), fresh.exact_dr());
+        assert_eq!(dr.first_peak(0), fresh.first_peak(0));
+    }
+
+    #[test]
+    fn merge() {
+        let data = blocks(&[0.5, 0.6, 0.4, 0.9, 0.3]);
+        let mut sequential = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        sequential.add_frames_f32(&data).unwrap();
+        sequential.finalize().unwrap();
+
+        let mut first = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        first.add_frames_f32(&data[..20]).unwrap();
+        let mut second = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        second.add_frames_f32(&data[20..35]).unwrap();
+        assert_eq!(first.merge(&second), Err(Error::NotFinalized));
+        second.finalize().unwrap();
+        first.merge(&second).unwrap();
+        let mut third = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        third.add_frames_f32(&data[35..]).unwrap();
+        third.finalize().unwrap();
+        first.merge(&third).unwrap();
+        first.finalize().unwrap();
+
+        // partial blocks of chunks are blocks of their own
+        assert_eq!(first.results().unwrap().blocks(), 6);
+        assert_eq!(first.frames(), sequential.frames());
+        assert_eq!(first.first_peak(0), sequential.first_peak(0));
+        assert_eq!(first.second_peak(0), sequential.second_peak(0));
+        assert_eq!(first.merge(&third), Err(Error::Finalized));
+
+        let mut other = DRMeter::new_with_window(1, 1000, 20).unwrap();
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        assert_eq!(dr.merge(&other), Err(Error::Mismatch));
+        other = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        other.set_weighting(Weighting::A).unwrap();
+        assert_eq!(dr.merge(&other), Err(Error::Mismatch));
+    }
+
+    #[test]
+    #[cfg(feature = "bincode")]
+    fn checkpoint() {
+        let data: Vec<f32> = (0..48_000 * 10)
+            .map(|i| f32::sin(i as f32 * 0.01) * (i % 7) as f32 / 7.0)
+            .collect();
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.set_weighting(Weighting::A).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+
+        // checkpoint in the middle of a block
+        let mut first = DRMeter::new(2, 48_000).unwrap();
+        first.set_weighting(Weighting::A).unwrap();
+        first.add_frames_f32(&data[..100_001 * 2]).unwrap();
+        let saved = bincode::serialize(&first).unwrap();
+        drop(first);
+        let mut resumed: DRMeter = bincode::deserialize(&saved).unwrap();
+        resumed.add_frames_f32(&data[100_001 * 2..]).unwrap();
+        resumed.finalize().unwrap();
+        assert_eq!(resumed.exact_dr(), dr.exact_dr());
+        assert_eq!(resumed.frames(), dr.frames());
+    }
+
+    #[test]
+    fn exact_mode() {
+        let peaks = [0.512_345f32, 0.612_345, 0.412_345, 0.912_345, 0.312_345];
+        let data = blocks(&peaks);
+        let mut exact = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        exact.set_exact_mode(true).unwrap();
+        exact.add_frames_f32(&data).unwrap();
+        exact.finalize().unwrap();
+        assert!(exact.exact_mode());
+        assert_eq!(exact.set_exact_mode(false), Err(Error::AlreadyStarted));
+
+        // nine samples at half of peak and one at peak
+        let rms = |peak: f32| peak as f64 * f64::sqrt(2.0 * 3.25 / 10.0);
+        let expected = decibel(peaks[1] as f64 / rms(peaks[3]));
+        assert_eq!(exact.first_peak(0), Ok(peaks[3] as f64));
+        assert_eq!(exact.second_peak(0), Ok(peaks[1] as f64));
+        assert_float_eq!(exact.exact_dr().unwrap(), expected, abs <= 1e-6);
+        let loud = exact.loud_blocks(0).unwrap();
+        assert_eq!((loud.target, loud.count), (1, 1));
+
+        // histogram bins quantize peaks
+        let mut binned = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        binned.add_frames_f32(&data).unwrap();
+        binned.finalize().unwrap();
+        assert_ne!(binned.second_peak(0), Ok(peaks[1] as f64));
+    }
+
+    #[test]
+    fn dr_timeline() {
+        let peaks = [0.5, 0.6, 0.4, 0.9, 0.3, 0.7];
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        assert_eq!(dr.dr_timeline(3), Ok(None));
+        dr.set_exact_mode(true).unwrap();
+        assert_eq!(dr.dr_timeline(0), Err(Error::ArgOutside));
+        dr.add_frames_f32(&blocks(&peaks)).unwrap();
+        dr.finalize().unwrap();
+
+        let timeline = dr.dr_timeline(4).unwrap().unwrap();
+        assert_eq!(timeline.len(), 6);
+        assert_eq!(timeline[0], f64::NEG_INFINITY);
+        for (i, &value) in timeline.iter().enumerate().skip(1) {
+            let mut window = DRMeter::new_with_window(1, 1000, 10).unwrap();
+            window.set_exact_mode(true).unwrap();
+            window
+                .add_frames_f32(&blocks(&peaks[(i + 1).saturating_sub(4)..=i]))
+                .unwrap();
+            window.finalize().unwrap();
+            assert_float_eq!(value, window.exact_dr().unwrap(), abs <= 1e-9);
+        }
+        let whole = dr.dr_timeline(6).unwrap().unwrap();
+        assert_float_eq!(whole[5], dr.exact_dr().unwrap(), abs <= 1e-9);
+    }
+
+    #[test]
+    fn blocks_csv() {
+        let mut dr = DRMeter::new_with_window(2, 1000, 10).unwrap();
+        assert_eq!(
+            dr.write_blocks_csv(Vec::new()).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+        dr.set_exact_mode(true).unwrap();
+        let data: Vec<f32> = blocks(&[0.5, 1.0, 0.0])
+            .into_iter()
+            .flat_map(|s| [s, s / 2.0])
+            .collect();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+
+        let mut csv = Vec::new();
+        dr.write_blocks_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "block,time,peak_1,rms_1,peak_2,rms_2\n\
+             0,0.000,-6.02,-7.89,-12.04,-13.91\n\
+             1,0.010,0.00,-1.87,-6.02,-7.89\n\
+             2,0.020,-inf,-inf,-inf,-inf\n"
+        );
+    }
+
+    #[test]
+    fn discard_partial_block() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&blocks(&[0.5, 0.6])).unwrap();
+        dr.add_frames_f32(&blocks(&[0.9])[..7]).unwrap();
+        assert_eq!(dr.discard_partial_block(), Ok(7));
+        assert_eq!(dr.discard_partial_block(), Ok(0));
+        assert_eq!(dr.frames(), 27);
+        assert_eq!(dr.set_exact_duration(25), Err(Error::AlreadyStarted));
+        dr.add_frames_f32(&blocks(&[0.4])).unwrap();
+        // dropped frames are not part of any block, but still shift next block
+        assert_eq!(dr.last_block().unwrap().start, 27);
+        dr.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 3);
+        assert_float_eq!(dr.first_peak(0).unwrap(), 0.6, abs <= 1e-4);
+        assert_eq!(dr.discard_partial_block(), Err(Error::Finalized));
+    }
+
+    #[test]
+    fn stems() {
+        let mut dr = DRMeter::new_with_window(3, 1000, 10).unwrap();
+        assert_eq!(dr.add_stem("dialogue", &[3]), Err(Error::ArgOutside));
+        dr.add_stem("dialogue", &[1]).unwrap();
+        dr.add_stem("music", &[0, 2]).unwrap();
+
+        let planes = [
+            blocks(&[0.2, 0.4]),
+            blocks(&[0.5, 0.5]),
+            blocks(&[0.8, 0.3]),
+        ];
+        let planes: Vec<&[f32]> = planes.iter().map(Vec::as_slice).collect();
+        dr.add_frames_planar_f32(&planes).unwrap();
+        assert_eq!(dr.add_stem("effects", &[2]), Err(Error::AlreadyStarted));
+        dr.finalize().unwrap();
+
+        let names: Vec<&str> = dr.stems().map(|(name, _)| name).collect();
+        assert_eq!(names, ["dialogue", "music"]);
+        let music = dr.stem("music").unwrap();
+        assert!(music.finalized());
+        assert_eq!(music.channels(), 2);
+        assert_eq!(music.exact_channel_dr(1), dr.exact_channel_dr(2));
+        assert_eq!(
+            dr.stem("dialogue").unwrap().exact_channel_dr(0),
+            dr.exact_channel_dr(1)
+        );
+    }
+
+    #[test]
+    fn worst_block() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        assert_eq!(dr.worst_block(0), Ok(None));
+        dr.add_frames_f32(&[0.0; 10]).unwrap();
+        assert_eq!(dr.worst_block(0), Ok(None));
+        dr.add_frames_f32(&blocks(&[0.8])).unwrap();
+        // constant block has the lowest crest factor
+        dr.add_frames_f32(&[0.5; 10]).unwrap();
+        dr.add_frames_f32(&blocks(&[0.9])).unwrap();
+        dr.finalize().unwrap();
+
+        let worst = dr.worst_block(0).unwrap().unwrap();
+        assert_eq!(worst.start, 20);
+        assert_float_eq!(worst.peak, 0.5, abs <= 1e-6);
+        assert_float_eq!(worst.crest_factor(), f64::sqrt(0.5), abs <= 1e-6);
+        assert_eq!(dr.results().unwrap().worst_block(0), Ok(Some(worst)));
+        assert!(dr.worst_block(1).is_err());
+    }
+
+    #[test]
+    fn weighting() {
+        // 50 Hz sine with rising level
+        let data: Vec<f64> = (0..8000 * 4)
+            .map(|i| {
+                let level = 0.125 * f64::from(1 << (i / 8000));
+                level * (2.0 * std::f64::consts::PI * 50.0 * f64::from(i) / 8000.0).sin()
+            })
+            .collect();
+        let measure = |weighting| {
+            let mut dr = DRMeter::new_with_window(1, 8000, 1000).unwrap();
+            dr.set_weighting(weighting).unwrap();
+            dr.add_frames_f64(&data).unwrap();
+            assert_eq!(dr.set_weighting(weighting), Err(Error::AlreadyStarted));
+            dr.finalize().unwrap();
+            dr.exact_dr().unwrap()
+        };
+
+        // peaks are unweighted, A attenuates 50 Hz by 30 dB
+        let difference = measure(Weighting::A) - measure(Weighting::None);
+        assert_float_eq!(difference, 30.2, abs <= 0.5);
+        assert!(measure(Weighting::C) - measure(Weighting::None) < 2.0);
+    }
+    #[test]
+    fn loud_blocks() {
+        // 10 blocks, top 20% are two loudest blocks
+        let data = blocks(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 0.9]);
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        let loud = dr.loud_blocks(0).unwrap();
+        assert_eq!(loud.target, 2);
+        assert_eq!(loud.count, 3);
+        // RMS of block with 0.8 peak
+        assert!(loud.cutoff_rms > 0.64 && loud.cutoff_rms < 0.65);
+        assert_eq!(dr.results().unwrap().loud_blocks(0).unwrap(), loud);
+
+        let loud_rms = dr.channel_loud_rms_db(0).unwrap();
+        assert_eq!(
+            loud_rms,
+            amplitude_to_dbfs(dr.results().unwrap().rms(0).unwrap())
+        );
+        assert_float_eq!(
+            dr.exact_channel_dr(0).unwrap(),
+            amplitude_to_dbfs(dr.second_peak(0).unwrap()) - loud_rms,
+            abs <= 1e-9
+        );
+        assert_eq!(dr.channel_loud_rms_db(1), Err(Error::InvalidChannelIndex));
+
+        // exact selection is kept in results
+        let mut exact = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        exact.set_exact_mode(true).unwrap();
+        exact.add_frames_f32(&data).unwrap();
+        exact.finalize().unwrap();
+        let loud = exact.loud_blocks(0).unwrap();
+        assert_eq!((loud.target, loud.count), (2, 2));
+        assert_eq!(exact.results().unwrap().loud_blocks(0).unwrap(), loud);
+    }
+
+    #[test]
+    fn loud_fraction() {
+        // 10 blocks, squared RMS of block is 0.65 of squared peak
+        let levels = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 0.9];
+        let data = blocks(&levels);
+        let measure = |fraction| {
+            let mut dr = DRMeter::builder()
+                .channels(1)
+                .rate(1000)
+                .window(10)
+                .loud_fraction(fraction)
+                .exact_mode(true)
+                .build()
+                .unwrap();
+            dr.add_frames_f32(&data).unwrap();
+            dr.finalize().unwrap();
+            dr
+        };
+
+        let dr = measure(LOUD_FRACTION);
+        assert_eq!(dr.loud_fraction(), LOUD_FRACTION);
+        assert_eq!(dr.results().unwrap().loud_fraction(), LOUD_FRACTION);
+
+        // all blocks
+        let dr = measure(1.0);
+        let loud = dr.loud_blocks(0).unwrap();
+        assert_eq!((loud.target, loud.count), (10, 10));
+        let energy = levels.iter().map(|l| f64::from(l * l) * 0.65).sum::<f64>() / 10.0;
+        assert_float_eq!(
+            dr.results().unwrap().rms(0).unwrap(),
+            energy.sqrt(),
+            abs <= 1e-4
+        );
+        let results = dr.results().unwrap();
+        assert_eq!(results.loud_fraction(), 1.0);
+        assert_eq!(results.loud_blocks(0).unwrap(), loud);
+        // more blocks in RMS lower it, so DR is higher
+        assert!(dr.exact_channel_dr(0).unwrap() > measure(0.2).exact_channel_dr(0).unwrap());
+
+        // can be changed until finalized
+        let mut dr = DRMeter::new_with_window(1, 1000, 10).unwrap();
+        assert_eq!(dr.set_loud_fraction(0.0), Err(Error::ArgOutside));
+        assert_eq!(dr.set_loud_fraction(f64::NAN), Err(Error::ArgOutside));
+        dr.add_frames_f32(&data).unwrap();
+        dr.set_loud_fraction(0.5).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.loud_blocks(0).unwrap().target, 5);
+        assert_eq!(dr.set_loud_fraction(0.2), Err(Error::Finalized));
     }
 }
@@ -1,7 +1,42 @@
 use std::{error, fmt};
 
+/// Option of meter configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigOption {
+    /// Number of channels (1 to 64)
+    Channels,
+    /// Sample rate (16 Hz to 2_822_400 Hz)
+    Rate,
+    /// Block length (at least 10 ms and a whole frame)
+    Window,
+    /// Headroom above full scale for peaks (0 to 20 dB)
+    PeakHeadroom,
+    /// Hop of overlapping blocks (whole frames dividing the window)
+    Hop,
+    /// Peak used in DR formula (true peak needs true peak mode)
+    DrNumerator,
+    /// Fraction of loudest blocks in RMS (above 0, at most 1)
+    LoudFraction,
+}
+
+impl ConfigOption {
+    /// Returns the name of option.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ConfigOption::Channels => "channels",
+            ConfigOption::Rate => "rate",
+            ConfigOption::Window => "window",
+            ConfigOption::PeakHeadroom => "peak headroom",
+            ConfigOption::Hop => "hop",
+            ConfigOption::DrNumerator => "DR numerator",
+            ConfigOption::LoudFraction => "loud fraction",
+        }
+    }
+}
+
 /// Error values for [`DRMeter`](struct.DRMeter.html) functions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Error), uniffi(flat_error))]
 pub enum Error {
     /// Not enough memory
     NoMem,
@@ -11,6 +46,44 @@ pub enum Error {
     InvalidChannelIndex,
     /// DR Meter is finalized
     Finalized,
+    /// DR Meter already processed frames
+    AlreadyStarted,
+    /// DR Meter is not finalized yet
+    NotFinalized,
+    /// Instances have incompatible configuration
+    Mismatch,
+    /// Template has unknown placeholder or unbalanced braces
+    InvalidTemplate,
+    /// Non-finite sample with [`NonFinitePolicy::Error`](crate::NonFinitePolicy::Error)
+    NonFinite {
+        /// position of the sample (in frames from start of stream)
+        frame: u64,
+    },
+    /// Frames do not match the number of channels
+    InvalidInput {
+        /// position of the first rejected frame (in frames from start of stream)
+        frame: u64,
+    },
+    /// Maximum duration was reached, frames above it were not measured
+    DurationLimit,
+    /// Configuration option is missing or outside of its limits
+    InvalidConfig {
+        /// the offending option
+        option: ConfigOption,
+    },
+}
+
+impl Error {
+    /// Returns the position in the stream (in frames) where the error occurred,
+    /// if the error is tied to specific input.
+    ///
+    /// Divide by sample rate to get the time.
+    pub const fn frame(&self) -> Option<u64> {
+        match *self {
+            Error::NonFinite { frame } | Error::InvalidInput { frame } => Some(frame),
+            _ => None,
+        }
+    }
 }
 
 impl error::Error for Error {}
@@ -22,6 +95,18 @@ impl fmt::Display for Error {
             Error::InvalidChannelIndex => write!(f, "Invalid Channel Index"),
             Error::Finalized => write!(f, "DR Meter instance is finalized"),
             Error::ArgOutside => write!(f, "Argument outside of it's limit"),
+            Error::AlreadyStarted => write!(f, "DR Meter instance already processed frames"),
+            Error::NotFinalized => write!(f, "DR Meter instance is not finalized"),
+            Error::Mismatch => write!(f, "Instances have incompatible configuration"),
+            Error::InvalidTemplate => write!(f, "Invalid template"),
+            Error::NonFinite { frame } => write!(f, "Non-finite sample at frame {frame}"),
+            Error::InvalidInput { frame } => {
+                write!(f, "Frames do not match channels at frame {frame}")
+            }
+            Error::DurationLimit => write!(f, "Maximum duration reached"),
+            Error::InvalidConfig { option } => {
+                write!(f, "Invalid configuration of {}", option.name())
+            }
         }
     }
 }
@@ -0,0 +1,89 @@
+//! Simplified object API for [uniffi](https://mozilla.github.io/uniffi-rs/) bindings
+//!
+//! Build the crate as `cdylib` with `uniffi` feature and generate Kotlin/Swift
+//! bindings from the library with `uniffi-bindgen`.
+
+use std::sync::Mutex;
+
+use crate::{DRMeter, Error};
+
+/// Results of finalized [`Analyzer`]
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct AnalyzerResults {
+    /// DR score
+    pub dr: u8,
+    /// Exact DR
+    pub exact_dr: f64,
+    /// Exact DR of every channel
+    pub channel_dr: Vec<f64>,
+    /// Duration of processed audio in seconds
+    pub duration: f64,
+}
+
+/// Thread-safe meter that is fed interleaved frames
+#[derive(Debug, uniffi::Object)]
+pub struct Analyzer {
+    meter: Mutex<DRMeter>,
+}
+
+#[uniffi::export]
+impl Analyzer {
+    /// Create a new analyzer
+    #[uniffi::constructor]
+    pub fn new(channels: u32, rate: u32) -> Result<Self, Error> {
+        Ok(Self {
+            meter: Mutex::new(DRMeter::new(channels, rate)?),
+        })
+    }
+
+    /// Add interleaved 16-bit frames
+    pub fn add_i16(&self, frames: Vec<i16>) -> Result<(), Error> {
+        self.meter.lock().unwrap().add_frames_i16(&frames)
+    }
+
+    /// Add interleaved 32-bit frames
+    pub fn add_i32(&self, frames: Vec<i32>) -> Result<(), Error> {
+        self.meter.lock().unwrap().add_frames_i32(&frames)
+    }
+
+    /// Add interleaved float frames
+    pub fn add_f32(&self, frames: Vec<f32>) -> Result<(), Error> {
+        self.meter.lock().unwrap().add_frames_f32(&frames)
+    }
+
+    /// Finalize analysis and return results
+    pub fn finalize(&self) -> Result<AnalyzerResults, Error> {
+        let mut meter = self.meter.lock().unwrap();
+        meter.finalize()?;
+        let results = meter.results()?;
+
+        Ok(AnalyzerResults {
+            dr: results.dr_score().get(),
+            exact_dr: results.exact_dr(),
+            channel_dr: (0..results.channels())
+                .map(|ch| results.exact_channel_dr(ch))
+                .collect::<Result<_, _>>()?,
+            duration: results.duration().as_secs_f64(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analyze() {
+        let analyzer = Analyzer::new(1, 8000).unwrap();
+        let frames: Vec<f32> = (0..8000 * 6)
+            .map(|i| f32::sin(i as f32 * 0.1) * (i % 8000) as f32 / 8000.0)
+            .collect();
+        analyzer.add_f32(frames).unwrap();
+
+        let results = analyzer.finalize().unwrap();
+        assert_eq!(results.channel_dr.len(), 1);
+        assert_eq!(results.dr, results.exact_dr as u8);
+        assert_eq!(results.duration, 6.0);
+        assert_eq!(analyzer.finalize(), Err(Error::Finalized));
+    }
+}
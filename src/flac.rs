@@ -0,0 +1,235 @@
+//! Measuring FLAC files decoded with [claxon](https://github.com/ruuda/claxon)
+
+use std::io::Read;
+use std::path::Path;
+use std::{error, fmt};
+
+use claxon::FlacReader;
+
+use crate::{DRMeter, DrReport, Error};
+
+/// Error of decoding and measuring FLAC stream
+#[derive(Debug)]
+pub enum FlacError {
+    /// Stream cannot be read or is not a valid FLAC
+    Flac(claxon::Error),
+    /// Meter rejected the audio (e.g. unsupported sample rate)
+    Meter(Error),
+}
+
+impl error::Error for FlacError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            FlacError::Flac(e) => Some(e),
+            FlacError::Meter(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for FlacError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlacError::Flac(e) => write!(f, "FLAC error: {e}"),
+            FlacError::Meter(e) => write!(f, "Meter error: {e}"),
+        }
+    }
+}
+
+impl From<claxon::Error> for FlacError {
+    fn from(e: claxon::Error) -> Self {
+        FlacError::Flac(e)
+    }
+}
+
+impl From<Error> for FlacError {
+    fn from(e: Error) -> Self {
+        FlacError::Meter(e)
+    }
+}
+
+/// Measure all blocks of FLAC stream
+fn measure<R: Read>(mut reader: FlacReader<R>) -> Result<DrReport, FlacError> {
+    let info = reader.streaminfo();
+    let mut meter = DRMeter::new(info.channels, info.sample_rate)?;
+    // scale to full range of `i32`, so samples of any bit depth stay exact
+    let shift = 32 - info.bits_per_sample;
+
+    let mut blocks = reader.blocks();
+    let mut buffer = Vec::new();
+    while let Some(block) = blocks.read_next_or_eof(buffer)? {
+        let frames = block.duration() as usize;
+        let channels = block.channels() as usize;
+        buffer = block.into_buffer();
+        if frames == 0 {
+            continue;
+        }
+        // channels are stored one after another
+        let samples = &mut buffer[..frames * channels];
+        samples.iter_mut().for_each(|s| *s <<= shift);
+        let planes: Vec<&[i32]> = samples.chunks_exact(frames).collect();
+        meter.add_frames_planar_i32(&planes)?;
+    }
+    Ok(meter.into_report()?)
+}
+
+/// Decode FLAC stream from reader, measure all of it and return report
+pub fn analyze_flac_reader<R: Read>(reader: R) -> Result<DrReport, FlacError> {
+    measure(FlacReader::new(reader)?)
+}
+
+/// Open FLAC file, measure all of it and return report
+pub fn analyze_flac(path: impl AsRef<Path>) -> Result<DrReport, FlacError> {
+    measure(FlacReader::open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Frames per FLAC frame
+    const BLOCK_SIZE: usize = 4096;
+
+    fn crc8(data: &[u8]) -> u8 {
+        data.iter().fold(0, |crc, byte| {
+            (0..8).fold(crc ^ byte, |crc, _| {
+                if crc & 0x80 != 0 {
+                    (crc << 1) ^ 0x07
+                } else {
+                    crc << 1
+                }
+            })
+        })
+    }
+
+    fn crc16(data: &[u8]) -> u16 {
+        data.iter().fold(0, |crc, byte| {
+            (0..8).fold(crc ^ (u16::from(*byte) << 8), |crc, _| {
+                if crc & 0x8000 != 0 {
+                    (crc << 1) ^ 0x8005
+                } else {
+                    crc << 1
+                }
+            })
+        })
+    }
+
+    /// Frame number in UTF-8 like coding of frame header
+    fn coded_number(number: u32) -> Vec<u8> {
+        if number < 0x80 {
+            return vec![number as u8];
+        }
+        let mut continuation = Vec::new();
+        let mut n = number;
+        // payload bits of first byte
+        let mut first_bits = 6;
+        loop {
+            continuation.push(0x80 | (n & 0x3F) as u8);
+            n >>= 6;
+            first_bits -= 1;
+            if n < 1 << first_bits {
+                break;
+            }
+        }
+        let len = continuation.len() + 1;
+        let mut coded = vec![(0xFF << (8 - len)) as u8 | n as u8];
+        coded.extend(continuation.iter().rev());
+        coded
+    }
+
+    /// FLAC stream with verbatim (uncompressed) subframes of 16 or 24-bit samples
+    fn flac(channels: u8, rate: u32, bits: u8, samples: &[i32]) -> Vec<u8> {
+        let frames = samples.len() / channels as usize;
+        let mut flac = Vec::new();
+        flac.extend(b"fLaC");
+        // last metadata block: STREAMINFO of 34 bytes
+        flac.extend([0x80, 0, 0, 34]);
+        flac.extend((BLOCK_SIZE as u16).to_be_bytes());
+        flac.extend((BLOCK_SIZE as u16).to_be_bytes());
+        flac.extend([0; 6]);
+        let info = (u64::from(rate) << 44)
+            | (u64::from(channels - 1) << 41)
+            | (u64::from(bits - 1) << 36)
+            | frames as u64;
+        flac.extend(info.to_be_bytes());
+        flac.extend([0; 16]);
+
+        let size_code = match bits {
+            16 => 0b100,
+            24 => 0b110,
+            _ => panic!("fixture only writes 16 or 24-bit samples, not {bits}"),
+        };
+        for (number, block) in samples.chunks(BLOCK_SIZE * channels as usize).enumerate() {
+            let block_frames = block.len() / channels as usize;
+            // fixed blocking, block size and rate from end of header / STREAMINFO
+            let mut frame = vec![
+                0xFF,
+                0xF8,
+                0b0111_0000,
+                ((channels - 1) << 4) | (size_code << 1),
+            ];
+            frame.extend(coded_number(number as u32));
+            frame.extend((block_frames as u16 - 1).to_be_bytes());
+            frame.push(crc8(&frame));
+            for ch in 0..channels as usize {
+                frame.push(0b0000_0010);
+                for s in block.iter().skip(ch).step_by(channels as usize) {
+                    frame.extend(&s.to_be_bytes()[4 - bits as usize / 8..]);
+                }
+            }
+            frame.extend(crc16(&frame).to_be_bytes());
+            flac.extend(frame);
+        }
+        flac
+    }
+
+    #[test]
+    fn bit_depths() {
+        let samples: Vec<i32> = (0..44_100 * 2 * 10)
+            .map(|i| (f64::sin(i as f64 * 0.003) * (1 + i / 200_000) as f64 * 6000.0) as i32)
+            .collect();
+        let mut dr = DRMeter::new(2, 44_100).unwrap();
+        let i16_samples: Vec<i16> = samples.iter().map(|s| *s as i16).collect();
+        dr.add_frames_i16(&i16_samples).unwrap();
+        let expected = dr.into_report().unwrap();
+
+        let report = analyze_flac_reader(Cursor::new(flac(2, 44_100, 16, &samples))).unwrap();
+        assert_eq!(report, expected);
+
+        let samples_24: Vec<i32> = samples.iter().map(|s| s << 8).collect();
+        let report = analyze_flac_reader(Cursor::new(flac(2, 44_100, 24, &samples_24))).unwrap();
+        assert_eq!(report, expected);
+    }
+
+    #[test]
+    fn frame_numbers() {
+        assert_eq!(coded_number(0x7F), [0x7F]);
+        assert_eq!(coded_number(0x80), [0xC2, 0x80]);
+        assert_eq!(coded_number(0x7FF), [0xDF, 0xBF]);
+        assert_eq!(coded_number(0x800), [0xE0, 0xA0, 0x80]);
+        assert_eq!(coded_number(0x1_0000), [0xF0, 0x90, 0x80, 0x80]);
+
+        // more than 128 frames
+        let samples: Vec<i32> = (0..BLOCK_SIZE as i32 * 300)
+            .map(|i| (f64::sin(i as f64 * 0.01) * (1 + i / 100_000) as f64 * 2000.0) as i32)
+            .collect();
+        let mut dr = DRMeter::new(1, 8000).unwrap();
+        let i16_samples: Vec<i16> = samples.iter().map(|s| *s as i16).collect();
+        dr.add_frames_i16(&i16_samples).unwrap();
+        let report = analyze_flac_reader(Cursor::new(flac(1, 8000, 16, &samples))).unwrap();
+        assert_eq!(report, dr.into_report().unwrap());
+    }
+
+    #[test]
+    fn not_flac() {
+        assert!(matches!(
+            analyze_flac_reader(Cursor::new(vec![0u8; 1024])),
+            Err(FlacError::Flac(_))
+        ));
+        assert!(matches!(
+            analyze_flac(std::env::temp_dir().join("drmeter-missing.flac")),
+            Err(FlacError::Flac(claxon::Error::IoError(_)))
+        ));
+    }
+}
@@ -0,0 +1,446 @@
+use std::collections::{btree_map, BTreeMap};
+use std::iter::Enumerate;
+use std::slice::Iter;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::math;
+
+/// Storage used for peak and RMS histograms.
+///
+/// Every channel has two histograms (peak and RMS) of `2¹⁵ + 1` bins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HistogramMode {
+    /// `u32` counter per bin (default).
+    #[default]
+    Dense,
+    /// `u16` counter per bin, which halves histogram memory.
+    ///
+    /// Single bin can count at most 65535 blocks (~54h of audio with 3s blocks),
+    /// further blocks are not counted and the meter reports that
+    /// [`histogram_saturated`](crate::DRMeter::histogram_saturated).
+    Compact,
+    /// Only populated bins are stored.
+    ///
+    /// Real-world material populates only a small fraction of bins,
+    /// so this saves most of the memory when many meters are running concurrently,
+    /// at the cost of slower block finalization.
+    Sparse,
+    /// Bins only cover the populated range, with up to
+    /// `2¹²` times finer resolution than full scale binning.
+    ///
+    /// Binning starts at the finest resolution around the first block
+    /// and gets coarser (carrying over counts) only when blocks fall outside
+    /// of covered range, which improves precision for very quiet or
+    /// narrow-dynamics material with the same memory as [`Dense`](Self::Dense).
+    Adaptive,
+}
+
+/// Rule that maps block RMS to its bin.
+///
+/// Borderline blocks end up in different bins, which slightly changes scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Quantization {
+    /// Nearest bin (default).
+    #[default]
+    Round,
+    /// Bin below, like tools that truncate.
+    Floor,
+}
+
+impl Quantization {
+    /// Quantize value scaled to bins
+    pub(crate) fn apply(self, x: f64) -> f64 {
+        match self {
+            Quantization::Round => math::round(x),
+            Quantization::Floor => math::floor(x),
+        }
+    }
+}
+
+/// Max refinement of adaptive histogram (as power of two)
+const MAX_SHIFT: u32 = 12;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+enum Storage {
+    Dense(Box<[u32]>),
+    Compact(Box<[u16]>),
+    Sparse(BTreeMap<usize, u32>),
+    Adaptive(Adaptive),
+}
+
+/// Bins covering `offset..=offset + window` with resolution refined by `shift`
+///
+/// Window is as large as the histogram without refinement,
+/// which also limits the values.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Adaptive {
+    shift: u32,
+    offset: usize,
+    counts: Box<[u32]>,
+    /// nothing is counted yet, so range is not placed
+    empty: bool,
+}
+
+/// Bin at resolution lowered by `levels` powers of two
+///
+/// Rounds to nearest, like binning of values.
+fn coarsen_bin(bin: usize, levels: u32) -> usize {
+    if levels == 0 {
+        bin
+    } else {
+        (bin + (1 << (levels - 1))) >> levels
+    }
+}
+
+impl Adaptive {
+    /// Populated range of absolute bins
+    fn range(&self) -> Option<(usize, usize)> {
+        let first = self.counts.iter().position(|c| *c != 0)?;
+        let last = self.counts.iter().rposition(|c| *c != 0)?;
+        Some((self.offset + first, self.offset + last))
+    }
+
+    /// Lower resolution by `levels` powers of two, counts of merged bins are summed
+    fn coarsen(&mut self, levels: u32) -> bool {
+        let coarse = |bin| coarsen_bin(bin, levels);
+        let mut saturated = false;
+        let mut counts = vec![0u32; self.counts.len()].into_boxed_slice();
+        let offset = coarse(self.offset);
+        for (i, count) in self.counts.iter().enumerate() {
+            if *count != 0 {
+                let bin = coarse(self.offset + i) - offset;
+                match counts[bin].checked_add(*count) {
+                    Some(c) => counts[bin] = c,
+                    None => saturated = true,
+                }
+            }
+        }
+        self.counts = counts;
+        self.offset = offset;
+        self.shift -= levels;
+        saturated
+    }
+
+    /// Count bin given by `bin(shift)` at resolution of `shift`
+    fn add(&mut self, bin: impl Fn(u32) -> usize, count: u32) -> bool {
+        let window = self.counts.len() - 1;
+        let mut saturated = false;
+        loop {
+            let bin = bin(self.shift).min(window << self.shift);
+            if self.empty {
+                self.offset = bin.saturating_sub(window / 2);
+                self.empty = false;
+            }
+            // move covered range if populated range still fits
+            let (lo, hi) = self
+                .range()
+                .map_or((bin, bin), |(lo, hi)| (lo.min(bin), hi.max(bin)));
+            if hi - lo <= window {
+                if bin < self.offset {
+                    self.move_to(lo);
+                } else if bin > self.offset + window {
+                    self.move_to(hi - window);
+                }
+                let slot = &mut self.counts[bin - self.offset];
+                match slot.checked_add(count) {
+                    Some(c) => *slot = c,
+                    None => saturated = true,
+                }
+                return saturated;
+            }
+            // at once, so bins are rounded only once
+            let mut levels = 1;
+            while (hi - lo) >> levels > window {
+                levels += 1;
+            }
+            saturated |= self.coarsen(levels.min(self.shift));
+        }
+    }
+
+    /// Move covered range to start at `offset`
+    fn move_to(&mut self, offset: usize) {
+        let mut counts = vec![0u32; self.counts.len()].into_boxed_slice();
+        for (i, count) in self.counts.iter().enumerate() {
+            if *count != 0 {
+                counts[self.offset + i - offset] = *count;
+            }
+        }
+        self.counts = counts;
+        self.offset = offset;
+    }
+}
+
+/// Histogram of block values
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Histogram {
+    storage: Storage,
+    /// Number of bins (without refinement)
+    bins: usize,
+    /// Some block could not be counted because bin counter was full
+    saturated: bool,
+    /// Number of blocks above the last bin, which were clamped into it
+    overflows: u32,
+}
+
+impl Histogram {
+    /// Creates a new empty [`Histogram`] with the given number of bins.
+    pub fn new(mode: HistogramMode, bins: usize) -> Self {
+        let storage = match mode {
+            HistogramMode::Dense => Storage::Dense(vec![0; bins].into_boxed_slice()),
+            HistogramMode::Compact => Storage::Compact(vec![0; bins].into_boxed_slice()),
+            HistogramMode::Sparse => Storage::Sparse(BTreeMap::new()),
+            HistogramMode::Adaptive => Storage::Adaptive(Adaptive {
+                shift: MAX_SHIFT,
+                offset: 0,
+                counts: vec![0; bins].into_boxed_slice(),
+                empty: true,
+            }),
+        };
+
+        Self {
+            storage,
+            bins,
+            saturated: false,
+            overflows: 0,
+        }
+    }
+
+    /// Remove all counts, keeping the allocation.
+    pub fn clear(&mut self) {
+        match &mut self.storage {
+            Storage::Dense(bins) => bins.fill(0),
+            Storage::Compact(bins) => bins.fill(0),
+            Storage::Sparse(bins) => bins.clear(),
+            Storage::Adaptive(adaptive) => {
+                adaptive.shift = MAX_SHIFT;
+                adaptive.offset = 0;
+                adaptive.counts.fill(0);
+                adaptive.empty = true;
+            }
+        }
+        self.saturated = false;
+        self.overflows = 0;
+    }
+
+    /// Returns `true` if some block was not counted because its bin was full.
+    pub const fn saturated(&self) -> bool {
+        self.saturated
+    }
+
+    /// Returns number of blocks above the last bin (clamped into it).
+    pub const fn overflows(&self) -> u32 {
+        self.overflows
+    }
+
+    /// Refinement of bins, values of bins are divided by `2^shift()`.
+    pub fn shift(&self) -> u32 {
+        match &self.storage {
+            Storage::Adaptive(adaptive) => adaptive.shift,
+            _ => 0,
+        }
+    }
+
+    /// Count one block in the given bin.
+    pub fn add(&mut self, bin: usize) {
+        self.add_count(bin, 1);
+    }
+
+    /// Count one block of the given value with `bins` bins per full scale
+    ///
+    /// Value is clamped to the last bin and counted as overflow.
+    pub fn add_value(&mut self, value: f64, bins: usize, quantization: Quantization) {
+        if quantization.apply(value * bins as f64) as usize >= self.bins {
+            self.overflows = self.overflows.saturating_add(1);
+        }
+        match &mut self.storage {
+            Storage::Adaptive(adaptive) => {
+                let bin = |shift| quantization.apply(value * (bins << shift) as f64) as usize;
+                self.saturated |= adaptive.add(bin, 1);
+            }
+            _ => {
+                let max = self.bins - 1;
+                self.add((quantization.apply(value * bins as f64) as usize).clamp(0, max));
+            }
+        }
+    }
+
+    fn add_count(&mut self, bin: usize, count: u32) {
+        let counted = match &mut self.storage {
+            Storage::Dense(bins) => bins[bin].checked_add(count).map(|c| bins[bin] = c),
+            Storage::Compact(bins) => u16::try_from(count)
+                .ok()
+                .and_then(|count| bins[bin].checked_add(count))
+                .map(|c| bins[bin] = c),
+            Storage::Sparse(bins) => {
+                let c = bins.entry(bin).or_insert(0);
+                c.checked_add(count).map(|n| *c = n)
+            }
+            Storage::Adaptive(adaptive) => {
+                (!adaptive.add(|shift| bin << shift, count)).then_some(())
+            }
+        };
+        self.saturated |= counted.is_none();
+    }
+
+    /// Add counts of other histogram with the same number of bins.
+    pub fn merge(&mut self, other: &Histogram) {
+        match (&mut self.storage, &other.storage) {
+            (Storage::Adaptive(this), Storage::Adaptive(other)) => {
+                for (i, count) in other.counts.iter().enumerate() {
+                    if *count != 0 {
+                        let bin = other.offset + i;
+                        let at = |shift: u32| match shift.checked_sub(other.shift) {
+                            Some(finer) => bin << finer,
+                            None => coarsen_bin(bin, other.shift - shift),
+                        };
+                        self.saturated |= this.add(at, *count);
+                    }
+                }
+            }
+            _ => {
+                for (bin, count) in other.populated() {
+                    self.add_count(bin, count);
+                }
+            }
+        }
+        self.saturated |= other.saturated;
+        self.overflows = self.overflows.saturating_add(other.overflows);
+    }
+
+    /// Iterate over non-empty bins as `(bin, count)` pairs in ascending order.
+    ///
+    /// Bins are refined by [`shift`](Self::shift).
+    pub fn populated(&self) -> Populated<'_> {
+        match &self.storage {
+            Storage::Dense(bins) => Populated::Dense(bins.iter().enumerate()),
+            Storage::Compact(bins) => Populated::Compact(bins.iter().enumerate()),
+            Storage::Sparse(bins) => Populated::Sparse(bins.iter()),
+            Storage::Adaptive(adaptive) => {
+                Populated::Adaptive(adaptive.offset, adaptive.counts.iter().enumerate())
+            }
+        }
+    }
+}
+
+/// Iterator over non-empty bins of a [`Histogram`]
+pub(crate) enum Populated<'a> {
+    Dense(Enumerate<Iter<'a, u32>>),
+    Compact(Enumerate<Iter<'a, u16>>),
+    Sparse(btree_map::Iter<'a, usize, u32>),
+    Adaptive(usize, Enumerate<Iter<'a, u32>>),
+}
+
+impl Iterator for Populated<'_> {
+    type Item = (usize, u32);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Populated::Dense(iter) => iter.find(|(_, c)| **c != 0).map(|(i, c)| (i, *c)),
+            Populated::Compact(iter) => iter
+                .find(|(_, c)| **c != 0)
+                .map(|(i, c)| (i, u32::from(*c))),
+            Populated::Sparse(iter) => iter.next().map(|(i, c)| (*i, *c)),
+            Populated::Adaptive(offset, iter) => {
+                iter.find(|(_, c)| **c != 0).map(|(i, c)| (*offset + i, *c))
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for Populated<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            Populated::Dense(iter) => iter.rfind(|(_, c)| **c != 0).map(|(i, c)| (i, *c)),
+            Populated::Compact(iter) => iter
+                .rfind(|(_, c)| **c != 0)
+                .map(|(i, c)| (i, u32::from(*c))),
+            Populated::Sparse(iter) => iter.next_back().map(|(i, c)| (*i, *c)),
+            Populated::Adaptive(offset, iter) => iter
+                .rfind(|(_, c)| **c != 0)
+                .map(|(i, c)| (*offset + i, *c)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_saturates() {
+        let mut h = Histogram::new(HistogramMode::Compact, 4);
+        for _ in 0..=u16::MAX {
+            h.add(2);
+        }
+        assert_eq!(
+            h.populated().collect::<Vec<_>>(),
+            vec![(2, u32::from(u16::MAX))]
+        );
+        assert!(h.saturated());
+    }
+
+    #[test]
+    fn adaptive_refines() {
+        let mut h = Histogram::new(HistogramMode::Adaptive, 9);
+        h.add_value(0.5, 8, Quantization::Round);
+        h.add_value(0.5 + 1.0 / 1024.0, 8, Quantization::Round);
+        // finest resolution that covers both values
+        assert_eq!(h.shift(), 10);
+        assert_eq!(
+            h.populated().collect::<Vec<_>>(),
+            vec![(4096, 1), (4104, 1)]
+        );
+
+        // value outside of range coarsens bins
+        h.add_value(0.25, 8, Quantization::Round);
+        assert_eq!(h.shift(), 2);
+        assert_eq!(h.populated().collect::<Vec<_>>(), vec![(8, 1), (16, 2)]);
+        assert!(!h.saturated());
+    }
+
+    #[test]
+    fn quantization() {
+        for (quantization, bin) in [(Quantization::Round, 3), (Quantization::Floor, 2)] {
+            let mut h = Histogram::new(HistogramMode::Dense, 9);
+            h.add_value(0.35, 8, quantization);
+            assert_eq!(h.populated().collect::<Vec<_>>(), vec![(bin, 1)]);
+        }
+    }
+
+    #[test]
+    fn overflows() {
+        for mode in [HistogramMode::Dense, HistogramMode::Adaptive] {
+            let mut h = Histogram::new(mode, 9);
+            h.add_value(1.0, 8, Quantization::Round);
+            h.add_value(1.5, 8, Quantization::Round);
+            assert_eq!(h.overflows(), 1);
+            assert_eq!(h.populated().next_back(), Some((8 << h.shift(), 2)));
+        }
+    }
+
+    #[test]
+    fn populated_bins() {
+        for mode in [
+            HistogramMode::Dense,
+            HistogramMode::Compact,
+            HistogramMode::Sparse,
+        ] {
+            let mut h = Histogram::new(mode, 8);
+            h.add(1);
+            h.add(5);
+            h.add(5);
+            assert_eq!(h.populated().collect::<Vec<_>>(), vec![(1, 1), (5, 2)]);
+            assert_eq!(
+                h.populated().rev().collect::<Vec<_>>(),
+                vec![(5, 2), (1, 1)]
+            );
+        }
+    }
+}
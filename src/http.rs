@@ -0,0 +1,299 @@
+//! Measuring audio streamed over HTTP(S)
+
+use std::collections::VecDeque;
+use std::io;
+use std::ops::ControlFlow;
+use std::thread;
+use std::time::Duration;
+
+use symphonia::core::audio::SignalSpec;
+use symphonia::core::io::ReadOnlySource;
+use symphonia::core::probe::Hint;
+
+use crate::decoder::{measure, AudioStream};
+use crate::{DRMeter, DRResults, DecodeError, Error};
+
+/// Request URL and open audio of response body
+fn open_url(url: &str) -> Result<AudioStream, DecodeError> {
+    let response = ureq::get(url).call().map_err(io::Error::other)?;
+
+    let mut hint = Hint::new();
+    if let Some(mime) = response
+        .headers()
+        .get("content-type")
+        .and_then(|value| value.to_str().ok())
+    {
+        hint.mime_type(mime);
+    }
+    if let Some((_, ext)) = url_path(url).rsplit_once('.') {
+        if !ext.contains('/') {
+            hint.with_extension(ext);
+        }
+    }
+
+    let reader = response.into_body().into_reader();
+    AudioStream::open(Box::new(ReadOnlySource::new(reader)), &hint)
+}
+
+/// Path part of URL (without query and fragment)
+fn url_path(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// Download audio from HTTP(S) URL and measure it
+///
+/// Audio is decoded and measured while downloading, so the file is never
+/// stored whole. The container is probed by `Content-Type` and URL extension.
+pub fn analyze_url(url: &str) -> Result<DRResults, DecodeError> {
+    measure(open_url(url)?)
+}
+
+/// Options of live stream monitoring with [`monitor_url`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorOptions {
+    /// Interval between reports (rounded up to whole blocks)
+    pub interval: Duration,
+    /// Length of rolling window (rounded up to whole intervals)
+    pub window: Duration,
+    /// Consecutive failed reconnects after which monitoring gives up
+    pub max_reconnects: u32,
+    /// Delay before reconnecting
+    pub reconnect_delay: Duration,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(15),
+            window: Duration::from_secs(300),
+            max_reconnects: 10,
+            reconnect_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Periodic report of [`monitor_url`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiveReport {
+    /// Exact DR of rolling window
+    pub rolling_dr: f64,
+    /// Exact DR of all received audio
+    pub cumulative_dr: f64,
+    /// Duration of all received audio
+    pub duration: Duration,
+    /// Number of reconnects after dropouts
+    pub reconnects: u32,
+}
+
+/// Cumulative meter with rolling window made of interval segments
+struct LiveMeter {
+    spec: SignalSpec,
+    total: DRMeter,
+    /// segment that is currently filled
+    segment: DRMeter,
+    /// finished segments of rolling window
+    window: VecDeque<DRMeter>,
+    segment_frames: u64,
+    window_segments: usize,
+}
+
+impl LiveMeter {
+    fn new(spec: SignalSpec, options: &MonitorOptions) -> Result<Self, Error> {
+        let total = DRMeter::new(spec.channels.count() as u32, spec.rate)?;
+        let block = total.block_frames() as u64;
+        let interval = (options.interval.as_secs_f64() * spec.rate as f64).ceil() as u64;
+        let segment_frames = interval.div_ceil(block).max(1) * block;
+        let segment_secs = segment_frames as f64 / spec.rate as f64;
+        let window_segments = (options.window.as_secs_f64() / segment_secs)
+            .ceil()
+            .max(1.0) as usize;
+
+        Ok(Self {
+            spec,
+            segment: total.empty_clone()?,
+            total,
+            window: VecDeque::with_capacity(window_segments),
+            segment_frames,
+            window_segments,
+        })
+    }
+
+    /// Add interleaved samples and report after every finished segment
+    fn add(
+        &mut self,
+        spec: SignalSpec,
+        mut samples: &[f64],
+        reconnects: u32,
+        on_report: &mut impl FnMut(&LiveReport) -> ControlFlow<()>,
+    ) -> Result<ControlFlow<()>, Error> {
+        if spec != self.spec {
+            return Err(Error::Mismatch);
+        }
+        let channels = spec.channels.count();
+        while !samples.is_empty() {
+            let missing = (self.segment_frames - self.segment.frames()) as usize * channels;
+            let (current, rest) = samples.split_at(missing.min(samples.len()));
+            self.total.add_frames_f64(current)?;
+            self.segment.add_frames_f64(current)?;
+            samples = rest;
+            if self.segment.frames() < self.segment_frames {
+                continue;
+            }
+
+            // segment ends on block boundary, so it has no partial block
+            let segment = std::mem::replace(&mut self.segment, self.total.empty_clone()?);
+            if self.window.len() == self.window_segments {
+                self.window.pop_front();
+            }
+            self.window.push_back(segment);
+            let mut rolling = self.total.empty_clone()?;
+            for segment in &self.window {
+                rolling.merge_blocks(segment);
+            }
+
+            let report = LiveReport {
+                rolling_dr: rolling.exact_dr()?,
+                cumulative_dr: self.total.exact_dr()?,
+                duration: Duration::from_secs_f64(
+                    self.total.frames() as f64 / self.spec.rate as f64,
+                ),
+                reconnects,
+            };
+            if on_report(&report).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+/// Monitor live stream (e.g. Icecast internet radio) from HTTP(S) URL
+///
+/// Reports rolling and cumulative DR after every interval until `on_report`
+/// breaks. Dropouts (errors or end of stream) are followed by reconnecting,
+/// monitoring gives up after [`MonitorOptions::max_reconnects`] consecutive
+/// reconnects without audio.
+pub fn monitor_url(
+    url: &str,
+    options: &MonitorOptions,
+    mut on_report: impl FnMut(&LiveReport) -> ControlFlow<()>,
+) -> Result<(), DecodeError> {
+    let mut live: Option<LiveMeter> = None;
+    let mut reconnects = 0;
+    let mut failures = 0;
+    loop {
+        let mut received = false;
+        let result = open_url(url).and_then(|mut stream| {
+            while let Some((spec, samples)) = stream.next_frames()? {
+                received = true;
+                let live = match &mut live {
+                    Some(live) => live,
+                    None => live.insert(LiveMeter::new(spec, options)?),
+                };
+                if live
+                    .add(spec, samples, reconnects, &mut on_report)?
+                    .is_break()
+                {
+                    return Ok(ControlFlow::Break(()));
+                }
+            }
+            Ok(ControlFlow::Continue(()))
+        });
+
+        let dropout = match result {
+            Ok(ControlFlow::Break(())) => return Ok(()),
+            Ok(ControlFlow::Continue(())) => None,
+            Err(DecodeError::Meter(e)) => return Err(e.into()),
+            Err(e) => Some(e),
+        };
+        failures = if received { 0 } else { failures + 1 };
+        if failures > options.max_reconnects {
+            return dropout.map_or(Ok(()), Err);
+        }
+
+        reconnects += 1;
+        thread::sleep(options.reconnect_delay);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{SocketAddr, TcpListener};
+    use std::thread::JoinHandle;
+
+    use super::*;
+    use crate::decoder::tests::wav;
+
+    /// Serve body on `connections` consecutive connections
+    fn serve(body: Vec<u8>, connections: usize) -> (SocketAddr, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            for _ in 0..connections {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                // client may hang up early
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: audio/wav\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .and_then(|_| stream.write_all(&body));
+            }
+        });
+        (addr, server)
+    }
+
+    /// Mono WAV with gain changing every second
+    fn signal(seconds: i32) -> Vec<u8> {
+        let samples: Vec<i16> = (0..8000 * seconds)
+            .map(|i| (((i * 37) % 4_000) * (1 + i / 8000 % 5)) as i16)
+            .collect();
+        wav(1, 8000, &samples)
+    }
+
+    #[test]
+    fn analyze_served_wav() {
+        let (addr, server) = serve(signal(4), 1);
+        let results = analyze_url(&format!("http://{addr}/track.wav?x=1")).unwrap();
+        server.join().unwrap();
+        assert_eq!(results.frames(), 8000 * 4);
+        assert_eq!(results.channels(), 1);
+    }
+
+    #[test]
+    fn monitor_reconnects() {
+        let (addr, server) = serve(signal(12), 2);
+        let options = MonitorOptions {
+            interval: Duration::from_secs(3),
+            window: Duration::from_secs(6),
+            reconnect_delay: Duration::ZERO,
+            ..Default::default()
+        };
+
+        let mut reports = Vec::new();
+        monitor_url(&format!("http://{addr}/live"), &options, |report| {
+            reports.push(*report);
+            if reports.len() == 6 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+        server.join().unwrap();
+
+        assert_eq!(reports.len(), 6);
+        assert_eq!(reports[3].reconnects, 0);
+        assert_eq!(reports[5].reconnects, 1);
+        assert_eq!(reports[5].duration, Duration::from_secs(18));
+        // second peak needs at least two blocks
+        assert!(reports[1..].iter().all(|r| r.rolling_dr.is_finite()));
+    }
+}
@@ -1,12 +1,114 @@
 //!  Implementation of the [DR Meter](https://web.archive.org/web/20180917133436/http://www.dynamicrange.de/sites/default/files/Measuring%20DR%20ENv3.pdf).
 
+/// Emit debug event with the `log` crate, no-op without the `log` feature
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::debug!(target: "drmeter", $($arg)+);
+    }};
+}
+
+/// Emit trace event with the `log` crate, no-op without the `log` feature
+macro_rules! trace {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::trace!(target: "drmeter", $($arg)+);
+    }};
+}
+
+#[cfg(feature = "actor")]
+pub mod actor;
+mod album;
+#[cfg(feature = "symphonia")]
+mod audio_buffer;
 mod block;
+mod channel_pairs;
+mod compare;
+#[cfg(feature = "dasp")]
+mod dasp;
+#[cfg(feature = "ffmpeg")]
+pub mod decode;
+#[cfg(feature = "decoder")]
+mod decoder;
+mod deferred;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod dr_report;
 mod drmeter;
 mod error;
+#[cfg(feature = "uniffi")]
+mod ffi;
+#[cfg(feature = "claxon")]
+mod flac;
+mod histogram;
+#[cfg(feature = "http")]
+mod http;
+mod math;
+mod metadata;
+mod multi_program;
+mod overlap;
+mod parallel;
+mod pcm;
+mod peak_hold;
+mod pool;
+#[cfg(feature = "profiling")]
+mod profile;
+pub mod report;
+mod results;
+#[cfg(feature = "bincode")]
+mod schema;
+mod score;
+#[cfg(feature = "futures")]
+mod sink;
+mod true_peak;
+pub mod units;
 mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "hound")]
+mod wav;
+mod weighting;
 
+pub use self::album::*;
+pub use self::channel_pairs::{DUAL_MONO_THRESHOLD, POLARITY_INVERSION_THRESHOLD};
+pub use self::compare::*;
+#[cfg(feature = "dasp")]
+pub use self::dasp::*;
+#[cfg(feature = "decoder")]
+pub use self::decoder::*;
+pub use self::deferred::*;
+#[cfg(feature = "diagnostics")]
+pub use self::diagnostics::*;
+pub use self::dr_report::*;
 pub use self::drmeter::*;
 pub use self::error::*;
+#[cfg(feature = "uniffi")]
+pub use self::ffi::*;
+#[cfg(feature = "claxon")]
+pub use self::flac::*;
+pub use self::histogram::{HistogramMode, Quantization};
+#[cfg(feature = "http")]
+pub use self::http::*;
+pub use self::metadata::*;
+pub use self::multi_program::*;
+pub use self::pcm::*;
+pub use self::peak_hold::PeakLevel;
+pub use self::pool::*;
+#[cfg(feature = "profiling")]
+pub use self::profile::Profile;
+pub use self::results::*;
+#[cfg(feature = "bincode")]
+pub use self::schema::RESULTS_SCHEMA_VERSION;
+pub use self::score::*;
+#[cfg(feature = "futures")]
+pub use self::sink::*;
+pub use self::utils::{Interleaved, Planar, Sample, Samples};
+#[cfg(feature = "hound")]
+pub use self::wav::*;
+pub use self::weighting::Weighting;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
 
 #[cfg(test)]
 pub mod tests {
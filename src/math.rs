@@ -0,0 +1,105 @@
+//! Math backend
+//!
+//! All float functions that need std intrinsics are routed through here,
+//! so the measurement core can use `libm` instead. Its functions are
+//! implemented in Rust, so results do not depend on platform math library.
+//! The crate still needs std either way.
+#![allow(dead_code)]
+
+#[cfg(feature = "libm")]
+mod backend {
+    #[inline(always)]
+    pub fn sqrt(x: f64) -> f64 {
+        libm::sqrt(x)
+    }
+
+    #[inline(always)]
+    pub fn log10(x: f64) -> f64 {
+        libm::log10(x)
+    }
+
+    #[inline(always)]
+    pub fn powf(x: f64, y: f64) -> f64 {
+        libm::pow(x, y)
+    }
+
+    #[inline(always)]
+    pub fn round(x: f64) -> f64 {
+        libm::round(x)
+    }
+
+    #[inline(always)]
+    pub fn floor(x: f64) -> f64 {
+        libm::floor(x)
+    }
+
+    #[inline(always)]
+    pub fn tan(x: f64) -> f64 {
+        libm::tan(x)
+    }
+
+    #[inline(always)]
+    pub fn cos(x: f64) -> f64 {
+        libm::cos(x)
+    }
+
+    #[inline(always)]
+    pub fn sin_cos(x: f64) -> (f64, f64) {
+        libm::sincos(x)
+    }
+
+    #[inline(always)]
+    pub fn hypot(x: f64, y: f64) -> f64 {
+        libm::hypot(x, y)
+    }
+}
+
+#[cfg(not(feature = "libm"))]
+mod backend {
+    #[inline(always)]
+    pub fn sqrt(x: f64) -> f64 {
+        f64::sqrt(x)
+    }
+
+    #[inline(always)]
+    pub fn log10(x: f64) -> f64 {
+        f64::log10(x)
+    }
+
+    #[inline(always)]
+    pub fn powf(x: f64, y: f64) -> f64 {
+        f64::powf(x, y)
+    }
+
+    #[inline(always)]
+    pub fn round(x: f64) -> f64 {
+        f64::round(x)
+    }
+
+    #[inline(always)]
+    pub fn floor(x: f64) -> f64 {
+        f64::floor(x)
+    }
+
+    #[inline(always)]
+    pub fn tan(x: f64) -> f64 {
+        f64::tan(x)
+    }
+
+    #[inline(always)]
+    pub fn cos(x: f64) -> f64 {
+        f64::cos(x)
+    }
+
+    #[inline(always)]
+    pub fn sin_cos(x: f64) -> (f64, f64) {
+        f64::sin_cos(x)
+    }
+
+    #[inline(always)]
+    pub fn hypot(x: f64, y: f64) -> f64 {
+        f64::hypot(x, y)
+    }
+}
+
+pub use self::backend::*;
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Metadata of measured track (as read from container/tags)
+///
+/// This is not used for measurement, only rendered by reports.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrackMetadata {
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    /// Artist of whole album (e.g. `Various Artists` of compilations)
+    pub album_artist: Option<String>,
+    /// Disc number of multi-disc album (starting with 1)
+    pub disc: Option<u32>,
+    /// Codec name (e.g. `FLAC`)
+    pub codec: Option<String>,
+    /// Bits per sample of source
+    pub bit_depth: Option<u32>,
+    /// Sample rate of source
+    pub sample_rate: Option<u32>,
+    /// Duration of source
+    pub duration: Option<Duration>,
+}
+
+impl TrackMetadata {
+    /// Returns `true` if no metadata is set.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Parse disc number tag, like `2` or `2/3` (disc 2 of 3)
+    pub fn parse_disc(tag: &str) -> Option<u32> {
+        let disc = tag.split('/').next()?.trim().parse().ok()?;
+        (disc > 0).then_some(disc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_disc() {
+        assert_eq!(TrackMetadata::parse_disc("2"), Some(2));
+        assert_eq!(TrackMetadata::parse_disc(" 1/2"), Some(1));
+        assert_eq!(TrackMetadata::parse_disc("0"), None);
+        assert_eq!(TrackMetadata::parse_disc("A"), None);
+    }
+}
@@ -0,0 +1,228 @@
+//! Metering of independent programs carried by one multichannel feed
+//!
+//! For example SDI embedded audio often carries four stereo programs
+//! in eight channels, every program gets its own [`DRMeter`].
+
+use crate::utils::{Interleaved, Planar, Remapped, Sample};
+use crate::{DRMeter, DRResults, Error};
+
+/// Program of a [`MultiProgramMeter`]
+#[derive(Debug)]
+struct Program {
+    /// feed channels of program
+    channels: Box<[usize]>,
+    meter: DRMeter,
+}
+
+/// Meter of independent programs carried by one multichannel feed
+///
+/// Frames of the whole feed are added once and every program
+/// is measured by its own meter from its channels.
+#[derive(Debug)]
+pub struct MultiProgramMeter {
+    /// number of feed channels
+    channels: u32,
+    programs: Vec<Program>,
+}
+
+impl MultiProgramMeter {
+    /// Create a new instance with default window of 3s.
+    ///
+    /// Every program is a list of feed channels,
+    /// a feed channel can be part of at most one program.
+    pub fn new(channels: u32, rate: u32, programs: &[&[u32]]) -> Result<Self, Error> {
+        Self::new_with_window(channels, rate, 3000, programs)
+    }
+
+    /// Create a new instance with the given window.
+    pub fn new_with_window(
+        channels: u32,
+        rate: u32,
+        window: usize,
+        programs: &[&[u32]],
+    ) -> Result<Self, Error> {
+        if programs.is_empty() {
+            return Err(Error::ArgOutside);
+        }
+        let mut used = vec![false; channels as usize];
+        let programs = programs
+            .iter()
+            .map(|program| {
+                for &ch in program.iter() {
+                    match used.get_mut(ch as usize) {
+                        Some(used) if !*used => *used = true,
+                        _ => return Err(Error::ArgOutside),
+                    }
+                }
+                Ok(Program {
+                    channels: program.iter().map(|&ch| ch as usize).collect(),
+                    meter: DRMeter::new_with_window(program.len() as u32, rate, window)?,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self { channels, programs })
+    }
+
+    /// Create a new instance of consecutive stereo programs
+    /// (channels 1+2, 3+4, ...) with default window of 3s.
+    pub fn stereo_pairs(channels: u32, rate: u32) -> Result<Self, Error> {
+        if !channels.is_multiple_of(2) {
+            return Err(Error::ArgOutside);
+        }
+        let pairs: Vec<[u32; 2]> = (0..channels / 2).map(|p| [2 * p, 2 * p + 1]).collect();
+        let pairs: Vec<&[u32]> = pairs.iter().map(|p| p.as_slice()).collect();
+        Self::new(channels, rate, &pairs)
+    }
+
+    /// Returns the number of feed channels.
+    pub const fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Returns the number of programs.
+    pub fn programs(&self) -> usize {
+        self.programs.len()
+    }
+
+    /// Returns the meter of program
+    pub fn program(&self, index: usize) -> Option<&DRMeter> {
+        self.programs.get(index).map(|program| &program.meter)
+    }
+
+    /// Returns the meter of program, so it can be configured
+    pub fn program_mut(&mut self, index: usize) -> Option<&mut DRMeter> {
+        self.programs
+            .get_mut(index)
+            .map(|program| &mut program.meter)
+    }
+
+    /// Returns error of input not matching feed channels at current position.
+    fn invalid_input(&self) -> Error {
+        self.programs[0].meter.invalid_input()
+    }
+
+    fn add_interleaved<T: Sample>(&mut self, frames: &[T]) -> Result<(), Error> {
+        let src =
+            Interleaved::new(frames, self.channels as usize).map_err(|_| self.invalid_input())?;
+        for program in &mut self.programs {
+            program
+                .meter
+                .add_samples(Remapped::new(src, &program.channels))?;
+        }
+        Ok(())
+    }
+
+    fn add_planar<T: Sample>(&mut self, frames: &[&[T]]) -> Result<(), Error> {
+        if frames.len() != self.channels as usize {
+            return Err(self.invalid_input());
+        }
+        let src = Planar::new(frames).map_err(|_| self.invalid_input())?;
+        for program in &mut self.programs {
+            program
+                .meter
+                .add_samples(Remapped::new(src, &program.channels))?;
+        }
+        Ok(())
+    }
+
+    /// Add interleaved frames of the feed to be processed.
+    pub fn add_frames_i16(&mut self, frames: &[i16]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames of the feed to be processed.
+    pub fn add_frames_i32(&mut self, frames: &[i32]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames of the feed to be processed.
+    pub fn add_frames_f32(&mut self, frames: &[f32]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add interleaved frames of the feed to be processed.
+    pub fn add_frames_f64(&mut self, frames: &[f64]) -> Result<(), Error> {
+        self.add_interleaved(frames)
+    }
+
+    /// Add planar frames of the feed to be processed.
+    pub fn add_frames_planar_i16(&mut self, frames: &[&[i16]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames of the feed to be processed.
+    pub fn add_frames_planar_i32(&mut self, frames: &[&[i32]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames of the feed to be processed.
+    pub fn add_frames_planar_f32(&mut self, frames: &[&[f32]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Add planar frames of the feed to be processed.
+    pub fn add_frames_planar_f64(&mut self, frames: &[&[f64]]) -> Result<(), Error> {
+        self.add_planar(frames)
+    }
+
+    /// Finalize meters of all programs (marking end of stream)
+    pub fn finalize(&mut self) -> Result<(), Error> {
+        self.programs
+            .iter_mut()
+            .try_for_each(|program| program.meter.finalize())
+    }
+
+    /// Return results of every program in order they were declared
+    pub fn results(&self) -> Result<Vec<DRResults>, Error> {
+        self.programs
+            .iter()
+            .map(|program| program.meter.results())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stereo_programs() {
+        let feed: Vec<f32> = (0..48_000 * 7)
+            .flat_map(|i| {
+                let s = f32::sin(i as f32 * 0.01) * (i % 5_000) as f32 / 5_000.0;
+                let t = f32::sin(i as f32 * 0.003);
+                [s, 0.5 * s, 0.0, t, 0.25 * t, 0.1]
+            })
+            .collect();
+        let mut meter = MultiProgramMeter::new(6, 48_000, &[&[0, 1], &[3, 4]]).unwrap();
+        meter.add_frames_f32(&feed).unwrap();
+        meter.finalize().unwrap();
+        let results = meter.results().unwrap();
+        assert_eq!(results.len(), 2);
+
+        for (program, channels) in [[0, 1], [3, 4]].into_iter().enumerate() {
+            let data: Vec<f32> = feed
+                .chunks_exact(6)
+                .flat_map(|frame| channels.map(|ch| frame[ch]))
+                .collect();
+            let mut dr = DRMeter::new(2, 48_000).unwrap();
+            dr.add_frames_f32(&data).unwrap();
+            dr.finalize().unwrap();
+            assert_eq!(results[program], dr.results().unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_programs() {
+        assert!(MultiProgramMeter::new(4, 48_000, &[&[0, 1], &[1, 2]]).is_err());
+        assert!(MultiProgramMeter::new(4, 48_000, &[&[0, 4]]).is_err());
+        assert!(MultiProgramMeter::stereo_pairs(3, 48_000).is_err());
+        assert_eq!(
+            MultiProgramMeter::stereo_pairs(8, 48_000)
+                .unwrap()
+                .programs(),
+            4
+        );
+    }
+}
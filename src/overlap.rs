@@ -0,0 +1,188 @@
+//! Overlapping blocks made of shorter hops
+//!
+//! Blocks are measured every hop instead of every window, so each block
+//! shares all but one hop with the previous one. Peak and energy of the
+//! last hops are kept, which is enough to combine them into a block.
+
+use std::collections::VecDeque;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::math;
+
+/// Hop of overlapping block as `(start, peak, energy, frames)`, energy is `rms² · frames`
+type Hop = (u64, Box<[f64]>, Box<[f64]>, usize);
+
+/// Start (in frames) and peak and RMS of block per channel
+type Levels = (u64, Box<[f64]>, Box<[f64]>);
+
+/// Last hops of overlapping blocks
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Overlap {
+    /// hop length in ms
+    hop: usize,
+    /// frames of hop
+    hop_frames: usize,
+    /// hops per block
+    hops: usize,
+    /// last hops, oldest first
+    ring: VecDeque<Hop>,
+    /// newest hop is not part of any measured block yet
+    pending: bool,
+}
+
+impl Overlap {
+    /// Hops of `hop` ms for blocks of `window` ms, `None` if hops do not tile the block
+    pub fn new(hop: usize, window: usize, rate: u32) -> Option<Self> {
+        if hop == 0 || hop > window || !window.is_multiple_of(hop) {
+            return None;
+        }
+        let hops = window / hop;
+        let hop_frames = (rate as usize).checked_mul(hop)? / 1000;
+        let block_frames = (rate as usize).checked_mul(window)? / 1000;
+        if hop_frames == 0 || hop_frames * hops != block_frames {
+            return None;
+        }
+
+        Some(Self {
+            hop,
+            hop_frames,
+            hops,
+            ring: VecDeque::with_capacity(hops),
+            pending: false,
+        })
+    }
+
+    /// Hop length in ms
+    pub const fn hop(&self) -> usize {
+        self.hop
+    }
+
+    /// Frames of hop
+    pub const fn hop_frames(&self) -> usize {
+        self.hop_frames
+    }
+
+    /// Add finished hop starting at `start`,
+    /// returns block ending with it once there are enough hops
+    pub fn push(
+        &mut self,
+        start: u64,
+        peak: Box<[f64]>,
+        rms: &[f64],
+        frames: usize,
+    ) -> Option<Levels> {
+        let energy = rms.iter().map(|rms| rms * rms * frames as f64).collect();
+        if self.ring.len() == self.hops {
+            self.ring.pop_front();
+        }
+        self.ring.push_back((start, peak, energy, frames));
+        self.pending = self.ring.len() < self.hops;
+        (!self.pending).then(|| self.block())
+    }
+
+    /// Returns block of hops, that are not part of any measured block yet,
+    /// and forgets all hops (at end of stream or discontinuity).
+    pub fn flush(&mut self) -> Option<Levels> {
+        let block = self.pending.then(|| self.block());
+        self.clear();
+        block
+    }
+
+    /// Forget all hops
+    pub fn clear(&mut self) {
+        self.ring.clear();
+        self.pending = false;
+    }
+
+    /// Start, peak and RMS of block made of all hops
+    fn block(&self) -> Levels {
+        let (start, channels) = self
+            .ring
+            .front()
+            .map_or((0, 0), |(start, peak, ..)| (*start, peak.len()));
+        let frames = self.ring.iter().map(|(.., frames)| frames).sum::<usize>() as f64;
+        let peak = (0..channels)
+            .map(|ch| {
+                self.ring
+                    .iter()
+                    .map(|(_, peak, ..)| peak[ch])
+                    .fold(0.0, f64::max)
+            })
+            .collect();
+        let rms = (0..channels)
+            .map(|ch| {
+                math::sqrt(
+                    self.ring
+                        .iter()
+                        .map(|(_, _, energy, _)| energy[ch])
+                        .sum::<f64>()
+                        / frames,
+                )
+            })
+            .collect();
+        (start, peak, rms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::{DRMeter, Error, WindowFunction};
+
+    /// Frames of 10ms hops (at 1kHz) with given peaks
+    fn hops(peaks: &[f32]) -> Vec<f32> {
+        peaks
+            .iter()
+            .flat_map(|peak| {
+                let mut hop = [peak / 2.0; 10];
+                hop[5] = *peak;
+                hop
+            })
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_blocks() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 30).unwrap();
+        dr.set_hop(Some(10)).unwrap();
+        assert_eq!(dr.hop(), Some(10));
+        dr.add_frames_f32(&hops(&[0.5, 0.6, 0.4, 0.9, 0.3]))
+            .unwrap();
+        let block = dr.last_block().unwrap();
+        assert_eq!((block.index, block.start), (2, 20));
+        assert_float_eq!(block.peak[0], 0.9, abs <= 1e-6);
+        // 0.4, 0.9 and 0.3 hops
+        let energy = 3.25 * (0.16 + 0.81 + 0.09);
+        assert_float_eq!(block.rms[0], f64::sqrt(2.0 * energy / 30.0), abs <= 1e-6);
+        dr.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 3);
+
+        // stream shorter than window is one block
+        let mut dr = DRMeter::new_with_window(1, 1000, 30).unwrap();
+        dr.set_hop(Some(10)).unwrap();
+        dr.add_frames_f32(&hops(&[0.5, 0.6])[..15]).unwrap();
+        dr.finalize().unwrap();
+        assert_eq!(dr.results().unwrap().blocks(), 1);
+        assert_float_eq!(dr.first_peak(0).unwrap(), 0.5, abs <= 1e-4);
+    }
+
+    #[test]
+    fn invalid_hop() {
+        let mut dr = DRMeter::new_with_window(1, 1000, 30).unwrap();
+        assert_eq!(dr.set_hop(Some(20)), Err(Error::ArgOutside));
+        assert_eq!(dr.set_hop(Some(60)), Err(Error::ArgOutside));
+        dr.set_window_function(WindowFunction::Hann).unwrap();
+        assert_eq!(dr.set_hop(Some(10)), Err(Error::ArgOutside));
+        dr.set_window_function(WindowFunction::Rectangular).unwrap();
+        dr.set_hop(Some(10)).unwrap();
+        assert_eq!(
+            dr.set_window_function(WindowFunction::Hann),
+            Err(Error::ArgOutside)
+        );
+        assert_eq!(dr.set_exact_duration(100), Err(Error::ArgOutside));
+    }
+}
@@ -0,0 +1,146 @@
+//! Parallel metering of a single long buffer
+//!
+//! Buffer is split into block-aligned segments, every segment is metered
+//! by its own meter on a separate thread and the meters are merged back.
+//! Results are the same as with sequential metering.
+
+use std::thread;
+
+use crate::drmeter::first_non_finite;
+use crate::utils::{Interleaved, Sample};
+use crate::weighting::Weighting;
+use crate::{DRMeter, Error, PeakMode};
+
+impl DRMeter {
+    fn add_frames_parallel<T: Sample + Sync>(
+        &mut self,
+        frames: &[T],
+        threads: usize,
+        add: fn(&mut DRMeter, &[T]) -> Result<(), Error>,
+    ) -> Result<(), Error> {
+        if self.finalized() {
+            return Err(Error::Finalized);
+        }
+
+        // block schedule of exact duration is not uniform,
+        // weighting filter state runs across blocks,
+        // duration limit is applied to frames in order
+        // stems are not split into segments,
+        // oversampling filter state runs across blocks
+        // and overlapping blocks span segments
+        if self.exact_duration()
+            || self.weighting() != Weighting::None
+            || self.peak_mode() == PeakMode::True
+            || self.hop().is_some()
+            || self.max_frames().is_some()
+            || self.has_stems()
+            || !self.track_boundaries().is_empty()
+            || self.has_block_callback()
+        {
+            return add(self, frames);
+        }
+
+        let channels = self.channels() as usize;
+        // non-finite samples are handled (and located) sequentially
+        if first_non_finite(&Interleaved::new(frames, channels)?).is_some() {
+            return add(self, frames);
+        }
+
+        let block = self.block_frames() * channels;
+        let threads = match threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+
+        // finish current partial block sequentially
+        let partial = (self.frames() % self.block_frames() as u64) as usize * channels;
+        let head = if partial == 0 { 0 } else { block - partial }.min(frames.len());
+        let (head, rest) = frames.split_at(head);
+        add(self, head)?;
+
+        // whole blocks are metered in parallel, remainder sequentially
+        let blocks = rest.len() / block;
+        let (body, tail) = rest.split_at(blocks * block);
+        if blocks != 0 {
+            let segment = blocks.div_ceil(threads) * block;
+            let meters = thread::scope(|s| {
+                let workers: Vec<_> = body
+                    .chunks(segment)
+                    .map(|segment| {
+                        let meter = self.empty_clone();
+                        s.spawn(move || {
+                            let mut meter = meter?;
+                            add(&mut meter, segment)?;
+                            Ok(meter)
+                        })
+                    })
+                    .collect();
+                workers
+                    .into_iter()
+                    .map(|w| w.join().expect("metering thread panicked"))
+                    .collect::<Result<Vec<DRMeter>, Error>>()
+            })?;
+            for meter in &meters {
+                self.merge_blocks(meter);
+            }
+        }
+
+        add(self, tail)
+    }
+
+    /// Add interleaved frames to be processed on `threads` threads
+    /// (`0` uses all available cores).
+    ///
+    /// This pays off for long buffers, that span many blocks.
+    pub fn add_frames_parallel_i16(&mut self, frames: &[i16], threads: usize) -> Result<(), Error> {
+        self.add_frames_parallel(frames, threads, Self::add_frames_i16)
+    }
+
+    /// Add interleaved frames to be processed on `threads` threads
+    /// (`0` uses all available cores).
+    ///
+    /// This pays off for long buffers, that span many blocks.
+    pub fn add_frames_parallel_i32(&mut self, frames: &[i32], threads: usize) -> Result<(), Error> {
+        self.add_frames_parallel(frames, threads, Self::add_frames_i32)
+    }
+
+    /// Add interleaved frames to be processed on `threads` threads
+    /// (`0` uses all available cores).
+    ///
+    /// This pays off for long buffers, that span many blocks.
+    pub fn add_frames_parallel_f32(&mut self, frames: &[f32], threads: usize) -> Result<(), Error> {
+        self.add_frames_parallel(frames, threads, Self::add_frames_f32)
+    }
+
+    /// Add interleaved frames to be processed on `threads` threads
+    /// (`0` uses all available cores).
+    ///
+    /// This pays off for long buffers, that span many blocks.
+    pub fn add_frames_parallel_f64(&mut self, frames: &[f64], threads: usize) -> Result<(), Error> {
+        self.add_frames_parallel(frames, threads, Self::add_frames_f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_as_sequential() {
+        let data: Vec<f32> = (0..8000 * 2 * 40)
+            .map(|i| f32::sin(i as f32 * 0.01) * (i % 50_000) as f32 / 50_000.0)
+            .collect();
+        let mut expected = DRMeter::new(2, 8000).unwrap();
+        expected.add_frames_f32(&data).unwrap();
+        expected.finalize().unwrap();
+
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        // start with partial block
+        dr.add_frames_f32(&data[..1000]).unwrap();
+        dr.add_frames_parallel_f32(&data[1000..], 3).unwrap();
+        dr.finalize().unwrap();
+
+        assert_eq!(dr.frames(), expected.frames());
+        assert_eq!(dr.results(), expected.results());
+    }
+}
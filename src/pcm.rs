@@ -0,0 +1,389 @@
+//! Raw PCM input
+//!
+//! Raw PCM bytes are decoded according to [`SampleFormat`] and fed into
+//! the meter, either from a byte slice, from a [`Read`]er
+//! or (with `tokio` feature) from an `AsyncRead`.
+
+use std::io::{self, Read};
+
+use crate::utils::Sample;
+use crate::{DRMeter, Error};
+
+/// Type of samples in raw PCM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleType {
+    /// 16-bit signed integer
+    I16,
+    /// 24-bit signed integer packed in 3 bytes
+    I24,
+    /// 32-bit signed integer
+    I32,
+    /// 32-bit float
+    F32,
+    /// 64-bit float
+    F64,
+}
+
+/// Byte order of samples in raw PCM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Arrangement of channels in raw PCM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// Samples of a frame follow each other
+    #[default]
+    Interleaved,
+    /// All samples of a channel follow each other, channel after channel
+    Planar,
+}
+
+/// Format of raw PCM samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleFormat {
+    pub sample_type: SampleType,
+    pub endianness: Endianness,
+    pub layout: Layout,
+}
+
+impl SampleFormat {
+    /// Create a new format of interleaved samples
+    pub const fn new(sample_type: SampleType, endianness: Endianness) -> Self {
+        Self {
+            sample_type,
+            endianness,
+            layout: Layout::Interleaved,
+        }
+    }
+
+    /// Returns the same format with the given layout
+    pub const fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Size of one sample in bytes
+    pub const fn bytes_per_sample(&self) -> usize {
+        match self.sample_type {
+            SampleType::I16 => 2,
+            SampleType::I24 => 3,
+            SampleType::I32 | SampleType::F32 => 4,
+            SampleType::F64 => 8,
+        }
+    }
+}
+
+macro_rules! decode {
+    ($bytes:expr, $endianness:expr, $t:ty, $n:expr) => {
+        $bytes
+            .chunks_exact($n)
+            .map(|b| {
+                let b: [u8; $n] = b.try_into().unwrap();
+                match $endianness {
+                    Endianness::Little => <$t>::from_le_bytes(b),
+                    Endianness::Big => <$t>::from_be_bytes(b),
+                }
+            })
+            .collect::<Vec<$t>>()
+    };
+}
+
+/// Decoded samples
+enum Samples {
+    I16(Vec<i16>),
+    I32(Vec<i32>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+}
+
+/// Add decoded samples in given layout to the meter
+fn add_decoded<T: Sample>(meter: &mut DRMeter, samples: &[T], layout: Layout) -> Result<(), Error> {
+    match layout {
+        Layout::Interleaved => meter.add_interleaved(samples),
+        Layout::Planar => {
+            let frames = samples.len() / meter.channels() as usize;
+            if frames == 0 {
+                return Ok(());
+            }
+            let planes: Vec<&[T]> = samples.chunks_exact(frames).collect();
+            meter.add_planar(&planes)
+        }
+    }
+}
+
+/// Decode raw PCM bytes and add them to the meter
+///
+/// Length of `bytes` must be whole number of frames.
+fn add_pcm(meter: &mut DRMeter, bytes: &[u8], format: SampleFormat) -> Result<(), Error> {
+    #[cfg(feature = "profiling")]
+    let timer = crate::profile::Timer::start();
+
+    let samples = match format.sample_type {
+        SampleType::I16 => Samples::I16(decode!(bytes, format.endianness, i16, 2)),
+        SampleType::I24 => {
+            // 24-bit samples are widened to the top of i32, which keeps their scale
+            let samples: Vec<i32> = bytes
+                .chunks_exact(3)
+                .map(|b| match format.endianness {
+                    Endianness::Little => i32::from_le_bytes([0, b[0], b[1], b[2]]),
+                    Endianness::Big => i32::from_be_bytes([b[0], b[1], b[2], 0]),
+                })
+                .collect();
+            Samples::I32(samples)
+        }
+        SampleType::I32 => Samples::I32(decode!(bytes, format.endianness, i32, 4)),
+        SampleType::F32 => Samples::F32(decode!(bytes, format.endianness, f32, 4)),
+        SampleType::F64 => Samples::F64(decode!(bytes, format.endianness, f64, 8)),
+    };
+
+    #[cfg(feature = "profiling")]
+    timer.stop(meter.conversion_time());
+
+    match samples {
+        Samples::I16(s) => add_decoded(meter, &s, format.layout),
+        Samples::I32(s) => add_decoded(meter, &s, format.layout),
+        Samples::F32(s) => add_decoded(meter, &s, format.layout),
+        Samples::F64(s) => add_decoded(meter, &s, format.layout),
+    }
+}
+
+/// Size of read chunks in frames
+const CHUNK_FRAMES: usize = 4096;
+
+/// Buffer that collects read bytes into whole frames
+struct PcmBuffer {
+    buf: Vec<u8>,
+    filled: usize,
+    frame_size: usize,
+    format: SampleFormat,
+    frames: u64,
+}
+
+impl PcmBuffer {
+    fn new(meter: &DRMeter, format: SampleFormat) -> io::Result<Self> {
+        if format.layout == Layout::Planar {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "planar PCM cannot be streamed",
+            ));
+        }
+        let frame_size = format.bytes_per_sample() * meter.channels() as usize;
+        Ok(Self {
+            buf: vec![0; frame_size * CHUNK_FRAMES],
+            filled: 0,
+            frame_size,
+            format,
+            frames: 0,
+        })
+    }
+
+    /// Space for reading
+    fn unfilled(&mut self) -> &mut [u8] {
+        &mut self.buf[self.filled..]
+    }
+
+    /// Mark `n` bytes as read and add whole frames to meter
+    fn advance(&mut self, meter: &mut DRMeter, n: usize) -> io::Result<()> {
+        self.filled += n;
+        let whole = self.filled - self.filled % self.frame_size;
+        add_pcm(meter, &self.buf[..whole], self.format)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.buf.copy_within(whole..self.filled, 0);
+        self.filled -= whole;
+        self.frames += (whole / self.frame_size) as u64;
+        Ok(())
+    }
+
+    /// Finish reading, failing on trailing partial frame
+    fn finish(self) -> io::Result<u64> {
+        if self.filled != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended with partial frame",
+            ));
+        }
+        Ok(self.frames)
+    }
+}
+
+impl DRMeter {
+    /// Add interleaved frames of 24-bit little-endian samples packed in 3 bytes
+    /// (the usual layout of 24-bit WAV) to be processed.
+    pub fn add_frames_i24_packed(&mut self, frames: &[u8]) -> Result<(), Error> {
+        self.add_frames_bytes(
+            frames,
+            SampleFormat::new(SampleType::I24, Endianness::Little),
+        )
+    }
+
+    /// Add frames of raw PCM bytes in given format to be processed.
+    ///
+    /// Length of `frames` must be whole number of frames.
+    pub fn add_frames_bytes(&mut self, frames: &[u8], format: SampleFormat) -> Result<(), Error> {
+        let frame_size = format.bytes_per_sample() * self.channels() as usize;
+        if !frames.len().is_multiple_of(frame_size) {
+            return Err(self.invalid_input());
+        }
+        add_pcm(self, frames, format)
+    }
+
+    /// Read interleaved raw PCM until end of stream and add it to be processed
+    ///
+    /// Returns number of read frames.
+    /// Planar formats cannot be streamed and fail with [`io::ErrorKind::InvalidInput`].
+    pub fn read_pcm<R: Read>(&mut self, mut reader: R, format: SampleFormat) -> io::Result<u64> {
+        let mut buf = PcmBuffer::new(self, format)?;
+        loop {
+            let n = match reader.read(buf.unfilled()) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            buf.advance(self, n)?;
+        }
+
+        buf.finish()
+    }
+
+    /// Read interleaved raw PCM from async reader until end of stream and add it to be processed
+    ///
+    /// This yields to the runtime after every processed chunk.
+    /// Returns number of read frames.
+    #[cfg(feature = "tokio")]
+    pub async fn read_pcm_async<R: tokio::io::AsyncRead + Unpin>(
+        &mut self,
+        mut reader: R,
+        format: SampleFormat,
+    ) -> io::Result<u64> {
+        use tokio::io::AsyncReadExt;
+
+        let mut buf = PcmBuffer::new(self, format)?;
+        loop {
+            let n = reader.read(buf.unfilled()).await?;
+            if n == 0 {
+                break;
+            }
+            buf.advance(self, n)?;
+            tokio::task::yield_now().await;
+        }
+
+        buf.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pcm() -> (Vec<i16>, Vec<u8>) {
+        let samples: Vec<i16> = (0..8000 * 2 * 4)
+            .map(|i| ((i as f32 * 0.05).sin() * (i % 16_000) as f32) as i16)
+            .collect();
+        let bytes = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+        (samples, bytes)
+    }
+
+    #[test]
+    fn read() {
+        let (samples, bytes) = pcm();
+        let mut expected = DRMeter::new(2, 8000).unwrap();
+        expected.add_frames_i16(&samples).unwrap();
+
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        let format = SampleFormat::new(SampleType::I16, Endianness::Big);
+        assert_eq!(dr.read_pcm(bytes.as_slice(), format).unwrap(), 8000 * 4);
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        assert!(dr.read_pcm(&bytes[..bytes.len() - 1], format).is_err());
+    }
+
+    #[test]
+    fn i24() {
+        let mut dr = DRMeter::new(1, 8000).unwrap();
+        let format = SampleFormat::new(SampleType::I24, Endianness::Little);
+        // -2²³ is full scale
+        let bytes: Vec<u8> = [0x00, 0x00, 0x80].repeat(8000 * 3);
+        dr.read_pcm(bytes.as_slice(), format).unwrap();
+        assert_eq!(dr.first_peak(0).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn i24_packed() {
+        let samples: Vec<i32> = (0..8000 * 2 * 4)
+            .map(|i| ((i as f32 * 0.05).sin() * (i % 16_000) as f32 * 500.0) as i32)
+            .collect();
+        let bytes: Vec<u8> = samples
+            .iter()
+            .flat_map(|s| s.to_le_bytes()[..3].to_vec())
+            .collect();
+        let mut expected = DRMeter::new(2, 8000).unwrap();
+        let widened: Vec<i32> = samples.iter().map(|s| s << 8).collect();
+        expected.add_frames_i32(&widened).unwrap();
+
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        dr.add_frames_i24_packed(&bytes).unwrap();
+        assert_eq!(dr.frames(), 8000 * 4);
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+        assert_eq!(
+            dr.add_frames_i24_packed(&bytes[..5]),
+            Err(Error::InvalidInput { frame: 8000 * 4 })
+        );
+        assert_eq!(
+            dr.add_frames_i24_packed(&bytes[..3]),
+            Err(Error::InvalidInput { frame: 8000 * 4 })
+        );
+    }
+
+    #[test]
+    fn bytes() {
+        let (samples, bytes) = pcm();
+        let mut expected = DRMeter::new(2, 8000).unwrap();
+        expected.add_frames_i16(&samples).unwrap();
+
+        let format = SampleFormat::new(SampleType::I16, Endianness::Big);
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        dr.add_frames_bytes(&bytes, format).unwrap();
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+        assert_eq!(
+            dr.add_frames_bytes(&bytes[..2], format),
+            Err(Error::InvalidInput { frame: 8000 * 4 })
+        );
+
+        let planar: Vec<u8> = (0..2)
+            .flat_map(|ch| samples.iter().skip(ch).step_by(2))
+            .flat_map(|s| (*s as f32 / 32768.0).to_le_bytes())
+            .collect();
+        let format =
+            SampleFormat::new(SampleType::F32, Endianness::Little).with_layout(Layout::Planar);
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        dr.add_frames_bytes(&planar, format).unwrap();
+        assert_eq!(dr.frames(), 8000 * 4);
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+        assert_eq!(
+            dr.read_pcm(planar.as_slice(), format).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn read_async() {
+        let (samples, bytes) = pcm();
+        let mut expected = DRMeter::new(2, 8000).unwrap();
+        expected.add_frames_i16(&samples).unwrap();
+
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        let format = SampleFormat::new(SampleType::I16, Endianness::Big);
+        assert_eq!(
+            dr.read_pcm_async(bytes.as_slice(), format).await.unwrap(),
+            8000 * 4
+        );
+        assert_eq!(dr.exact_dr(), expected.exact_dr());
+    }
+}
@@ -0,0 +1,84 @@
+//! Peak hold for live meters
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::math;
+use crate::utils::{Sample, Samples};
+
+/// Peak levels of channel (linear)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PeakLevel {
+    /// Sample peak of last added frames
+    pub current: f64,
+    /// Held peak, that decays over time
+    pub held: f64,
+}
+
+/// Peak hold of all channels
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct PeakHold {
+    /// Time in seconds for held peak to fall by 20 dB
+    decay: f64,
+    levels: Box<[PeakLevel]>,
+}
+
+impl PeakHold {
+    pub fn new(channels: usize, decay: f64) -> Self {
+        Self {
+            decay,
+            levels: vec![PeakLevel::default(); channels].into_boxed_slice(),
+        }
+    }
+
+    /// Clear levels for a new stream
+    pub fn reset(&mut self) {
+        self.levels.fill(PeakLevel::default());
+    }
+
+    pub fn levels(&self) -> &[PeakLevel] {
+        &self.levels
+    }
+
+    /// Update levels with newly added frames
+    pub fn update<'a, T: Sample + 'a, S: Samples<'a, T>>(&mut self, src: &S, rate: u32) {
+        let elapsed = src.frames() as f64 / rate as f64;
+        let fall = math::powf(10.0, -elapsed / self.decay);
+
+        for (channel, level) in self.levels.iter_mut().enumerate() {
+            let mut max = 0.0;
+            src.foreach_sample(channel, |sample| {
+                let v = sample.as_f64_raw().abs();
+                if v > max {
+                    max = v;
+                }
+            });
+
+            level.current = max / T::MAX_AMPLITUDE;
+            level.held = f64::max(level.held * fall, level.current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::DRMeter;
+
+    #[test]
+    fn decay() {
+        let mut dr = DRMeter::new(1, 1000).unwrap();
+        assert!(dr.peak_levels().is_none());
+        dr.set_peak_hold(1.0).unwrap();
+
+        dr.add_frames_f32(&[0.5; 100]).unwrap();
+        dr.add_frames_f32(&[0.01; 1000]).unwrap();
+        let level = dr.peak_levels().unwrap()[0];
+        assert_float_eq!(level.current, 0.01, abs <= 1e-6);
+        // fell by 20 dB in 1s
+        assert_float_eq!(level.held, 0.05, abs <= 1e-6);
+    }
+}
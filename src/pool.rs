@@ -0,0 +1,217 @@
+//! Reuse of meters and buffers across many files
+//!
+//! Every meter allocates its histograms (about 256 KB with the default
+//! [`HistogramMode`](crate::HistogramMode)) and conversion buffers, which adds
+//! up in large scans. Workers of a batch acquire meters and buffers from
+//! a shared [`AnalyzerPool`] and release them once a file is measured.
+
+use std::sync::Mutex;
+
+use crate::{DRMeter, Error};
+
+type Configure = dyn Fn(&mut DRMeter) -> Result<(), Error> + Send + Sync;
+
+/// Pool of reset meters and reusable sample buffers
+///
+/// The pool can be shared between worker threads (e.g. in an `Arc`).
+pub struct AnalyzerPool {
+    /// window length in ms of all meters
+    window: usize,
+    /// configuration applied to every handed out meter
+    configure: Option<Box<Configure>>,
+    /// idle meters
+    meters: Mutex<Vec<DRMeter>>,
+    /// idle buffers
+    buffers: Mutex<Vec<Vec<f32>>>,
+}
+
+impl std::fmt::Debug for AnalyzerPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnalyzerPool")
+            .field("window", &self.window)
+            .field("configured", &self.configure.is_some())
+            .field("meters", &self.idle_meters())
+            .field("buffers", &self.idle_buffers())
+            .finish()
+    }
+}
+
+impl Default for AnalyzerPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnalyzerPool {
+    /// Create a new pool of meters with default window of 3s.
+    pub const fn new() -> Self {
+        Self::new_with_window(3000)
+    }
+
+    /// Create a new pool of meters with the given window.
+    pub const fn new_with_window(window: usize) -> Self {
+        Self {
+            window,
+            configure: None,
+            meters: Mutex::new(Vec::new()),
+            buffers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Configure every meter handed out by [`AnalyzerPool::acquire`].
+    ///
+    /// The configuration is applied again on reuse, options not set by it
+    /// keep the value of the previous user of the meter.
+    pub fn with_config(
+        mut self,
+        configure: impl Fn(&mut DRMeter) -> Result<(), Error> + Send + Sync + 'static,
+    ) -> Self {
+        self.configure = Some(Box::new(configure));
+        self
+    }
+
+    /// Returns the window of meters.
+    pub const fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Returns a meter that has not measured anything yet.
+    ///
+    /// An idle meter with the same number of channels and rate is reused,
+    /// otherwise a new one is created.
+    pub fn acquire(&self, channels: u32, rate: u32) -> Result<DRMeter, Error> {
+        let idle = {
+            let mut meters = self.meters.lock().unwrap_or_else(|e| e.into_inner());
+            meters
+                .iter()
+                .position(|meter| meter.channels() == channels && meter.rate() == rate)
+                .map(|i| meters.swap_remove(i))
+        };
+        let mut meter = match idle {
+            Some(mut meter) => {
+                meter.reset();
+                meter
+            }
+            None => DRMeter::new_with_window(channels, rate, self.window)?,
+        };
+        if let Some(configure) = &self.configure {
+            configure(&mut meter)?;
+        }
+        Ok(meter)
+    }
+
+    /// Return meter to the pool, so it can be handed out again.
+    ///
+    /// Meters with a different window are dropped.
+    pub fn release(&self, meter: DRMeter) {
+        if meter.window() == self.window {
+            self.meters
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(meter);
+        }
+    }
+
+    /// Returns an empty buffer for decoded or converted samples,
+    /// possibly with capacity left from previous use.
+    pub fn acquire_buffer(&self) -> Vec<f32> {
+        self.buffers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Return buffer to the pool, so it can be handed out again.
+    pub fn release_buffer(&self, mut buffer: Vec<f32>) {
+        buffer.clear();
+        self.buffers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(buffer);
+    }
+
+    /// Returns the number of idle meters.
+    pub fn idle_meters(&self) -> usize {
+        self.meters.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Returns the number of idle buffers.
+    pub fn idle_buffers(&self) -> usize {
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Weighting;
+
+    fn stereo_sine(freq: f32, amplitude: f32, seconds: usize) -> Vec<f32> {
+        (0..48_000 * seconds)
+            .map(|i| amplitude * f32::sin(i as f32 * freq * std::f32::consts::TAU / 48_000.0))
+            .flat_map(|s| [s, s])
+            .collect()
+    }
+
+    fn measure(meter: &mut DRMeter, signal: &[f32]) -> f64 {
+        meter.add_frames_f32(signal).unwrap();
+        meter.finalize().unwrap();
+        meter.exact_dr().unwrap()
+    }
+
+    #[test]
+    fn reuse() {
+        let pool = AnalyzerPool::new().with_config(|meter| meter.set_weighting(Weighting::A));
+        let first: Vec<f32> = stereo_sine(1000.0, 0.5, 10);
+        let second: Vec<f32> = stereo_sine(440.0, 0.25, 7);
+
+        let mut meter = pool.acquire(2, 48_000).unwrap();
+        assert_eq!(meter.weighting(), Weighting::A);
+        measure(&mut meter, &first);
+        pool.release(meter);
+        assert_eq!(pool.idle_meters(), 1);
+
+        // other format does not take idle meter
+        let other = pool.acquire(1, 44_100).unwrap();
+        assert_eq!(pool.idle_meters(), 1);
+        drop(other);
+
+        let mut meter = pool.acquire(2, 48_000).unwrap();
+        assert_eq!(pool.idle_meters(), 0);
+        assert_eq!(meter.weighting(), Weighting::A);
+        let reused = measure(&mut meter, &second);
+
+        let mut fresh = DRMeter::new(2, 48_000).unwrap();
+        fresh.set_weighting(Weighting::A).unwrap();
+        assert_eq!(reused, measure(&mut fresh, &second));
+    }
+
+    #[test]
+    fn buffers() {
+        let pool = AnalyzerPool::default();
+        let mut buffer = pool.acquire_buffer();
+        buffer.extend_from_slice(&[0.5; 1024]);
+        pool.release_buffer(buffer);
+        assert_eq!(pool.idle_buffers(), 1);
+
+        let buffer = pool.acquire_buffer();
+        assert!(buffer.is_empty());
+        assert!(buffer.capacity() >= 1024);
+        assert_eq!(pool.idle_buffers(), 0);
+    }
+
+    #[test]
+    fn shared() {
+        let pool = AnalyzerPool::new();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let meter = pool.acquire(2, 44_100).unwrap();
+                    pool.release(meter);
+                });
+            }
+        });
+        assert!((1..=4).contains(&pool.idle_meters()));
+    }
+}
@@ -0,0 +1,55 @@
+//! Processing time counters
+//!
+//! Time spent in every phase of the hot path is accumulated,
+//! so performance regressions can be measured in real workloads.
+
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+/// Accumulated processing time per phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Profile {
+    /// Decoding of raw PCM bytes into samples
+    pub conversion: Duration,
+    /// Sample peak measurement
+    pub peak: Duration,
+    /// Sum of squares for RMS
+    pub rms: Duration,
+    /// Binning of finished blocks and finalization of instance
+    pub finalize: Duration,
+}
+
+/// Measures elapsed time of single phase
+pub(crate) struct Timer(Instant);
+
+impl Timer {
+    pub fn start() -> Self {
+        Self(Instant::now())
+    }
+
+    /// Add elapsed time to counter
+    pub fn stop(self, counter: &mut Duration) {
+        *counter += self.0.elapsed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DRMeter;
+
+    #[test]
+    fn counters() {
+        let mut dr = DRMeter::new(2, 8000).unwrap();
+        let data: Vec<f32> = (0..8000 * 2 * 4).map(|i| f32::sin(i as f32)).collect();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+
+        let profile = dr.profile();
+        assert!(profile.peak > Default::default());
+        assert!(profile.rms > Default::default());
+        assert!(profile.finalize > Default::default());
+        assert_eq!(profile.conversion, Default::default());
+    }
+}
@@ -0,0 +1,208 @@
+//! Log in the format of foobar2000 Dynamic Range Meter (`dr.txt`)
+
+use std::io::{self, Write};
+
+use super::template::format_duration;
+use super::ReportWriter;
+use crate::units::amplitude_to_dbfs;
+use crate::{DRAlbum, DRResults, DRScore};
+
+/// Separator lines of log
+const LINE: &str =
+    "--------------------------------------------------------------------------------";
+const END: &str =
+    "================================================================================";
+
+/// Log in the format of foobar2000 Dynamic Range Meter and TT DR Offline Meter
+///
+/// It has one table row with DR, peak, RMS and duration per track
+/// and the official album DR in the footer, like the `dr.txt` logs
+/// submitted to the DR database. Peak is the highest peak of all channels
+/// and RMS is the RMS of the whole track (from block RMS histograms).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Foobar2000Report {
+    log_date: Option<String>,
+}
+
+impl Foobar2000Report {
+    /// Create a new report without log date.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `log date:` line of the header (e.g. `2024-01-31 12:00:00`).
+    pub fn with_log_date(mut self, date: impl Into<String>) -> Self {
+        self.log_date = Some(date.into());
+        self
+    }
+
+    fn write_header(&self, writer: &mut dyn Write, analyzed: &str) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{} {} / Dynamic Range Meter",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        )?;
+        if let Some(date) = &self.log_date {
+            writeln!(writer, "log date: {date}")?;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "{LINE}")?;
+        writeln!(writer, "Analyzed: {analyzed}")?;
+        writeln!(writer, "{LINE}")?;
+        writeln!(writer)?;
+        writeln!(
+            writer,
+            "{:<5}{:>13}{:>13}{:>12} Track",
+            "DR", "Peak", "RMS", "Duration"
+        )?;
+        writeln!(writer, "{LINE}")
+    }
+
+    fn write_row(writer: &mut dyn Write, name: &str, results: &DRResults) -> io::Result<()> {
+        let peak = (0..results.channels())
+            .map(|ch| results.first_peak(ch).unwrap_or_default())
+            .fold(0.0, f64::max);
+        writeln!(
+            writer,
+            "{:<5}{:>10} dB{:>10} dB{:>12} {}",
+            results.dr_score(),
+            format!("{:.2}", amplitude_to_dbfs(peak)),
+            format!("{:.2}", amplitude_to_dbfs(track_rms(results))),
+            format_duration(results.duration().as_secs()),
+            name,
+        )
+    }
+
+    fn write_footer(
+        writer: &mut dyn Write,
+        tracks: usize,
+        score: DRScore,
+        results: &DRResults,
+    ) -> io::Result<()> {
+        writeln!(writer, "{LINE}")?;
+        writeln!(writer)?;
+        writeln!(writer, "Number of tracks:  {tracks}")?;
+        writeln!(writer, "Official DR value: {score}")?;
+        writeln!(writer)?;
+        let metadata = results.metadata();
+        writeln!(
+            writer,
+            "Samplerate:        {} Hz",
+            metadata.sample_rate.unwrap_or(results.rate())
+        )?;
+        writeln!(writer, "Channels:          {}", results.channels())?;
+        if let Some(bit_depth) = metadata.bit_depth {
+            writeln!(writer, "Bits per sample:   {bit_depth}")?;
+        }
+        if let Some(codec) = &metadata.codec {
+            writeln!(writer, "Codec:             {codec}")?;
+        }
+        writeln!(writer, "{END}")
+    }
+}
+
+/// RMS of whole track averaged over channels (linear)
+///
+/// Every block is weighted equally, so this is the power mean of block RMS.
+fn track_rms(results: &DRResults) -> f64 {
+    if results.blocks() == 0 || results.channels() == 0 {
+        return 0.0;
+    }
+    let bins = results.bins() as f64;
+    let power: f64 = (0..results.channels())
+        .flat_map(|ch| results.rms_histogram(ch).unwrap_or_default())
+        .map(|&(bin, count)| count as f64 * (bin as f64 / bins).powi(2))
+        .sum();
+    (power / (results.blocks() * results.channels() as usize) as f64).sqrt()
+}
+
+/// `artist / album` of results, with placeholders for unknown values
+fn analyzed(results: Option<&DRResults>) -> String {
+    let metadata = results.map(DRResults::metadata);
+    let field = |value: Option<&Option<String>>| {
+        value
+            .and_then(Option::as_deref)
+            .unwrap_or("Unknown")
+            .to_owned()
+    };
+    format!(
+        "{} / {}",
+        field(metadata.map(|m| &m.artist)),
+        field(metadata.map(|m| &m.album))
+    )
+}
+
+impl ReportWriter for Foobar2000Report {
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        self.write_header(writer, &analyzed(Some(results)))?;
+        let name = results.metadata().title.as_deref().unwrap_or("Track");
+        Self::write_row(writer, name, results)?;
+        Self::write_footer(writer, 1, results.dr_score(), results)
+    }
+
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()> {
+        let first = album.tracks().first().map(|track| &track.results);
+        self.write_header(writer, &analyzed(first))?;
+        for track in album.tracks() {
+            Self::write_row(writer, &track.name, &track.results)?;
+        }
+        match first {
+            Some(results) => {
+                Self::write_footer(writer, album.tracks().len(), album.dr_score(), results)
+            }
+            None => {
+                writeln!(writer, "{LINE}")?;
+                writeln!(writer, "{END}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DRMeter, TrackMetadata};
+
+    #[test]
+    fn album_log() {
+        let data: Vec<f32> = (0..44_100 * 2 * 65)
+            .map(|i| 0.5 * f32::sin(i as f32 * 0.01) * (i % 88_200) as f32 / 88_200.0)
+            .collect();
+        let mut dr = DRMeter::new(2, 44_100).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        let results = dr.results().unwrap().with_metadata(TrackMetadata {
+            artist: Some("Artist".to_owned()),
+            album: Some("Album".to_owned()),
+            bit_depth: Some(16),
+            codec: Some("FLAC".to_owned()),
+            ..Default::default()
+        });
+
+        let mut album = DRAlbum::new();
+        album.add_track("01-Intro", results.clone());
+        album.add_track("02-Outro", results);
+        let mut out = Vec::new();
+        Foobar2000Report::new()
+            .with_log_date("2024-01-31 12:00:00")
+            .write_album(&mut out, &album)
+            .unwrap();
+        let log = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+
+        assert_eq!(lines[1], "log date: 2024-01-31 12:00:00");
+        assert_eq!(lines[4], "Analyzed: Artist / Album");
+        assert_eq!(
+            lines[7],
+            "DR            Peak          RMS    Duration Track"
+        );
+        let dr = album.dr_score();
+        assert!(lines[9].starts_with(&format!("{dr:<5}     -6.02 dB")));
+        assert!(lines[9].ends_with("        1:05 01-Intro"));
+        assert!(log.contains(&format!("Official DR value: {dr}\n")));
+        assert!(log.contains("Samplerate:        44100 Hz\nChannels:          2\n"));
+        assert!(log.contains("Bits per sample:   16\nCodec:             FLAC\n"));
+        assert!(log.ends_with("=\n"));
+    }
+}
@@ -0,0 +1,156 @@
+//! Self-contained HTML report
+
+use std::io::{self, Write};
+
+use super::{db, format_duration, ReportWriter};
+use crate::{Aggregation, DRAlbum, DRResults};
+
+/// Self-contained HTML report
+///
+/// With `plot` feature, RMS histogram of every track is embedded as SVG.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlReport;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:right}\
+td.name{text-align:left}\
+svg{display:block}";
+
+/// Escape HTML text
+fn html_escape(value: &str) -> String {
+    let mut s = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '"' => s.push_str("&quot;"),
+            '\'' => s.push_str("&#39;"),
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+/// RMS histogram of all channels as SVG bar chart (-60..0 dB)
+#[cfg(feature = "plot")]
+fn rms_svg(results: &DRResults) -> String {
+    use crate::units::amplitude_to_dbfs;
+
+    const WIDTH: usize = 240;
+    const HEIGHT: f64 = 40.0;
+    const RANGE: f64 = 60.0;
+
+    let mut columns = [0u64; WIDTH];
+    for ch in 0..results.channels() {
+        for &(bin, count) in results.rms_histogram(ch).unwrap_or_default() {
+            let level = amplitude_to_dbfs(bin as f64 / results.bins() as f64);
+            if level.is_finite() && level > -RANGE {
+                let x = ((level + RANGE) / RANGE * WIDTH as f64) as usize;
+                columns[x.min(WIDTH - 1)] += u64::from(count);
+            }
+        }
+    }
+
+    let max = columns.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}"><rect width="{WIDTH}" height="{HEIGHT}" fill="#f4f4f4"/>"##
+    );
+    for (x, &count) in columns.iter().enumerate().filter(|(_, c)| **c != 0) {
+        let h = count as f64 / max * HEIGHT;
+        svg.push_str(&format!(
+            r##"<rect x="{x}" y="{:.1}" width="1" height="{h:.1}" fill="#36c"/>"##,
+            HEIGHT - h
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+impl HtmlReport {
+    fn write_head(writer: &mut dyn Write, title: &str) -> io::Result<()> {
+        writeln!(writer, "<!DOCTYPE html>")?;
+        writeln!(writer, r#"<html><head><meta charset="utf-8">"#)?;
+        writeln!(writer, "<title>{}</title>", html_escape(title))?;
+        writeln!(writer, "<style>{STYLE}</style></head><body>")
+    }
+
+    fn write_table_head(writer: &mut dyn Write) -> io::Result<()> {
+        write!(
+            writer,
+            "<table><tr><th>DR</th><th>Peak</th><th>RMS</th><th>Duration</th><th>Track</th>"
+        )?;
+        #[cfg(feature = "plot")]
+        write!(writer, "<th>RMS histogram</th>")?;
+        writeln!(writer, "</tr>")
+    }
+
+    fn write_row(writer: &mut dyn Write, name: &str, results: &DRResults) -> io::Result<()> {
+        let channels = 0..results.channels();
+        let peak = channels
+            .clone()
+            .filter_map(|ch| results.first_peak(ch).ok())
+            .fold(0.0, f64::max);
+        let rms = channels
+            .filter_map(|ch| results.rms(ch).ok())
+            .fold(0.0, f64::max);
+
+        write!(
+            writer,
+            r#"<tr><td>{}</td><td>{} dB</td><td>{} dB</td><td>{}</td><td class="name">{}</td>"#,
+            results.dr_score(),
+            db(peak),
+            db(rms),
+            format_duration(results.duration().as_secs()),
+            html_escape(name),
+        )?;
+        #[cfg(feature = "plot")]
+        write!(writer, "<td>{}</td>", rms_svg(results))?;
+        writeln!(writer, "</tr>")
+    }
+}
+
+impl ReportWriter for HtmlReport {
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        let title = format!("Official DR value: {}", results.dr_score());
+        Self::write_head(writer, &title)?;
+        writeln!(writer, "<h1>{title}</h1>")?;
+        Self::write_table_head(writer)?;
+        Self::write_row(writer, "", results)?;
+        writeln!(writer, "</table>")?;
+        writeln!(writer, "</body></html>")
+    }
+
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()> {
+        let title = format!("Official DR value: {}", album.dr_score());
+        Self::write_head(writer, &title)?;
+        writeln!(writer, "<h1>{title}</h1>")?;
+        writeln!(writer, "<p>Number of tracks: {}</p>", album.tracks().len())?;
+        writeln!(
+            writer,
+            "<p>Median: {}, Minimum: {}, Maximum: {}</p>",
+            album.dr_score_with(Aggregation::Median),
+            album.dr_score_with(Aggregation::Minimum),
+            album.dr_score_with(Aggregation::Maximum),
+        )?;
+
+        let discs = album.discs();
+        for &disc in &discs {
+            if discs.len() > 1 {
+                writeln!(
+                    writer,
+                    "<h2>Disc {disc} ({})</h2>",
+                    album.disc_dr_score(disc)
+                )?;
+            }
+            Self::write_table_head(writer)?;
+            for track in album.disc_tracks(disc) {
+                Self::write_row(writer, &track.name, &track.results)?;
+            }
+            writeln!(writer, "</table>")?;
+        }
+
+        writeln!(writer, "</body></html>")
+    }
+}
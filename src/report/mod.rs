@@ -0,0 +1,700 @@
+//! Rendering of results into reports
+//!
+//! Every report format implements [`ReportWriter`], which renders
+//! [`DRResults`] and [`DRAlbum`] into any [`io::Write`].
+
+use std::io::{self, Write};
+
+use self::template::format_duration;
+use crate::units::amplitude_to_dbfs;
+use crate::{Aggregation, DRAlbum, DRResults, DrNumerator};
+
+mod foobar;
+mod html;
+mod template;
+mod xml;
+
+pub use self::foobar::*;
+pub use self::html::*;
+pub use self::template::*;
+pub use self::xml::*;
+
+/// Renders results into some report format
+pub trait ReportWriter {
+    /// Write report of single results
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()>;
+
+    /// Write report of whole album
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()>;
+}
+
+/// Plain text report
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextReport;
+
+/// JSON report
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonReport;
+
+/// CSV report with one row per channel
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvReport;
+
+/// Format dB value with two decimals
+fn db(value: f64) -> String {
+    format!("{:.2}", amplitude_to_dbfs(value))
+}
+
+/// Uppercase first letter of ASCII word
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// Format value as JSON number (`null` for non-finite values)
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{value}")
+    } else {
+        "null".to_owned()
+    }
+}
+
+/// Format value as JSON string
+fn json_string(value: &str) -> String {
+    let mut s = String::with_capacity(value.len() + 2);
+    s.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+/// Format value as CSV field
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+impl TextReport {
+    fn write_channels(writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        for ch in 0..results.channels() {
+            writeln!(
+                writer,
+                "Channel {}: {} ({:.2}), Peak {} dB, RMS {} dB",
+                ch + 1,
+                results.channel_dr_score(ch).unwrap_or_default(),
+                results.exact_channel_dr(ch).unwrap_or_default(),
+                db(results.first_peak(ch).unwrap_or_default()),
+                db(results.rms(ch).unwrap_or_default()),
+            )?;
+        }
+        for ch in 0..results.channels() {
+            if let Ok(Some(block)) = results.worst_block(ch) {
+                writeln!(
+                    writer,
+                    "Channel {} worst block: {}, Peak {} dB, RMS {} dB",
+                    ch + 1,
+                    format_duration(block.time(results.rate()).as_secs()),
+                    db(block.peak),
+                    db(block.rms),
+                )?;
+            }
+        }
+        for (a, b) in results.dual_mono_pairs() {
+            writeln!(writer, "Channels {} and {} are identical", a + 1, b + 1)?;
+        }
+        for (a, b) in results.inverted_pairs() {
+            writeln!(
+                writer,
+                "Channels {} and {} have inverted polarity",
+                a + 1,
+                b + 1
+            )?;
+        }
+        if results.channels() > 1 {
+            let spread = results.channel_spread();
+            write!(
+                writer,
+                "Channel DR spread: {:.2} (std dev {:.2})",
+                spread.range, spread.std_dev
+            )?;
+            if spread.is_inconsistent() {
+                write!(writer, ", channels are inconsistent")?;
+            }
+            writeln!(writer)?;
+        }
+        writeln!(writer, "Confidence: {}", results.confidence().name())?;
+
+        Ok(())
+    }
+}
+
+impl ReportWriter for TextReport {
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        writeln!(writer, "{} ({:.2})", results.dr_score(), results.exact_dr())?;
+        Self::write_channels(writer, results)
+    }
+
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()> {
+        let discs = album.discs();
+        let gains = album.track_gains();
+        for &disc in &discs {
+            if discs.len() > 1 {
+                writeln!(writer, "Disc {disc}")?;
+            }
+            for (track, gain) in album.tracks().iter().zip(&gains) {
+                if track.disc != disc {
+                    continue;
+                }
+                write!(writer, "{:<6}{}", track.results.dr_score(), track.name)?;
+                if album.gain_alignment() {
+                    write!(writer, " (gain {gain:.2} dB)")?;
+                }
+                writeln!(writer)?;
+                Self::write_channels(writer, &track.results)?;
+            }
+            if discs.len() > 1 {
+                writeln!(writer, "Disc DR value: {}", album.disc_dr_score(disc))?;
+            }
+        }
+        writeln!(writer, "Number of tracks: {}", album.tracks().len())?;
+        for aggregation in [
+            Aggregation::Median,
+            Aggregation::Minimum,
+            Aggregation::Maximum,
+        ] {
+            writeln!(
+                writer,
+                "{} DR value: {} ({:.2})",
+                capitalize(aggregation.name()),
+                album.dr_score_with(aggregation),
+                album.exact_dr_with(aggregation),
+            )?;
+        }
+        writeln!(writer, "Official DR value: {}", album.dr_score())
+    }
+}
+
+impl JsonReport {
+    /// Version of report layout (`schema_version` field)
+    ///
+    /// Within one version fields are only added, never removed, renamed or
+    /// changed in meaning, so consumers must ignore unknown fields.
+    /// Any other change bumps the version.
+    pub const SCHEMA_VERSION: u32 = 3;
+
+    /// [JSON Schema](https://json-schema.org) of reports
+    pub const SCHEMA: &'static str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "title": "drmeter report",
+  "type": "object",
+  "required": ["schema_version", "generator", "algorithm"],
+  "properties": {
+    "schema_version": { "const": 2 },
+    "generator": {
+      "type": "object",
+      "required": ["name", "version"],
+      "properties": { "name": { "type": "string" }, "version": { "type": "string" } }
+    },
+    "algorithm": {
+      "type": "object",
+      "required": ["name", "loud_fraction", "dr_numerator"],
+      "properties": {
+        "name": { "type": "string" },
+        "loud_fraction": { "$ref": "#/$defs/number" },
+        "dr_numerator": { "enum": ["sample_peak", "true_peak", null] }
+      }
+    },
+    "results": { "$ref": "#/$defs/results" },
+    "album": { "$ref": "#/$defs/album" }
+  },
+  "oneOf": [{ "required": ["results"] }, { "required": ["album"] }],
+  "$defs": {
+    "number": { "type": ["number", "null"] },
+    "results": {
+      "type": "object",
+      "required": ["dr", "exact_dr", "rate", "window", "blocks", "bins", "metadata", "channels"],
+      "properties": {
+        "dr": { "type": "integer" },
+        "exact_dr": { "$ref": "#/$defs/number" },
+        "rate": { "type": "integer" },
+        "window": { "type": "integer" },
+        "blocks": { "type": "integer" },
+        "bins": { "type": "integer" },
+        "metadata": {
+          "type": "object",
+          "properties": {
+            "artist": { "type": "string" },
+            "title": { "type": "string" },
+            "album": { "type": "string" },
+            "album_artist": { "type": "string" },
+            "disc": { "type": "integer" },
+            "codec": { "type": "string" },
+            "bit_depth": { "type": "integer" },
+            "sample_rate": { "type": "integer" },
+            "duration": { "$ref": "#/$defs/number" }
+          }
+        },
+        "channels": {
+          "type": "array",
+          "items": {
+            "type": "object",
+            "required": ["dr", "exact_dr", "peak", "second_peak", "rms"],
+            "properties": {
+              "dr": { "type": "integer" },
+              "exact_dr": { "$ref": "#/$defs/number" },
+              "peak": { "$ref": "#/$defs/number" },
+              "second_peak": { "$ref": "#/$defs/number" },
+              "rms": { "$ref": "#/$defs/number" },
+              "worst_block": {
+                "type": ["object", "null"],
+                "properties": {
+                  "start": { "$ref": "#/$defs/number" },
+                  "peak": { "$ref": "#/$defs/number" },
+                  "rms": { "$ref": "#/$defs/number" }
+                }
+              }
+            }
+          }
+        },
+        "dual_mono": {
+          "type": "array",
+          "items": { "type": "array", "items": { "type": "integer" } }
+        },
+        "inverted": {
+          "type": "array",
+          "items": { "type": "array", "items": { "type": "integer" } }
+        },
+        "channel_spread": {
+          "type": "object",
+          "properties": {
+            "range": { "$ref": "#/$defs/number" },
+            "std_dev": { "$ref": "#/$defs/number" },
+            "inconsistent": { "type": "boolean" }
+          }
+        },
+        "confidence": { "enum": ["low", "medium", "high"] }
+      }
+    },
+    "album": {
+      "type": "object",
+      "required": ["dr", "exact_dr", "discs", "tracks"],
+      "properties": {
+        "dr": { "type": "integer" },
+        "exact_dr": { "$ref": "#/$defs/number" },
+        "aggregates": {
+          "type": "object",
+          "properties": {
+            "mean": { "$ref": "#/$defs/number" },
+            "median": { "$ref": "#/$defs/number" },
+            "minimum": { "$ref": "#/$defs/number" },
+            "maximum": { "$ref": "#/$defs/number" }
+          }
+        },
+        "discs": {
+          "type": "array",
+          "items": {
+            "type": "object",
+            "required": ["disc", "dr", "exact_dr"],
+            "properties": {
+              "disc": { "type": "integer" },
+              "dr": { "type": "integer" },
+              "exact_dr": { "$ref": "#/$defs/number" }
+            }
+          }
+        },
+        "tracks": {
+          "type": "array",
+          "items": {
+            "type": "object",
+            "required": ["name", "disc", "results"],
+            "properties": {
+              "name": { "type": "string" },
+              "disc": { "type": "integer" },
+              "gain": { "$ref": "#/$defs/number" },
+              "results": { "$ref": "#/$defs/results" }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"##;
+
+    /// Fields common to all reports (without braces)
+    ///
+    /// Loud fraction and numerator are `null` for albums of tracks
+    /// measured with different ones.
+    fn header(loud_fraction: Option<f64>, numerator: Option<DrNumerator>) -> String {
+        format!(
+            "\"schema_version\":{},\"generator\":{{\"name\":{},\"version\":{}}},\"algorithm\":{{\"name\":\"tt-dr\",\"loud_fraction\":{},\"dr_numerator\":{}}}",
+            Self::SCHEMA_VERSION,
+            json_string(env!("CARGO_PKG_NAME")),
+            json_string(env!("CARGO_PKG_VERSION")),
+            loud_fraction.map_or("null".to_owned(), json_number),
+            numerator.map_or("null".to_owned(), |n| json_string(n.name())),
+        )
+    }
+
+    fn results_json(results: &DRResults) -> String {
+        let channels: Vec<String> = (0..results.channels())
+            .map(|ch| {
+                let worst_block = match results.worst_block(ch).unwrap_or_default() {
+                    Some(block) => format!(
+                        "{{\"start\":{},\"peak\":{},\"rms\":{}}}",
+                        json_number(block.time(results.rate()).as_secs_f64()),
+                        json_number(block.peak),
+                        json_number(block.rms),
+                    ),
+                    None => "null".to_owned(),
+                };
+                format!(
+                    "{{\"dr\":{},\"exact_dr\":{},\"peak\":{},\"second_peak\":{},\"rms\":{},\"worst_block\":{}}}",
+                    results.channel_dr_score(ch).unwrap_or_default().get(),
+                    json_number(results.exact_channel_dr(ch).unwrap_or_default()),
+                    json_number(results.first_peak(ch).unwrap_or_default()),
+                    json_number(results.second_peak(ch).unwrap_or_default()),
+                    json_number(results.rms(ch).unwrap_or_default()),
+                    worst_block,
+                )
+            })
+            .collect();
+
+        let metadata = results.metadata();
+        let mut metadata_fields = Vec::new();
+        for (key, value) in [
+            ("artist", &metadata.artist),
+            ("title", &metadata.title),
+            ("album", &metadata.album),
+            ("album_artist", &metadata.album_artist),
+            ("codec", &metadata.codec),
+        ] {
+            if let Some(value) = value {
+                metadata_fields.push(format!("\"{key}\":{}", json_string(value)));
+            }
+        }
+        for (key, value) in [
+            ("disc", metadata.disc),
+            ("bit_depth", metadata.bit_depth),
+            ("sample_rate", metadata.sample_rate),
+        ] {
+            if let Some(value) = value {
+                metadata_fields.push(format!("\"{key}\":{value}"));
+            }
+        }
+        if let Some(duration) = metadata.duration {
+            metadata_fields.push(format!(
+                "\"duration\":{}",
+                json_number(duration.as_secs_f64())
+            ));
+        }
+
+        let spread = results.channel_spread();
+        let pairs = |pairs: &[(u32, u32)]| -> Vec<String> {
+            pairs.iter().map(|(a, b)| format!("[{a},{b}]")).collect()
+        };
+        let dual_mono = pairs(results.dual_mono_pairs());
+        let inverted = pairs(results.inverted_pairs());
+
+        format!(
+            "{{\"dr\":{},\"exact_dr\":{},\"rate\":{},\"window\":{},\"blocks\":{},\"bins\":{},\"metadata\":{{{}}},\"channels\":[{}],\"dual_mono\":[{}],\"inverted\":[{}],\"channel_spread\":{{\"range\":{},\"std_dev\":{},\"inconsistent\":{}}},\"confidence\":\"{}\"}}",
+            results.dr_score().get(),
+            json_number(results.exact_dr()),
+            results.rate(),
+            results.window(),
+            results.blocks(),
+            results.bins(),
+            metadata_fields.join(","),
+            channels.join(","),
+            dual_mono.join(","),
+            inverted.join(","),
+            json_number(spread.range),
+            json_number(spread.std_dev),
+            spread.is_inconsistent(),
+            results.confidence().name(),
+        )
+    }
+}
+
+impl ReportWriter for JsonReport {
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{{{},\"results\":{}}}",
+            Self::header(Some(results.loud_fraction()), Some(results.dr_numerator())),
+            Self::results_json(results)
+        )
+    }
+
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()> {
+        // option shared by all tracks
+        fn common<T: PartialEq>(album: &DRAlbum, option: impl Fn(&DRResults) -> T) -> Option<T> {
+            let mut values = album.tracks().iter().map(|t| option(&t.results));
+            let first = values.next()?;
+            values.all(|v| v == first).then_some(first)
+        }
+        let loud_fraction = common(album, DRResults::loud_fraction);
+        let numerator = common(album, DRResults::dr_numerator);
+        let tracks: Vec<String> = album
+            .tracks()
+            .iter()
+            .zip(album.track_gains())
+            .map(|(track, gain)| {
+                let gain = if album.gain_alignment() {
+                    format!(",\"gain\":{}", json_number(gain))
+                } else {
+                    String::new()
+                };
+                format!(
+                    "{{\"name\":{},\"disc\":{}{},\"results\":{}}}",
+                    json_string(&track.name),
+                    track.disc,
+                    gain,
+                    Self::results_json(&track.results)
+                )
+            })
+            .collect();
+        let discs: Vec<String> = album
+            .discs()
+            .into_iter()
+            .map(|disc| {
+                format!(
+                    "{{\"disc\":{},\"dr\":{},\"exact_dr\":{}}}",
+                    disc,
+                    album.disc_dr_score(disc).get(),
+                    json_number(album.disc_exact_dr(disc))
+                )
+            })
+            .collect();
+
+        let aggregates: Vec<String> = Aggregation::ALL
+            .into_iter()
+            .map(|aggregation| {
+                format!(
+                    "\"{}\":{}",
+                    aggregation.name(),
+                    json_number(album.exact_dr_with(aggregation))
+                )
+            })
+            .collect();
+
+        writeln!(
+            writer,
+            "{{{},\"album\":{{\"dr\":{},\"exact_dr\":{},\"aggregates\":{{{}}},\"discs\":[{}],\"tracks\":[{}]}}}}",
+            Self::header(loud_fraction, numerator),
+            album.dr_score().get(),
+            json_number(album.exact_dr()),
+            aggregates.join(","),
+            discs.join(","),
+            tracks.join(","),
+        )
+    }
+}
+
+impl CsvReport {
+    const HEADER: &'static str = "track,disc,channel,dr,exact_dr,peak_db,rms_db";
+
+    fn write_rows(
+        writer: &mut dyn Write,
+        name: &str,
+        disc: Option<u32>,
+        results: &DRResults,
+    ) -> io::Result<()> {
+        let name = csv_field(name);
+        let disc = disc.map(|disc| disc.to_string()).unwrap_or_default();
+        for ch in 0..results.channels() {
+            writeln!(
+                writer,
+                "{},{},{},{},{:.2},{},{}",
+                name,
+                disc,
+                ch + 1,
+                results.channel_dr_score(ch).unwrap_or_default().get(),
+                results.exact_channel_dr(ch).unwrap_or_default(),
+                db(results.first_peak(ch).unwrap_or_default()),
+                db(results.rms(ch).unwrap_or_default()),
+            )?;
+        }
+
+        writeln!(
+            writer,
+            "{},{},,{},{:.2},,",
+            name,
+            disc,
+            results.dr_score().get(),
+            results.exact_dr()
+        )
+    }
+}
+
+impl ReportWriter for CsvReport {
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        writeln!(writer, "{}", Self::HEADER)?;
+        Self::write_rows(writer, "", None, results)
+    }
+
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()> {
+        writeln!(writer, "{}", Self::HEADER)?;
+        for track in album.tracks() {
+            Self::write_rows(writer, &track.name, Some(track.disc), &track.results)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DRMeter;
+
+    fn results() -> DRResults {
+        results_at(0.5)
+    }
+
+    /// Results of the same signal at twice the level
+    fn loud_results() -> DRResults {
+        results_at(1.0)
+    }
+
+    fn results_at(level: f32) -> DRResults {
+        let data: Vec<f32> = (0..48_000 * 2 * 6)
+            .map(|i| level * f32::sin(i as f32 * 0.01) * (i % 96_000) as f32 / 96_000.0)
+            .collect();
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        dr.results().unwrap()
+    }
+
+    fn render(writer: &dyn ReportWriter, album: &DRAlbum) -> String {
+        let mut out = Vec::new();
+        writer.write_album(&mut out, album).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn album_reports() {
+        let mut album = DRAlbum::new();
+        album.add_track("01 \"Intro\", live", results());
+        album.add_track("02", results());
+        let dr = album.dr_score().get();
+
+        let text = render(&TextReport, &album);
+        assert!(text.ends_with(&format!("Official DR value: DR{dr}\n")));
+        assert_eq!(text.matches("Channel DR spread: 0.00").count(), 2);
+        assert_eq!(text.matches("Confidence: low").count(), 2);
+
+        let json = render(&JsonReport, &album);
+        assert!(json.starts_with("{\"schema_version\":3,"));
+        assert!(json.contains("\"loud_fraction\":0.2,"));
+        assert!(json.contains("\"dr_numerator\":\"sample_peak\"}"));
+        assert!(json.contains(&format!("\"album\":{{\"dr\":{dr},")));
+        assert!(json.contains("\"name\":\"01 \\\"Intro\\\", live\""));
+        assert!(
+            json.contains("\"channel_spread\":{\"range\":0,\"std_dev\":0,\"inconsistent\":false}")
+        );
+        assert!(json.contains("\"confidence\":\"low\""));
+
+        // tracks measured with different fractions
+        let mut mixed = album.clone();
+        let mut loud = results();
+        loud.loud_fraction = 0.5;
+        mixed.add_track("03", loud);
+        let json = render(&JsonReport, &mixed);
+        assert!(json.contains("\"loud_fraction\":null,"));
+
+        let csv = render(&CsvReport, &album);
+        assert_eq!(csv.lines().count(), 1 + 2 * 3);
+        assert!(csv.contains("\"01 \"\"Intro\"\", live\",1,"));
+
+        let xml = render(&XmlReport, &album);
+        assert!(xml.contains(&format!("<album dr=\"{dr}\"")));
+        assert!(xml.contains("<track name=\"01 &quot;Intro&quot;, live\" disc=\"1\">"));
+        assert_eq!(xml.matches("<channel ").count(), 4);
+        assert!(xml.ends_with("</drmeter>\n"));
+
+        let html = render(&HtmlReport, &album);
+        assert!(html.contains(&format!("<h1>Official DR value: DR{dr}</h1>")));
+        assert!(html.contains("01 &quot;Intro&quot;, live"));
+    }
+
+    #[test]
+    fn multi_disc() {
+        let mut album = DRAlbum::new();
+        album.add_disc_track(1, "1-01", results());
+        album.add_disc_track(2, "2-01", results());
+        album.add_disc_track(2, "2-02", results());
+        assert_eq!(album.discs(), vec![1, 2]);
+        assert_eq!(album.disc_tracks(2).count(), 2);
+
+        let text = render(&TextReport, &album);
+        assert!(text.starts_with("Disc 1\n"));
+        assert_eq!(text.matches("Disc DR value: DR").count(), 2);
+
+        let json = render(&JsonReport, &album);
+        assert!(json.contains("\"discs\":[{\"disc\":1,"));
+    }
+
+    #[test]
+    fn aggregates() {
+        let mut album = DRAlbum::new();
+        album.add_track("01", results());
+        album.add_track("02", loud_results());
+        album.add_track("03", results());
+        let median = album.exact_dr_with(Aggregation::Median);
+        assert_eq!(median, album.tracks()[0].results.exact_dr());
+
+        let text = render(&TextReport, &album);
+        assert!(text.contains(&format!(
+            "Median DR value: {} ({median:.2})\n",
+            album.dr_score_with(Aggregation::Median)
+        )));
+        assert!(text.contains("Minimum DR value: DR"));
+        let json = render(&JsonReport, &album);
+        assert!(json.contains(&format!(
+            "\"aggregates\":{{\"mean\":{},\"median\":{median},",
+            album.exact_dr()
+        )));
+        let xml = render(&XmlReport, &album);
+        assert!(xml.contains(&format!("median_exact_dr=\"{median}\"")));
+    }
+
+    #[test]
+    fn gain_alignment() {
+        let quiet = results();
+        let mut album = DRAlbum::new();
+        album.add_track("loud", loud_results());
+        album.add_track("quiet", quiet);
+        let gains = album.track_gains();
+        assert!((gains[0] + 6.02).abs() < 0.01, "{gains:?}");
+        assert_eq!(gains[1], 0.0);
+
+        assert!(!render(&TextReport, &album).contains("gain"));
+        album.set_gain_alignment(true);
+        let text = render(&TextReport, &album);
+        assert!(text.contains("loud (gain -6.02 dB)\n"));
+        assert!(text.contains("quiet (gain 0.00 dB)\n"));
+        let json = render(&JsonReport, &album);
+        assert!(json.contains("\"name\":\"quiet\",\"disc\":1,\"gain\":0,"));
+        let xml = render(&XmlReport, &album);
+        assert!(xml.contains("<track name=\"quiet\" disc=\"1\" gain=\"0\">"));
+    }
+}
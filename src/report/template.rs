@@ -0,0 +1,290 @@
+//! Reports rendered from user format strings
+
+use std::io::{self, Write};
+
+use super::ReportWriter;
+use crate::math;
+use crate::units::amplitude_to_dbfs;
+use crate::utils::sqr;
+use crate::{DRAlbum, DRResults, Error};
+
+/// Placeholder in a [`Template`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    /// Track name
+    Name,
+    /// DR score
+    Dr,
+    /// Exact DR
+    ExactDr,
+    /// Highest peak of all channels (dB)
+    Peak,
+    /// Highest second peak of all channels (dB)
+    SecondPeak,
+    /// Top 20% RMS of all channels (dB)
+    Rms,
+    /// Duration as `m:ss`
+    Duration,
+    /// Sample rate
+    Rate,
+    /// Number of channels
+    Channels,
+    /// Number of blocks
+    Blocks,
+    /// Artist from metadata
+    Artist,
+    /// Title from metadata
+    Title,
+    /// Album from metadata
+    Album,
+    /// Codec from metadata
+    Codec,
+    /// Bit depth from metadata
+    BitDepth,
+    /// Album DR score
+    AlbumDr,
+    /// Exact album DR
+    AlbumExactDr,
+    /// Number of tracks in album
+    Tracks,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "name" => Field::Name,
+            "dr" => Field::Dr,
+            "exact_dr" => Field::ExactDr,
+            "peak" => Field::Peak,
+            "second_peak" => Field::SecondPeak,
+            "rms" => Field::Rms,
+            "duration" => Field::Duration,
+            "rate" => Field::Rate,
+            "channels" => Field::Channels,
+            "blocks" => Field::Blocks,
+            "artist" => Field::Artist,
+            "title" => Field::Title,
+            "album" => Field::Album,
+            "codec" => Field::Codec,
+            "bit_depth" => Field::BitDepth,
+            "album_dr" => Field::AlbumDr,
+            "album_exact_dr" => Field::AlbumExactDr,
+            "tracks" => Field::Tracks,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// Format string with placeholders
+///
+/// Supported placeholders are:
+/// - `{name}` track name (empty for single results)
+/// - `{dr}`, `{exact_dr}` DR score and exact DR
+/// - `{peak}`, `{second_peak}` highest and second highest block peak of all channels in dB
+/// - `{rms}` top 20% RMS of all channels in dB
+/// - `{duration}` duration as `m:ss`
+/// - `{rate}`, `{channels}`, `{blocks}` sample rate, number of channels and blocks
+/// - `{artist}`, `{title}`, `{album}`, `{codec}`, `{bit_depth}` attached track metadata (empty if missing)
+/// - `{album_dr}`, `{album_exact_dr}`, `{tracks}` album DR score, exact album DR and number of tracks
+///
+/// Literal braces are written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Parse format string
+    pub fn new(format: &str) -> Result<Self, Error> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('{') if name.is_empty() => {
+                                literal.push('{');
+                                break;
+                            }
+                            Some('}') => {
+                                let field = Field::parse(&name).ok_or(Error::InvalidTemplate)?;
+                                if !literal.is_empty() {
+                                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                                }
+                                segments.push(Segment::Field(field));
+                                break;
+                            }
+                            Some(c) => name.push(c),
+                            None => return Err(Error::InvalidTemplate),
+                        }
+                    }
+                }
+                '}' => match chars.next() {
+                    Some('}') => literal.push('}'),
+                    _ => return Err(Error::InvalidTemplate),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Render template
+    ///
+    /// Album placeholders are empty if no album is given.
+    pub fn render(
+        &self,
+        name: &str,
+        results: Option<&DRResults>,
+        album: Option<&DRAlbum>,
+    ) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => out.push_str(literal),
+                Segment::Field(field) => out.push_str(&Self::value(*field, name, results, album)),
+            }
+        }
+        out
+    }
+
+    fn value(
+        field: Field,
+        name: &str,
+        results: Option<&DRResults>,
+        album: Option<&DRAlbum>,
+    ) -> String {
+        let db = |v: f64| format!("{:.2}", amplitude_to_dbfs(v));
+        let channels = |r: &DRResults| 0..r.channels();
+        match (field, results, album) {
+            (Field::Name, _, _) => name.to_owned(),
+            (Field::Dr, Some(r), _) => r.dr_score().get().to_string(),
+            (Field::ExactDr, Some(r), _) => format!("{:.2}", r.exact_dr()),
+            (Field::Peak, Some(r), _) => db(channels(r)
+                .filter_map(|ch| r.first_peak(ch).ok())
+                .fold(0.0, f64::max)),
+            (Field::SecondPeak, Some(r), _) => db(channels(r)
+                .filter_map(|ch| r.second_peak(ch).ok())
+                .fold(0.0, f64::max)),
+            (Field::Rms, Some(r), _) => db(math::sqrt(
+                channels(r)
+                    .filter_map(|ch| r.rms(ch).ok())
+                    .map(sqr)
+                    .sum::<f64>()
+                    / r.channels() as f64,
+            )),
+            (Field::Duration, Some(r), _) => format_duration(r.duration().as_secs()),
+            (Field::Rate, Some(r), _) => r.rate().to_string(),
+            (Field::Channels, Some(r), _) => r.channels().to_string(),
+            (Field::Blocks, Some(r), _) => r.blocks().to_string(),
+            (Field::Artist, Some(r), _) => r.metadata().artist.clone().unwrap_or_default(),
+            (Field::Title, Some(r), _) => r.metadata().title.clone().unwrap_or_default(),
+            (Field::Album, Some(r), _) => r.metadata().album.clone().unwrap_or_default(),
+            (Field::Codec, Some(r), _) => r.metadata().codec.clone().unwrap_or_default(),
+            (Field::BitDepth, Some(r), _) => r
+                .metadata()
+                .bit_depth
+                .map(|b| b.to_string())
+                .unwrap_or_default(),
+            (Field::AlbumDr, _, Some(a)) => a.dr_score().get().to_string(),
+            (Field::AlbumExactDr, _, Some(a)) => format!("{:.2}", a.exact_dr()),
+            (Field::Tracks, _, Some(a)) => a.tracks().len().to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Format seconds as `m:ss` (or `h:mm:ss`)
+pub(crate) fn format_duration(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, (secs / 60) % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+/// Report rendered from templates
+///
+/// Every rendered template is written as its own line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateReport {
+    /// Rendered for results of every track
+    pub track: Template,
+    /// Rendered once after all tracks of album
+    pub album: Option<Template>,
+}
+
+impl TemplateReport {
+    /// Create report from track template and optional album footer template
+    pub fn new(track: &str, album: Option<&str>) -> Result<Self, Error> {
+        Ok(Self {
+            track: Template::new(track)?,
+            album: album.map(Template::new).transpose()?,
+        })
+    }
+}
+
+impl ReportWriter for TemplateReport {
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        writeln!(writer, "{}", self.track.render("", Some(results), None))
+    }
+
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()> {
+        for track in album.tracks() {
+            writeln!(
+                writer,
+                "{}",
+                self.track
+                    .render(&track.name, Some(&track.results), Some(album))
+            )?;
+        }
+        if let Some(footer) = &self.album {
+            writeln!(writer, "{}", footer.render("", None, Some(album)))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let template = Template::new("{{DR{dr}}} {name}").unwrap();
+        assert_eq!(
+            template.segments,
+            vec![
+                Segment::Literal("{DR".to_owned()),
+                Segment::Field(Field::Dr),
+                Segment::Literal("} ".to_owned()),
+                Segment::Field(Field::Name),
+            ]
+        );
+        assert_eq!(Template::new("{unknown}"), Err(Error::InvalidTemplate));
+        assert_eq!(Template::new("{dr"), Err(Error::InvalidTemplate));
+        assert_eq!(Template::new("dr}"), Err(Error::InvalidTemplate));
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(format_duration(59), "0:59");
+        assert_eq!(format_duration(225), "3:45");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+}
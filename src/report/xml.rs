@@ -0,0 +1,254 @@
+//! XML report for QC systems
+
+use std::io::{self, Write};
+
+use super::ReportWriter;
+use crate::units::amplitude_to_dbfs;
+use crate::{Aggregation, DRAlbum, DRResults};
+
+/// XML report
+///
+/// Documents are valid against [`XmlReport::SCHEMA`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XmlReport;
+
+/// Format value as `xs:double`
+fn xml_double(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_owned()
+    } else if value.is_infinite() {
+        if value > 0.0 { "INF" } else { "-INF" }.to_owned()
+    } else {
+        format!("{value}")
+    }
+}
+
+/// Format linear value in dB with two decimals as `xs:double`
+fn xml_db(value: f64) -> String {
+    let db = amplitude_to_dbfs(value);
+    if db.is_finite() {
+        format!("{db:.2}")
+    } else {
+        xml_double(db)
+    }
+}
+
+/// Escape value for attribute
+fn xml_escape(value: &str) -> String {
+    let mut s = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '"' => s.push_str("&quot;"),
+            '\'' => s.push_str("&apos;"),
+            '\n' => s.push_str("&#10;"),
+            '\r' => s.push_str("&#13;"),
+            '\t' => s.push_str("&#9;"),
+            // not allowed in XML 1.0
+            c if (c as u32) < 0x20 => {}
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+impl XmlReport {
+    /// Version of document layout (`version` attribute of root)
+    pub const VERSION: u32 = 1;
+
+    /// XML Schema of reports
+    pub const SCHEMA: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" elementFormDefault="qualified">
+  <xs:element name="drmeter">
+    <xs:complexType>
+      <xs:choice>
+        <xs:element name="results" type="results"/>
+        <xs:element name="album" type="album"/>
+      </xs:choice>
+      <xs:attribute name="version" type="xs:positiveInteger" use="required"/>
+    </xs:complexType>
+  </xs:element>
+  <xs:complexType name="results">
+    <xs:sequence>
+      <xs:element name="metadata" type="metadata" minOccurs="0"/>
+      <xs:element name="channel" type="channel" maxOccurs="unbounded"/>
+    </xs:sequence>
+    <xs:attribute name="dr" type="xs:unsignedByte" use="required"/>
+    <xs:attribute name="exact_dr" type="xs:double" use="required"/>
+    <xs:attribute name="rate" type="xs:unsignedInt" use="required"/>
+    <xs:attribute name="window" type="xs:unsignedInt" use="required"/>
+    <xs:attribute name="blocks" type="xs:unsignedLong" use="required"/>
+    <xs:attribute name="duration" type="xs:double" use="required"/>
+  </xs:complexType>
+  <xs:complexType name="metadata">
+    <xs:attribute name="artist" type="xs:string"/>
+    <xs:attribute name="title" type="xs:string"/>
+    <xs:attribute name="album" type="xs:string"/>
+    <xs:attribute name="codec" type="xs:string"/>
+    <xs:attribute name="bit_depth" type="xs:unsignedInt"/>
+    <xs:attribute name="sample_rate" type="xs:unsignedInt"/>
+    <xs:attribute name="duration" type="xs:double"/>
+  </xs:complexType>
+  <xs:complexType name="channel">
+    <xs:attribute name="index" type="xs:positiveInteger" use="required"/>
+    <xs:attribute name="dr" type="xs:unsignedByte" use="required"/>
+    <xs:attribute name="exact_dr" type="xs:double" use="required"/>
+    <xs:attribute name="peak_db" type="xs:double" use="required"/>
+    <xs:attribute name="second_peak_db" type="xs:double" use="required"/>
+    <xs:attribute name="rms_db" type="xs:double" use="required"/>
+  </xs:complexType>
+  <xs:complexType name="album">
+    <xs:sequence>
+      <xs:element name="disc" maxOccurs="unbounded" minOccurs="0">
+        <xs:complexType>
+          <xs:attribute name="number" type="xs:unsignedInt" use="required"/>
+          <xs:attribute name="dr" type="xs:unsignedByte" use="required"/>
+          <xs:attribute name="exact_dr" type="xs:double" use="required"/>
+        </xs:complexType>
+      </xs:element>
+      <xs:element name="track" maxOccurs="unbounded" minOccurs="0">
+        <xs:complexType>
+          <xs:sequence>
+            <xs:element name="results" type="results"/>
+          </xs:sequence>
+          <xs:attribute name="name" type="xs:string" use="required"/>
+          <xs:attribute name="disc" type="xs:unsignedInt" use="required"/>
+          <xs:attribute name="gain" type="xs:double"/>
+        </xs:complexType>
+      </xs:element>
+    </xs:sequence>
+    <xs:attribute name="dr" type="xs:unsignedByte" use="required"/>
+    <xs:attribute name="exact_dr" type="xs:double" use="required"/>
+    <xs:attribute name="tracks" type="xs:unsignedInt" use="required"/>
+    <xs:attribute name="median_exact_dr" type="xs:double"/>
+    <xs:attribute name="minimum_exact_dr" type="xs:double"/>
+    <xs:attribute name="maximum_exact_dr" type="xs:double"/>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+    fn write_header(writer: &mut dyn Write) -> io::Result<()> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(writer, r#"<drmeter version="{}">"#, Self::VERSION)
+    }
+
+    fn write_results_element(
+        writer: &mut dyn Write,
+        indent: &str,
+        results: &DRResults,
+    ) -> io::Result<()> {
+        writeln!(
+            writer,
+            r#"{indent}<results dr="{}" exact_dr="{}" rate="{}" window="{}" blocks="{}" duration="{}">"#,
+            results.dr_score().get(),
+            xml_double(results.exact_dr()),
+            results.rate(),
+            results.window(),
+            results.blocks(),
+            xml_double(results.duration().as_secs_f64()),
+        )?;
+
+        let metadata = results.metadata();
+        if !metadata.is_empty() {
+            let mut attrs = String::new();
+            for (key, value) in [
+                ("artist", &metadata.artist),
+                ("title", &metadata.title),
+                ("album", &metadata.album),
+                ("codec", &metadata.codec),
+            ] {
+                if let Some(value) = value {
+                    attrs.push_str(&format!(r#" {key}="{}""#, xml_escape(value)));
+                }
+            }
+            for (key, value) in [
+                ("bit_depth", metadata.bit_depth),
+                ("sample_rate", metadata.sample_rate),
+            ] {
+                if let Some(value) = value {
+                    attrs.push_str(&format!(r#" {key}="{value}""#));
+                }
+            }
+            if let Some(duration) = metadata.duration {
+                attrs.push_str(&format!(
+                    r#" duration="{}""#,
+                    xml_double(duration.as_secs_f64())
+                ));
+            }
+            writeln!(writer, "{indent}  <metadata{attrs}/>")?;
+        }
+
+        for ch in 0..results.channels() {
+            writeln!(
+                writer,
+                r#"{indent}  <channel index="{}" dr="{}" exact_dr="{}" peak_db="{}" second_peak_db="{}" rms_db="{}"/>"#,
+                ch + 1,
+                results.channel_dr_score(ch).unwrap_or_default().get(),
+                xml_double(results.exact_channel_dr(ch).unwrap_or_default()),
+                xml_db(results.first_peak(ch).unwrap_or_default()),
+                xml_db(results.second_peak(ch).unwrap_or_default()),
+                xml_db(results.rms(ch).unwrap_or_default()),
+            )?;
+        }
+
+        writeln!(writer, "{indent}</results>")
+    }
+}
+
+impl ReportWriter for XmlReport {
+    fn write_results(&self, writer: &mut dyn Write, results: &DRResults) -> io::Result<()> {
+        Self::write_header(writer)?;
+        Self::write_results_element(writer, "  ", results)?;
+        writeln!(writer, "</drmeter>")
+    }
+
+    fn write_album(&self, writer: &mut dyn Write, album: &DRAlbum) -> io::Result<()> {
+        Self::write_header(writer)?;
+        write!(
+            writer,
+            r#"  <album dr="{}" exact_dr="{}" tracks="{}""#,
+            album.dr_score().get(),
+            xml_double(album.exact_dr()),
+            album.tracks().len(),
+        )?;
+        for aggregation in [
+            Aggregation::Median,
+            Aggregation::Minimum,
+            Aggregation::Maximum,
+        ] {
+            write!(
+                writer,
+                r#" {}_exact_dr="{}""#,
+                aggregation.name(),
+                xml_double(album.exact_dr_with(aggregation))
+            )?;
+        }
+        writeln!(writer, ">")?;
+        for disc in album.discs() {
+            writeln!(
+                writer,
+                r#"    <disc number="{disc}" dr="{}" exact_dr="{}"/>"#,
+                album.disc_dr_score(disc).get(),
+                xml_double(album.disc_exact_dr(disc)),
+            )?;
+        }
+        for (track, gain) in album.tracks().iter().zip(album.track_gains()) {
+            write!(
+                writer,
+                r#"    <track name="{}" disc="{}""#,
+                xml_escape(&track.name),
+                track.disc
+            )?;
+            if album.gain_alignment() {
+                write!(writer, r#" gain="{}""#, xml_double(gain))?;
+            }
+            writeln!(writer, ">")?;
+            Self::write_results_element(writer, "      ", &track.results)?;
+            writeln!(writer, "    </track>")?;
+        }
+        writeln!(writer, "  </album>")?;
+        writeln!(writer, "</drmeter>")
+    }
+}
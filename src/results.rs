@@ -0,0 +1,510 @@
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::drmeter::loud_rms_sum;
+use crate::units::decibel;
+use crate::{DRScore, DrNumerator, Error, LoudBlocks, TrackMetadata};
+
+/// Results of one channel
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct ChannelResults {
+    /// exact DR
+    pub dr: f64,
+    /// highest block peak
+    pub first_peak: f64,
+    /// second highest block peak
+    pub second_peak: f64,
+    /// top 20% RMS
+    pub rms: f64,
+    /// populated peak bins as `(bin, count)`
+    pub peak_histogram: Vec<(usize, u32)>,
+    /// populated RMS bins as `(bin, count)`
+    pub rms_histogram: Vec<(usize, u32)>,
+    /// blocks with peak above histogram range
+    pub peak_overflows: u32,
+    /// blocks with RMS above full scale
+    pub rms_overflows: u32,
+    /// block with the lowest crest factor
+    pub worst_block: Option<WorstBlock>,
+    /// top 20% RMS blocks selected from exact block values (in exact mode)
+    pub exact_loud_blocks: Option<LoudBlocks>,
+}
+
+/// Block with the lowest crest factor (the most compressed moment) of a channel
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WorstBlock {
+    /// Position of block start (in frames)
+    pub start: u64,
+    /// Block peak (linear)
+    pub peak: f64,
+    /// Block RMS (linear)
+    pub rms: f64,
+}
+
+impl WorstBlock {
+    /// Returns peak to RMS ratio of block (linear)
+    pub fn crest_factor(&self) -> f64 {
+        self.peak / self.rms
+    }
+
+    /// Returns peak to RMS ratio of block in dB
+    pub fn crest_factor_db(&self) -> f64 {
+        decibel(self.crest_factor())
+    }
+
+    /// Returns time position of block start
+    pub fn time(&self, rate: u32) -> Duration {
+        Duration::from_secs_f64(self.start as f64 / rate as f64)
+    }
+
+    /// Returns block that has lower crest factor
+    ///
+    /// Silent blocks have no crest factor and are never worst,
+    /// on tie the earlier block is kept.
+    pub(crate) fn worse(current: Option<Self>, block: Self) -> Option<Self> {
+        if block.rms <= 0.0 {
+            return current;
+        }
+        match current {
+            Some(current) if current.crest_factor() <= block.crest_factor() => Some(current),
+            _ => Some(block),
+        }
+    }
+}
+
+/// Levels of one finished block
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BlockResult {
+    /// Index of block
+    pub index: usize,
+    /// Position of block start (in frames)
+    pub start: u64,
+    /// Block peak per channel (linear)
+    pub peak: Box<[f64]>,
+    /// Block RMS per channel (linear)
+    pub rms: Box<[f64]>,
+}
+
+impl BlockResult {
+    /// Returns time position of block start
+    pub fn time(&self, rate: u32) -> Duration {
+        Duration::from_secs_f64(self.start as f64 / rate as f64)
+    }
+}
+
+/// Spread of per-channel DR values
+///
+/// Large spread means one channel is much more compressed than the others,
+/// which is usually a sign of a mastering or channel-swap fault.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChannelSpread {
+    /// Difference between highest and lowest channel DR
+    pub range: f64,
+    /// Standard deviation of channel DR
+    pub std_dev: f64,
+}
+
+impl ChannelSpread {
+    /// Range of channel DR (in dB) above which channels are inconsistent
+    pub const INCONSISTENT_RANGE: f64 = 3.0;
+
+    /// Compute spread of channel DR values
+    fn new(values: impl Iterator<Item = f64> + Clone) -> Self {
+        let (min, max, sum, count) = values.clone().fold(
+            (f64::INFINITY, f64::NEG_INFINITY, 0.0, 0),
+            |(min, max, sum, count), dr| (min.min(dr), max.max(dr), sum + dr, count + 1),
+        );
+        if count == 0 {
+            return Self {
+                range: 0.0,
+                std_dev: 0.0,
+            };
+        }
+        let mean = sum / count as f64;
+        let variance = values.map(|dr| (dr - mean).powi(2)).sum::<f64>() / count as f64;
+        Self {
+            range: max - min,
+            std_dev: variance.sqrt(),
+        }
+    }
+
+    /// Returns whether channel DR values differ dramatically
+    pub fn is_inconsistent(&self) -> bool {
+        self.range > Self::INCONSISTENT_RANGE
+    }
+}
+
+/// Qualitative confidence of DR results
+///
+/// See [`DRResults::confidence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Confidence {
+    /// DR is computed from a handful of blocks
+    Low,
+    /// DR is usable, but may shift noticeably with a slightly different program
+    Medium,
+    /// DR is computed from enough audible blocks
+    High,
+}
+
+impl Confidence {
+    /// Returns name of confidence
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+/// Results of a finalized [`DRMeter`](crate::DRMeter)
+///
+/// This holds scores together with populated histogram bins,
+/// so results can be archived and rendered again later without the audio.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DRResults {
+    pub(crate) rate: u32,
+    pub(crate) window: usize,
+    /// number of blocks that were scanned
+    pub(crate) blocks: usize,
+    /// number of frames that were added
+    pub(crate) frames: u64,
+    /// Number of bins that represent full scale
+    pub(crate) bins: usize,
+    pub(crate) channels: Vec<ChannelResults>,
+    /// pairs of identical channels
+    pub(crate) dual_mono: Vec<(u32, u32)>,
+    /// pairs of polarity inverted channels
+    pub(crate) inverted: Vec<(u32, u32)>,
+    /// peak used in DR formula
+    pub(crate) dr_numerator: DrNumerator,
+    /// fraction of loudest blocks in RMS
+    pub(crate) loud_fraction: f64,
+    /// attached track metadata
+    pub(crate) metadata: TrackMetadata,
+}
+
+impl DRResults {
+    /// Check channel number (index)
+    fn channel(&self, ch: u32) -> Result<&ChannelResults, Error> {
+        self.channels
+            .get(ch as usize)
+            .ok_or(Error::InvalidChannelIndex)
+    }
+
+    /// Returns the number of channels.
+    pub fn channels(&self) -> u32 {
+        self.channels.len() as u32
+    }
+
+    /// Returns the sample rate.
+    pub const fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    /// Returns the window.
+    pub const fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Returns the number of scanned blocks.
+    pub const fn blocks(&self) -> usize {
+        self.blocks
+    }
+
+    /// Returns the number of frames that were measured.
+    pub const fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the duration of measured audio.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames as f64 / self.rate as f64)
+    }
+
+    /// Returns the peak used in DR formula.
+    ///
+    /// Peaks and peak histograms of results are of this kind.
+    pub const fn dr_numerator(&self) -> DrNumerator {
+        self.dr_numerator
+    }
+
+    /// Returns the fraction of loudest blocks in RMS.
+    pub const fn loud_fraction(&self) -> f64 {
+        self.loud_fraction
+    }
+
+    /// Returns the number of histogram bins that represent full scale.
+    pub const fn bins(&self) -> usize {
+        self.bins
+    }
+
+    /// Returns linear value (full scale is `1.0`) of histogram bin,
+    /// use [`units::amplitude_to_dbfs`](crate::units::amplitude_to_dbfs) to get dBFS.
+    pub fn bin_value(&self, bin: usize) -> f64 {
+        bin as f64 / self.bins as f64
+    }
+
+    /// Return exact channel DR
+    pub fn exact_channel_dr(&self, channel_number: u32) -> Result<f64, Error> {
+        Ok(self.channel(channel_number)?.dr)
+    }
+
+    /// Return channel DR score
+    pub fn channel_dr_score(&self, channel_number: u32) -> Result<DRScore, Error> {
+        Ok(DRScore::from_exact(self.exact_channel_dr(channel_number)?))
+    }
+
+    /// Return exact DR
+    pub fn exact_dr(&self) -> f64 {
+        self.channels.iter().map(|ch| ch.dr).sum::<f64>() / self.channels.len() as f64
+    }
+
+    /// Return DR score
+    pub fn dr_score(&self) -> DRScore {
+        DRScore::from_exact(self.exact_dr())
+    }
+
+    /// Return spread of channel DR values
+    pub fn channel_spread(&self) -> ChannelSpread {
+        ChannelSpread::new(self.channels.iter().map(|ch| ch.dr))
+    }
+
+    /// Returns how much the DR value can be relied on
+    ///
+    /// Top 20% RMS of a short or mostly silent program is taken
+    /// from a few blocks, so its DR is not stable. Confidence is
+    /// - low with fewer than 5 audible blocks or more than half of blocks silent,
+    /// - medium with fewer than 20 audible blocks, more than 20% of blocks silent,
+    ///   clamped peaks or RMS, or a short last block with fewer than 20 blocks,
+    /// - high otherwise.
+    pub fn confidence(&self) -> Confidence {
+        let silent = self
+            .channels
+            .iter()
+            .map(|ch| match ch.rms_histogram.first() {
+                Some(&(0, count)) => count as usize,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        let audible = self.blocks.saturating_sub(silent);
+        let overflows = self
+            .channels
+            .iter()
+            .any(|ch| ch.peak_overflows > 0 || ch.rms_overflows > 0);
+        let block_frames = self.rate as u64 * self.window as u64 / 1000;
+        let partial = self.blocks as u64 * block_frames > self.frames;
+
+        if audible < 5 || silent * 2 > self.blocks {
+            Confidence::Low
+        } else if audible < 20
+            || silent * 5 > self.blocks
+            || overflows
+            || (partial && self.blocks < 20)
+        {
+            Confidence::Medium
+        } else {
+            Confidence::High
+        }
+    }
+
+    /// Get highest block peak for channel (linear)
+    pub fn first_peak(&self, channel_number: u32) -> Result<f64, Error> {
+        Ok(self.channel(channel_number)?.first_peak)
+    }
+
+    /// Get second highest block peak for channel (linear)
+    pub fn second_peak(&self, channel_number: u32) -> Result<f64, Error> {
+        Ok(self.channel(channel_number)?.second_peak)
+    }
+
+    /// Get top 20% RMS for channel (linear)
+    pub fn rms(&self, channel_number: u32) -> Result<f64, Error> {
+        Ok(self.channel(channel_number)?.rms)
+    }
+
+    /// Return blocks that contributed to top 20% RMS of channel
+    ///
+    /// Results of a meter in exact mode keep its selection of blocks,
+    /// otherwise it is computed from RMS histogram.
+    pub fn loud_blocks(&self, channel_number: u32) -> Result<LoudBlocks, Error> {
+        let channel = self.channel(channel_number)?;
+        if let Some(loud_blocks) = channel.exact_loud_blocks {
+            return Ok(loud_blocks);
+        }
+        let rms_histogram = &channel.rms_histogram;
+        Ok(loud_rms_sum(
+            rms_histogram.iter().rev().copied(),
+            self.blocks,
+            self.bins,
+            self.loud_fraction,
+        )
+        .1)
+    }
+
+    /// Populated peak histogram bins of channel as `(bin, count)` pairs
+    ///
+    /// Bin value is [`bin_value`](Self::bin_value) of bin.
+    pub fn peak_histogram(&self, channel_number: u32) -> Result<&[(usize, u32)], Error> {
+        Ok(&self.channel(channel_number)?.peak_histogram)
+    }
+
+    /// Populated RMS histogram bins of channel as `(bin, count)` pairs
+    ///
+    /// Bin value is [`bin_value`](Self::bin_value) of bin.
+    pub fn rms_histogram(&self, channel_number: u32) -> Result<&[(usize, u32)], Error> {
+        Ok(&self.channel(channel_number)?.rms_histogram)
+    }
+
+    /// Returns the number of blocks of channel with peak above
+    /// the histogram range, which were clamped.
+    pub fn peak_overflows(&self, channel_number: u32) -> Result<u32, Error> {
+        Ok(self.channel(channel_number)?.peak_overflows)
+    }
+
+    /// Returns the number of blocks of channel with RMS above full scale,
+    /// which were clamped.
+    pub fn rms_overflows(&self, channel_number: u32) -> Result<u32, Error> {
+        Ok(self.channel(channel_number)?.rms_overflows)
+    }
+
+    /// Returns the block of channel with the lowest crest factor
+    ///
+    /// Returns `None` if all blocks are silent.
+    pub fn worst_block(&self, channel_number: u32) -> Result<Option<WorstBlock>, Error> {
+        Ok(self.channel(channel_number)?.worst_block)
+    }
+
+    /// Returns pairs of identical channels `(a, b)` with `a < b`
+    ///
+    /// This is empty if dual-mono detection was not enabled
+    /// (see [`DRMeter::set_dual_mono_detection`](crate::DRMeter::set_dual_mono_detection)).
+    pub fn dual_mono_pairs(&self) -> &[(u32, u32)] {
+        &self.dual_mono
+    }
+
+    /// Returns pairs of strongly negatively correlated channels `(a, b)` with `a < b`
+    ///
+    /// This is empty if polarity detection was not enabled
+    /// (see [`DRMeter::set_polarity_detection`](crate::DRMeter::set_polarity_detection)).
+    pub fn inverted_pairs(&self) -> &[(u32, u32)] {
+        &self.inverted
+    }
+
+    /// Returns attached track metadata.
+    pub const fn metadata(&self) -> &TrackMetadata {
+        &self.metadata
+    }
+
+    /// Attach track metadata
+    pub fn set_metadata(&mut self, metadata: TrackMetadata) {
+        self.metadata = metadata;
+    }
+
+    /// Attach track metadata
+    pub fn with_metadata(mut self, metadata: TrackMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Save results in compact binary encoding
+    ///
+    /// Encoding is versioned with [`RESULTS_SCHEMA_VERSION`](crate::RESULTS_SCHEMA_VERSION).
+    #[cfg(feature = "bincode")]
+    pub fn save<W: std::io::Write>(&self, writer: W) -> bincode::Result<()> {
+        crate::schema::save(self, writer)
+    }
+
+    /// Load results saved by [`save`](Self::save)
+    ///
+    /// Results saved with older schema versions are migrated.
+    #[cfg(feature = "bincode")]
+    pub fn load<R: std::io::Read>(reader: R) -> bincode::Result<Self> {
+        crate::schema::load(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "bincode")]
+    use crate::DRMeter;
+
+    use super::*;
+
+    #[test]
+    fn confidence() {
+        let measure = |blocks: usize, silent: usize| {
+            let mut dr = crate::DRMeter::new_with_window(1, 1000, 10).unwrap();
+            let data: Vec<f32> = (0..blocks * 10)
+                .map(|i| if i < silent * 10 { 0.0 } else { 0.5 })
+                .collect();
+            dr.add_frames_f32(&data).unwrap();
+            dr.finalize().unwrap();
+            dr.results().unwrap().confidence()
+        };
+        assert_eq!(measure(4, 0), Confidence::Low);
+        assert_eq!(measure(30, 20), Confidence::Low);
+        assert_eq!(measure(10, 0), Confidence::Medium);
+        assert_eq!(measure(30, 10), Confidence::Medium);
+        assert_eq!(measure(30, 0), Confidence::High);
+        assert!(Confidence::Low < Confidence::High);
+    }
+
+    #[test]
+    fn channel_spread() {
+        let spread = ChannelSpread::new([8.0, 12.0].into_iter());
+        assert_eq!(spread.range, 4.0);
+        assert_eq!(spread.std_dev, 2.0);
+        assert!(spread.is_inconsistent());
+
+        let spread = ChannelSpread::new([10.0].into_iter());
+        assert_eq!(spread.range, 0.0);
+        assert_eq!(spread.std_dev, 0.0);
+        assert!(!spread.is_inconsistent());
+    }
+
+    #[test]
+    fn worst_block() {
+        let block = |start, peak, rms| WorstBlock { start, peak, rms };
+        let worst = WorstBlock::worse(None, block(0, 0.0, 0.0));
+        assert_eq!(worst, None);
+        let worst = WorstBlock::worse(worst, block(0, 1.0, 0.25));
+        let worst = WorstBlock::worse(worst, block(10, 0.5, 0.25));
+        let worst = WorstBlock::worse(worst, block(20, 1.0, 0.5));
+        assert_eq!(worst, Some(block(10, 0.5, 0.25)));
+        assert_eq!(worst.unwrap().time(10), Duration::from_secs(1));
+        assert!((worst.unwrap().crest_factor_db() - 6.02).abs() < 0.01);
+    }
+
+    #[test]
+    #[cfg(feature = "bincode")]
+    fn save_load() {
+        let data: Vec<f32> = (0..48_000 * 10)
+            .map(|i| f32::sin(i as f32 * 0.01) * (i % 7) as f32 / 7.0)
+            .collect();
+        let mut dr = DRMeter::new(2, 48_000).unwrap();
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        let results = dr.results().unwrap().with_metadata(TrackMetadata {
+            artist: Some("Artist".to_owned()),
+            bit_depth: Some(24),
+            ..Default::default()
+        });
+
+        let mut saved = Vec::new();
+        results.save(&mut saved).unwrap();
+        let loaded = DRResults::load(saved.as_slice()).unwrap();
+        assert_eq!(results, loaded);
+        assert_eq!(loaded.exact_dr(), dr.exact_dr().unwrap());
+    }
+}
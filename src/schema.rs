@@ -0,0 +1,896 @@
+//! Versioned encoding of saved [`DRResults`]
+//!
+//! Saved results start with magic bytes and the schema version,
+//! followed by bincode encoded results of that version.
+//! Older versions are migrated to current [`DRResults`] on load.
+//!
+//! Version 0 (results saved before versioning) has no header,
+//! otherwise it has the same layout as version 1.
+//! Its first field is sample rate, which cannot collide with the magic
+//! as such rate would be above the rate limit.
+//!
+//! Version history:
+//! - 1: initial version
+//! - 2: added number of measured frames
+//! - 3: added track metadata
+//! - 4: added overflow counters
+//! - 5: added worst blocks
+//! - 6: added dual-mono channel pairs
+//! - 7: added polarity inverted channel pairs
+//! - 8: added DR numerator
+//! - 9: added top 20% RMS blocks of exact mode
+//! - 10: added album artist and disc number to track metadata
+//! - 11: added loud block fraction
+
+use std::io::{Read, Write};
+
+use crate::DRResults;
+
+/// Current schema version of saved results
+pub const RESULTS_SCHEMA_VERSION: u16 = 11;
+
+/// Magic bytes that start saved results
+const MAGIC: [u8; 4] = *b"DRMR";
+
+/// Frozen layouts of older versions
+mod v1 {
+    use serde::{Deserialize, Serialize};
+
+    use crate::results::ChannelResults as CurrentChannelResults;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ChannelResults {
+        pub dr: f64,
+        pub first_peak: f64,
+        pub second_peak: f64,
+        pub rms: f64,
+        pub peak_histogram: Vec<(usize, u32)>,
+        pub rms_histogram: Vec<(usize, u32)>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v1: DRResults) -> Self {
+            Self {
+                rate: v1.rate,
+                window: v1.window,
+                blocks: v1.blocks,
+                // number of frames was not stored, so estimate it from full blocks
+                frames: (v1.blocks as u64 * v1.window as u64 * v1.rate as u64) / 1000,
+                bins: v1.bins,
+                channels: v1.channels.into_iter().map(Into::into).collect(),
+                dual_mono: Vec::new(),
+                inverted: Vec::new(),
+                // only sample peaks were used
+                dr_numerator: Default::default(),
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: Default::default(),
+            }
+        }
+    }
+
+    impl From<ChannelResults> for CurrentChannelResults {
+        fn from(ch: ChannelResults) -> Self {
+            Self {
+                dr: ch.dr,
+                first_peak: ch.first_peak,
+                second_peak: ch.second_peak,
+                rms: ch.rms,
+                peak_histogram: ch.peak_histogram,
+                rms_histogram: ch.rms_histogram,
+                // overflows were not counted
+                peak_overflows: 0,
+                rms_overflows: 0,
+                worst_block: None,
+                exact_loud_blocks: None,
+            }
+        }
+    }
+}
+
+mod v2 {
+    use serde::{Deserialize, Serialize};
+
+    // channel layout did not change since version 1
+    pub use super::v1::ChannelResults;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub frames: u64,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v2: DRResults) -> Self {
+            Self {
+                rate: v2.rate,
+                window: v2.window,
+                blocks: v2.blocks,
+                frames: v2.frames,
+                bins: v2.bins,
+                channels: v2.channels.into_iter().map(Into::into).collect(),
+                dual_mono: Vec::new(),
+                inverted: Vec::new(),
+                // only sample peaks were used
+                dr_numerator: Default::default(),
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: Default::default(),
+            }
+        }
+    }
+}
+
+mod v3 {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Serialize};
+
+    // channel layout did not change since version 1
+    pub use super::v1::ChannelResults;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct TrackMetadata {
+        pub artist: Option<String>,
+        pub title: Option<String>,
+        pub album: Option<String>,
+        pub codec: Option<String>,
+        pub bit_depth: Option<u32>,
+        pub sample_rate: Option<u32>,
+        pub duration: Option<Duration>,
+    }
+
+    impl From<TrackMetadata> for crate::TrackMetadata {
+        fn from(v3: TrackMetadata) -> Self {
+            Self {
+                artist: v3.artist,
+                title: v3.title,
+                album: v3.album,
+                codec: v3.codec,
+                bit_depth: v3.bit_depth,
+                sample_rate: v3.sample_rate,
+                duration: v3.duration,
+                // album tags were not read
+                ..Default::default()
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub frames: u64,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+        pub metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v3: DRResults) -> Self {
+            Self {
+                rate: v3.rate,
+                window: v3.window,
+                blocks: v3.blocks,
+                frames: v3.frames,
+                bins: v3.bins,
+                channels: v3.channels.into_iter().map(Into::into).collect(),
+                dual_mono: Vec::new(),
+                inverted: Vec::new(),
+                // only sample peaks were used
+                dr_numerator: Default::default(),
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v3.metadata.into(),
+            }
+        }
+    }
+}
+
+mod v4 {
+    use serde::{Deserialize, Serialize};
+
+    // metadata layout did not change since version 3
+    use super::v3::TrackMetadata;
+    use crate::results::ChannelResults as CurrentChannelResults;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ChannelResults {
+        pub dr: f64,
+        pub first_peak: f64,
+        pub second_peak: f64,
+        pub rms: f64,
+        pub peak_histogram: Vec<(usize, u32)>,
+        pub rms_histogram: Vec<(usize, u32)>,
+        pub peak_overflows: u32,
+        pub rms_overflows: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub frames: u64,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+        pub metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v4: DRResults) -> Self {
+            Self {
+                rate: v4.rate,
+                window: v4.window,
+                blocks: v4.blocks,
+                frames: v4.frames,
+                bins: v4.bins,
+                channels: v4.channels.into_iter().map(Into::into).collect(),
+                dual_mono: Vec::new(),
+                inverted: Vec::new(),
+                // only sample peaks were used
+                dr_numerator: Default::default(),
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v4.metadata.into(),
+            }
+        }
+    }
+
+    impl From<ChannelResults> for CurrentChannelResults {
+        fn from(ch: ChannelResults) -> Self {
+            Self {
+                dr: ch.dr,
+                first_peak: ch.first_peak,
+                second_peak: ch.second_peak,
+                rms: ch.rms,
+                peak_histogram: ch.peak_histogram,
+                rms_histogram: ch.rms_histogram,
+                peak_overflows: ch.peak_overflows,
+                rms_overflows: ch.rms_overflows,
+                // blocks were not tracked
+                worst_block: None,
+                exact_loud_blocks: None,
+            }
+        }
+    }
+}
+
+mod v5 {
+    use serde::{Deserialize, Serialize};
+
+    // metadata layout did not change since version 3
+    use super::v3::TrackMetadata;
+    use crate::results::ChannelResults as CurrentChannelResults;
+    use crate::WorstBlock;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ChannelResults {
+        pub dr: f64,
+        pub first_peak: f64,
+        pub second_peak: f64,
+        pub rms: f64,
+        pub peak_histogram: Vec<(usize, u32)>,
+        pub rms_histogram: Vec<(usize, u32)>,
+        pub peak_overflows: u32,
+        pub rms_overflows: u32,
+        pub worst_block: Option<WorstBlock>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub frames: u64,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+        pub metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v5: DRResults) -> Self {
+            Self {
+                rate: v5.rate,
+                window: v5.window,
+                blocks: v5.blocks,
+                frames: v5.frames,
+                bins: v5.bins,
+                channels: v5.channels.into_iter().map(Into::into).collect(),
+                dual_mono: Vec::new(),
+                inverted: Vec::new(),
+                // only sample peaks were used
+                dr_numerator: Default::default(),
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v5.metadata.into(),
+            }
+        }
+    }
+
+    impl From<ChannelResults> for CurrentChannelResults {
+        fn from(ch: ChannelResults) -> Self {
+            Self {
+                dr: ch.dr,
+                first_peak: ch.first_peak,
+                second_peak: ch.second_peak,
+                rms: ch.rms,
+                peak_histogram: ch.peak_histogram,
+                rms_histogram: ch.rms_histogram,
+                peak_overflows: ch.peak_overflows,
+                rms_overflows: ch.rms_overflows,
+                worst_block: ch.worst_block,
+                // selection of blocks was not kept
+                exact_loud_blocks: None,
+            }
+        }
+    }
+}
+
+mod v6 {
+    use serde::{Deserialize, Serialize};
+
+    // channel layout did not change since version 5
+    pub use super::v5::ChannelResults;
+    // metadata layout did not change since version 3
+    use super::v3::TrackMetadata;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub frames: u64,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+        pub dual_mono: Vec<(u32, u32)>,
+        pub metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v6: DRResults) -> Self {
+            Self {
+                rate: v6.rate,
+                window: v6.window,
+                blocks: v6.blocks,
+                frames: v6.frames,
+                bins: v6.bins,
+                channels: v6.channels.into_iter().map(Into::into).collect(),
+                dual_mono: v6.dual_mono,
+                inverted: Vec::new(),
+                // only sample peaks were used
+                dr_numerator: Default::default(),
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v6.metadata.into(),
+            }
+        }
+    }
+}
+
+mod v7 {
+    use serde::{Deserialize, Serialize};
+
+    // channel layout did not change since version 5
+    pub use super::v5::ChannelResults;
+    // metadata layout did not change since version 3
+    use super::v3::TrackMetadata;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub frames: u64,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+        pub dual_mono: Vec<(u32, u32)>,
+        pub inverted: Vec<(u32, u32)>,
+        pub metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v7: DRResults) -> Self {
+            Self {
+                rate: v7.rate,
+                window: v7.window,
+                blocks: v7.blocks,
+                frames: v7.frames,
+                bins: v7.bins,
+                channels: v7.channels.into_iter().map(Into::into).collect(),
+                dual_mono: v7.dual_mono,
+                inverted: v7.inverted,
+                // only sample peaks were used
+                dr_numerator: Default::default(),
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v7.metadata.into(),
+            }
+        }
+    }
+}
+
+mod v8 {
+    use serde::{Deserialize, Serialize};
+
+    // channel layout did not change since version 5
+    pub use super::v5::ChannelResults;
+    // metadata layout did not change since version 3
+    use super::v3::TrackMetadata;
+    use crate::DrNumerator;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct DRResults {
+        pub rate: u32,
+        pub window: usize,
+        pub blocks: usize,
+        pub frames: u64,
+        pub bins: usize,
+        pub channels: Vec<ChannelResults>,
+        pub dual_mono: Vec<(u32, u32)>,
+        pub inverted: Vec<(u32, u32)>,
+        pub dr_numerator: DrNumerator,
+        pub metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v8: DRResults) -> Self {
+            Self {
+                rate: v8.rate,
+                window: v8.window,
+                blocks: v8.blocks,
+                frames: v8.frames,
+                bins: v8.bins,
+                channels: v8.channels.into_iter().map(Into::into).collect(),
+                dual_mono: v8.dual_mono,
+                inverted: v8.inverted,
+                dr_numerator: v8.dr_numerator,
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v8.metadata.into(),
+            }
+        }
+    }
+}
+
+mod v9 {
+    use serde::{Deserialize, Serialize};
+
+    // metadata layout did not change since version 3
+    use super::v3::TrackMetadata;
+    use crate::results::ChannelResults;
+    use crate::DrNumerator;
+
+    #[derive(Serialize, Deserialize)]
+    pub(crate) struct DRResults {
+        pub(crate) rate: u32,
+        pub(crate) window: usize,
+        pub(crate) blocks: usize,
+        pub(crate) frames: u64,
+        pub(crate) bins: usize,
+        pub(crate) channels: Vec<ChannelResults>,
+        pub(crate) dual_mono: Vec<(u32, u32)>,
+        pub(crate) inverted: Vec<(u32, u32)>,
+        pub(crate) dr_numerator: DrNumerator,
+        pub(crate) metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v9: DRResults) -> Self {
+            Self {
+                rate: v9.rate,
+                window: v9.window,
+                blocks: v9.blocks,
+                frames: v9.frames,
+                bins: v9.bins,
+                channels: v9.channels,
+                dual_mono: v9.dual_mono,
+                inverted: v9.inverted,
+                dr_numerator: v9.dr_numerator,
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v9.metadata.into(),
+            }
+        }
+    }
+}
+
+mod v10 {
+    use serde::{Deserialize, Serialize};
+
+    // channel layout did not change since version 9
+    use crate::results::ChannelResults;
+    use crate::{DrNumerator, TrackMetadata};
+
+    #[derive(Serialize, Deserialize)]
+    pub(crate) struct DRResults {
+        pub(crate) rate: u32,
+        pub(crate) window: usize,
+        pub(crate) blocks: usize,
+        pub(crate) frames: u64,
+        pub(crate) bins: usize,
+        pub(crate) channels: Vec<ChannelResults>,
+        pub(crate) dual_mono: Vec<(u32, u32)>,
+        pub(crate) inverted: Vec<(u32, u32)>,
+        pub(crate) dr_numerator: DrNumerator,
+        pub(crate) metadata: TrackMetadata,
+    }
+
+    impl From<DRResults> for crate::DRResults {
+        fn from(v10: DRResults) -> Self {
+            Self {
+                rate: v10.rate,
+                window: v10.window,
+                blocks: v10.blocks,
+                frames: v10.frames,
+                bins: v10.bins,
+                channels: v10.channels,
+                dual_mono: v10.dual_mono,
+                inverted: v10.inverted,
+                dr_numerator: v10.dr_numerator,
+                // only top 20% was used
+                loud_fraction: crate::LOUD_FRACTION,
+                metadata: v10.metadata,
+            }
+        }
+    }
+}
+
+pub(crate) fn save<W: Write>(results: &DRResults, mut writer: W) -> bincode::Result<()> {
+    writer.write_all(&MAGIC)?;
+    bincode::serialize_into(&mut writer, &RESULTS_SCHEMA_VERSION)?;
+    bincode::serialize_into(writer, results)
+}
+
+pub(crate) fn load<R: Read>(mut reader: R) -> bincode::Result<DRResults> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        // version 0 without header
+        return bincode::deserialize_from::<_, v1::DRResults>(magic.chain(reader)).map(Into::into);
+    }
+
+    let version: u16 = bincode::deserialize_from(&mut reader)?;
+    match version {
+        1 => bincode::deserialize_from::<_, v1::DRResults>(reader).map(Into::into),
+        2 => bincode::deserialize_from::<_, v2::DRResults>(reader).map(Into::into),
+        3 => bincode::deserialize_from::<_, v3::DRResults>(reader).map(Into::into),
+        4 => bincode::deserialize_from::<_, v4::DRResults>(reader).map(Into::into),
+        5 => bincode::deserialize_from::<_, v5::DRResults>(reader).map(Into::into),
+        6 => bincode::deserialize_from::<_, v6::DRResults>(reader).map(Into::into),
+        7 => bincode::deserialize_from::<_, v7::DRResults>(reader).map(Into::into),
+        8 => bincode::deserialize_from::<_, v8::DRResults>(reader).map(Into::into),
+        9 => bincode::deserialize_from::<_, v9::DRResults>(reader).map(Into::into),
+        10 => bincode::deserialize_from::<_, v10::DRResults>(reader).map(Into::into),
+        11 => bincode::deserialize_from(reader),
+        _ => Err(Box::new(bincode::ErrorKind::Custom(format!(
+            "unsupported results schema version {version}"
+        )))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DRMeter;
+
+    fn results() -> DRResults {
+        let data: Vec<i16> = (0..44_100 * 6)
+            .map(|i| ((i * 37) % 20_000) as i16)
+            .collect();
+        let mut dr = DRMeter::new(1, 44_100).unwrap();
+        dr.add_frames_i16(&data).unwrap();
+        dr.finalize().unwrap();
+        dr.results().unwrap()
+    }
+
+    /// Results as migrated from versions without worst blocks
+    fn without_worst_blocks(mut results: DRResults) -> DRResults {
+        for ch in &mut results.channels {
+            ch.worst_block = None;
+        }
+        results
+    }
+
+    fn as_v1_channels(results: &DRResults) -> Vec<v1::ChannelResults> {
+        results
+            .channels
+            .iter()
+            .map(|ch| v1::ChannelResults {
+                dr: ch.dr,
+                first_peak: ch.first_peak,
+                second_peak: ch.second_peak,
+                rms: ch.rms,
+                peak_histogram: ch.peak_histogram.clone(),
+                rms_histogram: ch.rms_histogram.clone(),
+            })
+            .collect()
+    }
+
+    fn as_v5_channels(results: &DRResults) -> Vec<v5::ChannelResults> {
+        results
+            .channels
+            .iter()
+            .map(|ch| v5::ChannelResults {
+                dr: ch.dr,
+                first_peak: ch.first_peak,
+                second_peak: ch.second_peak,
+                rms: ch.rms,
+                peak_histogram: ch.peak_histogram.clone(),
+                rms_histogram: ch.rms_histogram.clone(),
+                peak_overflows: ch.peak_overflows,
+                rms_overflows: ch.rms_overflows,
+                worst_block: ch.worst_block,
+            })
+            .collect()
+    }
+
+    fn as_v3_metadata(results: &DRResults) -> v3::TrackMetadata {
+        let metadata = results.metadata.clone();
+        v3::TrackMetadata {
+            artist: metadata.artist,
+            title: metadata.title,
+            album: metadata.album,
+            codec: metadata.codec,
+            bit_depth: metadata.bit_depth,
+            sample_rate: metadata.sample_rate,
+            duration: metadata.duration,
+        }
+    }
+
+    fn as_v1(results: &DRResults) -> v1::DRResults {
+        v1::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            bins: results.bins,
+            channels: as_v1_channels(results),
+        }
+    }
+
+    #[test]
+    fn migrate_v0() {
+        let results = results();
+        let v0 = bincode::serialize(&as_v1(&results)).unwrap();
+        assert_eq!(load(v0.as_slice()).unwrap(), without_worst_blocks(results));
+    }
+
+    #[test]
+    fn migrate_v1() {
+        let results = results();
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&1u16).unwrap());
+        data.extend(bincode::serialize(&as_v1(&results)).unwrap());
+        assert_eq!(
+            load(data.as_slice()).unwrap(),
+            without_worst_blocks(results)
+        );
+    }
+
+    #[test]
+    fn migrate_v2() {
+        let results = results();
+        let v2 = v2::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: as_v1_channels(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&2u16).unwrap());
+        data.extend(bincode::serialize(&v2).unwrap());
+        assert_eq!(
+            load(data.as_slice()).unwrap(),
+            without_worst_blocks(results)
+        );
+    }
+
+    #[test]
+    fn migrate_v3() {
+        let results = results().with_metadata(crate::TrackMetadata {
+            title: Some("Title".to_owned()),
+            ..Default::default()
+        });
+        let v3 = v3::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: as_v1_channels(&results),
+            metadata: as_v3_metadata(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&3u16).unwrap());
+        data.extend(bincode::serialize(&v3).unwrap());
+        assert_eq!(
+            load(data.as_slice()).unwrap(),
+            without_worst_blocks(results)
+        );
+    }
+
+    #[test]
+    fn migrate_v4() {
+        let results = results();
+        let v4 = v4::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: results
+                .channels
+                .iter()
+                .map(|ch| v4::ChannelResults {
+                    dr: ch.dr,
+                    first_peak: ch.first_peak,
+                    second_peak: ch.second_peak,
+                    rms: ch.rms,
+                    peak_histogram: ch.peak_histogram.clone(),
+                    rms_histogram: ch.rms_histogram.clone(),
+                    peak_overflows: ch.peak_overflows,
+                    rms_overflows: ch.rms_overflows,
+                })
+                .collect(),
+            metadata: as_v3_metadata(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&4u16).unwrap());
+        data.extend(bincode::serialize(&v4).unwrap());
+        assert_eq!(
+            load(data.as_slice()).unwrap(),
+            without_worst_blocks(results)
+        );
+    }
+
+    #[test]
+    fn migrate_v5() {
+        let results = results();
+        let v5 = v5::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: as_v5_channels(&results),
+            metadata: as_v3_metadata(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&5u16).unwrap());
+        data.extend(bincode::serialize(&v5).unwrap());
+        assert_eq!(load(data.as_slice()).unwrap(), results);
+    }
+
+    #[test]
+    fn migrate_v6() {
+        let mut results = results();
+        results.dual_mono = vec![(0, 1)];
+        let v6 = v6::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: as_v5_channels(&results),
+            dual_mono: results.dual_mono.clone(),
+            metadata: as_v3_metadata(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&6u16).unwrap());
+        data.extend(bincode::serialize(&v6).unwrap());
+        assert_eq!(load(data.as_slice()).unwrap(), results);
+    }
+
+    #[test]
+    fn migrate_v7() {
+        let mut results = results();
+        results.inverted = vec![(0, 1)];
+        let v7 = v7::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: as_v5_channels(&results),
+            dual_mono: results.dual_mono.clone(),
+            inverted: results.inverted.clone(),
+            metadata: as_v3_metadata(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&7u16).unwrap());
+        data.extend(bincode::serialize(&v7).unwrap());
+        assert_eq!(load(data.as_slice()).unwrap(), results);
+    }
+
+    #[test]
+    fn migrate_v8() {
+        let mut results = results();
+        results.dr_numerator = crate::DrNumerator::TruePeak;
+        let v8 = v8::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: as_v5_channels(&results),
+            dual_mono: results.dual_mono.clone(),
+            inverted: results.inverted.clone(),
+            dr_numerator: results.dr_numerator,
+            metadata: as_v3_metadata(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&8u16).unwrap());
+        data.extend(bincode::serialize(&v8).unwrap());
+        assert_eq!(load(data.as_slice()).unwrap(), results);
+    }
+
+    #[test]
+    fn migrate_v9() {
+        let mut dr = DRMeter::new(1, 44_100).unwrap();
+        dr.set_exact_mode(true).unwrap();
+        dr.add_frames_i16(&[1000; 44_100 * 6]).unwrap();
+        dr.finalize().unwrap();
+        let results = dr.results().unwrap();
+        let v9 = v9::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: results.channels.clone(),
+            dual_mono: results.dual_mono.clone(),
+            inverted: results.inverted.clone(),
+            dr_numerator: results.dr_numerator,
+            metadata: as_v3_metadata(&results),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&9u16).unwrap());
+        data.extend(bincode::serialize(&v9).unwrap());
+        assert_eq!(load(data.as_slice()).unwrap(), results);
+    }
+
+    #[test]
+    fn migrate_v10() {
+        let results = results();
+        let v10 = v10::DRResults {
+            rate: results.rate,
+            window: results.window,
+            blocks: results.blocks,
+            frames: results.frames,
+            bins: results.bins,
+            channels: results.channels.clone(),
+            dual_mono: results.dual_mono.clone(),
+            inverted: results.inverted.clone(),
+            dr_numerator: results.dr_numerator,
+            metadata: results.metadata.clone(),
+        };
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&10u16).unwrap());
+        data.extend(bincode::serialize(&v10).unwrap());
+        assert_eq!(load(data.as_slice()).unwrap(), results);
+    }
+
+    #[test]
+    fn unsupported_version() {
+        let mut data = MAGIC.to_vec();
+        data.extend(bincode::serialize(&(RESULTS_SCHEMA_VERSION + 1)).unwrap());
+        data.extend(bincode::serialize(&results()).unwrap());
+        assert!(load(data.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Integer DR score
+///
+/// Displayed as `DR12`, use [`get`](Self::get) for the plain number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct DRScore(u8);
+
+impl DRScore {
+    /// Create score from integer value
+    pub const fn new(score: u8) -> Self {
+        Self(score)
+    }
+
+    /// Create score from exact DR
+    ///
+    /// Score is the integer part of exact DR (as in the TT DR Offline Meter),
+    /// negative and `NaN` values are `DR0`.
+    pub fn from_exact(exact: f64) -> Self {
+        Self(exact as u8)
+    }
+
+    /// Returns score as integer
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+}
+
+impl From<u8> for DRScore {
+    fn from(score: u8) -> Self {
+        Self(score)
+    }
+}
+
+impl From<DRScore> for u8 {
+    fn from(score: DRScore) -> Self {
+        score.0
+    }
+}
+
+impl fmt::Display for DRScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(&format!("DR{}", self.0))
+    }
+}
+
+/// Parses `DR8`, `dr8` or plain `8`
+impl FromStr for DRScore {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let n = s
+            .strip_prefix("DR")
+            .or_else(|| s.strip_prefix("dr"))
+            .unwrap_or(s);
+        n.parse().map(Self).map_err(|_| crate::Error::ArgOutside)
+    }
+}
+
+/// Statistic that aggregates exact DR of multiple tracks
+///
+/// Official album DR is the mean, which can hide a single badly
+/// compressed track, so other statistics are available for comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Aggregation {
+    /// Average (official)
+    #[default]
+    Mean,
+    /// Middle value (average of two middle values for even count)
+    Median,
+    /// Lowest value
+    Minimum,
+    /// Highest value
+    Maximum,
+}
+
+impl Aggregation {
+    /// All aggregations
+    pub const ALL: [Self; 4] = [Self::Mean, Self::Median, Self::Minimum, Self::Maximum];
+
+    /// Returns name of aggregation
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Mean => "mean",
+            Self::Median => "median",
+            Self::Minimum => "minimum",
+            Self::Maximum => "maximum",
+        }
+    }
+
+    /// Aggregate values
+    ///
+    /// No values aggregate to `NaN`.
+    pub fn apply(self, values: impl IntoIterator<Item = f64>) -> f64 {
+        let mut values: Vec<f64> = values.into_iter().collect();
+        if values.is_empty() {
+            return f64::NAN;
+        }
+        match self {
+            Self::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            Self::Median => {
+                values.sort_unstable_by(f64::total_cmp);
+                let mid = values.len() / 2;
+                if values.len().is_multiple_of(2) {
+                    (values[mid - 1] + values[mid]) / 2.0
+                } else {
+                    values[mid]
+                }
+            }
+            Self::Minimum => values.into_iter().fold(f64::INFINITY, f64::min),
+            Self::Maximum => values.into_iter().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(DRScore::from_exact(12.97).to_string(), "DR12");
+        assert_eq!(format!("{:<6}|", DRScore::new(7)), "DR7   |");
+        assert_eq!(DRScore::from_exact(f64::NAN), DRScore::new(0));
+        assert!(DRScore::new(13) > DRScore::new(9));
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("DR8".parse(), Ok(DRScore::new(8)));
+        assert_eq!("dr12".parse(), Ok(DRScore::new(12)));
+        assert_eq!("5".parse(), Ok(DRScore::new(5)));
+        assert!("DR".parse::<DRScore>().is_err());
+        assert!("DR-1".parse::<DRScore>().is_err());
+    }
+
+    #[test]
+    fn aggregation() {
+        let values = [12.5, 4.0, 10.0, 11.0];
+        assert_eq!(Aggregation::Mean.apply(values), 9.375);
+        assert_eq!(Aggregation::Median.apply(values), 10.5);
+        assert_eq!(Aggregation::Median.apply([3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(Aggregation::Minimum.apply(values), 4.0);
+        assert_eq!(Aggregation::Maximum.apply(values), 12.5);
+        assert!(Aggregation::Median.apply([]).is_nan());
+    }
+}
@@ -0,0 +1,110 @@
+//! [`Sink`] adapter for async pipelines
+//!
+//! [`DRSink`] accepts [`FrameChunk`]s, so decoded streams can be
+//! `.forward()`ed into the meter. Closing the sink finalizes the meter.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_sink::Sink;
+
+use crate::{DRMeter, Error};
+
+/// Chunk of interleaved frames sent into [`DRSink`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameChunk {
+    /// Interleaved `i16` frames
+    I16(Vec<i16>),
+    /// Interleaved `i32` frames
+    I32(Vec<i32>),
+    /// Interleaved `f32` frames
+    F32(Vec<f32>),
+    /// Interleaved `f64` frames
+    F64(Vec<f64>),
+}
+
+/// [`Sink`] of [`FrameChunk`]s over [`DRMeter`]
+#[derive(Debug)]
+pub struct DRSink {
+    meter: DRMeter,
+}
+
+impl DRSink {
+    /// Create sink over meter
+    pub fn new(meter: DRMeter) -> Self {
+        Self { meter }
+    }
+
+    /// Returns the meter
+    pub fn meter(&self) -> &DRMeter {
+        &self.meter
+    }
+
+    /// Consume sink and return the meter
+    pub fn into_inner(self) -> DRMeter {
+        self.meter
+    }
+}
+
+impl From<DRMeter> for DRSink {
+    fn from(meter: DRMeter) -> Self {
+        Self::new(meter)
+    }
+}
+
+impl Sink<FrameChunk> for DRSink {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, chunk: FrameChunk) -> Result<(), Error> {
+        let meter = &mut self.get_mut().meter;
+        match &chunk {
+            FrameChunk::I16(f) => meter.add_frames_i16(f),
+            FrameChunk::I32(f) => meter.add_frames_i32(f),
+            FrameChunk::F32(f) => meter.add_frames_f32(f),
+            FrameChunk::F64(f) => meter.add_frames_f64(f),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Finalizes the meter (closing more than once is fine)
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        let meter = &mut self.get_mut().meter;
+        if meter.finalized() {
+            return Poll::Ready(Ok(()));
+        }
+        Poll::Ready(meter.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{executor::block_on, stream, StreamExt};
+
+    #[test]
+    fn forward() {
+        let frames: Vec<f32> = (0..8000 * 3)
+            .map(|i| f32::sin(i as f32 * 0.1) * (i % 8000) as f32 / 8000.0)
+            .collect();
+        let mut expected = DRMeter::new(1, 8000).unwrap();
+        expected.add_frames_f32(&frames).unwrap();
+        expected.finalize().unwrap();
+
+        let mut sink = DRSink::new(DRMeter::new(1, 8000).unwrap());
+        let chunks = frames
+            .chunks(1000)
+            .map(|chunk| Ok(FrameChunk::F32(chunk.to_vec())));
+        block_on(stream::iter(chunks).forward(&mut sink)).unwrap();
+
+        let meter = sink.into_inner();
+        assert!(meter.finalized());
+        assert_eq!(meter.exact_dr(), expected.exact_dr());
+    }
+}
@@ -0,0 +1,183 @@
+//! True peak detection by polyphase oversampling
+//!
+//! Interpolation filter follows ITU-R BS.1770 Annex 2 like
+//! [ebur128](https://github.com/sdroege/ebur128): 49 tap windowed sinc,
+//! 4x oversampling below 96 kHz and 2x below 192 kHz.
+
+use std::f64::consts::PI;
+
+use crate::math;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Number of taps of interpolation filter
+const TAPS: usize = 49;
+
+/// Tap of interpolation filter as `(delay, coefficient)`
+type Tap = (usize, f64);
+
+/// Polyphase interpolator of all channels
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct Oversampler {
+    /// taps of every phase
+    phases: Box<[Box<[Tap]>]>,
+    /// length of history of one channel
+    delay: usize,
+    /// last `delay` samples per channel (ring buffers)
+    history: Box<[f64]>,
+    /// next write position in ring buffer per channel
+    pos: Box<[usize]>,
+}
+
+impl Oversampler {
+    /// Returns oversampling factor for the given rate
+    pub fn factor(rate: u32) -> usize {
+        match rate {
+            0..96_000 => 4,
+            96_000..192_000 => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn new(rate: u32, channels: usize) -> Self {
+        let factor = Self::factor(rate);
+        let delay = TAPS.div_ceil(factor);
+        let mut phases = vec![Vec::new(); factor];
+        for j in 0..TAPS {
+            let m = j as f64 - (TAPS - 1) as f64 / 2.0;
+            let sinc = if m == 0.0 {
+                1.0
+            } else {
+                math::sin_cos(m * PI / factor as f64).0 / (m * PI / factor as f64)
+            };
+            let window = 0.5 * (1.0 - math::cos(2.0 * PI * j as f64 / (TAPS - 1) as f64));
+            let c = sinc * window;
+            if c.abs() > 1e-12 {
+                phases[j % factor].push((j / factor, c));
+            }
+        }
+
+        Self {
+            phases: phases.into_iter().map(Vec::into_boxed_slice).collect(),
+            delay,
+            history: vec![0.0; channels * delay].into_boxed_slice(),
+            pos: vec![0; channels].into_boxed_slice(),
+        }
+    }
+
+    /// Clear history for a new stream
+    pub fn reset(&mut self) {
+        self.history.fill(0.0);
+        self.pos.fill(0);
+    }
+
+    /// Feed sample of channel, returns the highest absolute interpolated value
+    #[inline(always)]
+    pub fn process(&mut self, channel: usize, sample: f64) -> f64 {
+        let history = &mut self.history[channel * self.delay..(channel + 1) * self.delay];
+        let pos = &mut self.pos[channel];
+        history[*pos] = sample;
+
+        let mut max = 0.0f64;
+        for phase in self.phases.iter() {
+            let mut out = 0.0;
+            for &(d, c) in phase.iter() {
+                // `d` samples before the newest one
+                let i = (*pos + self.delay - d) % self.delay;
+                out += c * history[i];
+            }
+            max = max.max(out.abs());
+        }
+
+        *pos = (*pos + 1) % self.delay;
+        max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::{ConfigOption, DRMeter, DRMeterBuilder, DrNumerator, Error, PeakMode};
+
+    #[test]
+    fn inter_sample_peak() {
+        // sine at rate / 4 sampled at 45 degrees never hits its peak
+        let data: Vec<f32> = (0..48_000 * 10)
+            .map(|i| [1.0, 1.0, -1.0, -1.0][i % 4] * std::f32::consts::FRAC_1_SQRT_2)
+            .collect();
+        let mut sample = DRMeter::new(1, 48_000).unwrap();
+        sample.add_frames_f32(&data).unwrap();
+        sample.finalize().unwrap();
+        assert_float_eq!(
+            sample.first_peak(0).unwrap(),
+            std::f64::consts::FRAC_1_SQRT_2,
+            abs <= 1e-3
+        );
+        assert_eq!(sample.true_peak(0), Ok(None));
+
+        let mut true_peak = DRMeter::new(1, 48_000).unwrap();
+        true_peak.set_peak_mode(PeakMode::True).unwrap();
+        true_peak.add_frames_f32(&data).unwrap();
+        true_peak.finalize().unwrap();
+        let peak = true_peak.true_peak(0).unwrap().unwrap();
+        assert!(peak > 0.95 && peak < 1.05, "{peak}");
+        // true peaks are measured alongside, sample peaks and DR are the same
+        assert_eq!(true_peak.first_peak(0), sample.first_peak(0));
+        assert_eq!(true_peak.exact_dr(), sample.exact_dr());
+    }
+
+    #[test]
+    fn true_peak_numerator() {
+        // level rises, so the second highest block peak is below the first
+        let data: Vec<f32> = (0..48_000 * 10)
+            .map(|i| {
+                let level = 0.5 + i as f32 / 960_000.0;
+                [1.0, 1.0, -1.0, -1.0][i % 4] * std::f32::consts::FRAC_1_SQRT_2 * level
+            })
+            .collect();
+        let mut sample = DRMeter::new(1, 48_000).unwrap();
+        sample.add_frames_f32(&data).unwrap();
+        sample.finalize().unwrap();
+
+        let mut dr = DRMeter::new(1, 48_000).unwrap();
+        assert_eq!(
+            dr.set_dr_numerator(DrNumerator::TruePeak),
+            Err(Error::ArgOutside)
+        );
+        dr.set_peak_mode(PeakMode::True).unwrap();
+        dr.set_peak_headroom(3.0).unwrap();
+        dr.set_dr_numerator(DrNumerator::TruePeak).unwrap();
+        assert_eq!(dr.set_peak_mode(PeakMode::Sample), Err(Error::ArgOutside));
+        dr.add_frames_f32(&data).unwrap();
+        dr.finalize().unwrap();
+        let peak = dr.true_peak(0).unwrap().unwrap();
+        assert_float_eq!(dr.first_peak(0).unwrap(), peak, abs <= 1e-3);
+        // inter-sample peaks are 3 dB above sample peaks
+        assert_float_eq!(
+            dr.exact_dr().unwrap() - sample.exact_dr().unwrap(),
+            3.01,
+            abs <= 0.2
+        );
+        let results = dr.results().unwrap();
+        assert_eq!(results.dr_numerator(), DrNumerator::TruePeak);
+        assert_eq!(
+            sample.results().unwrap().dr_numerator(),
+            DrNumerator::SamplePeak
+        );
+
+        let builder = DRMeterBuilder::new()
+            .channels(1)
+            .rate(48_000)
+            .dr_numerator(DrNumerator::TruePeak);
+        assert_eq!(
+            builder.build().err(),
+            Some(Error::InvalidConfig {
+                option: ConfigOption::DrNumerator
+            })
+        );
+        let meter = builder.peak_mode(PeakMode::True).build().unwrap();
+        assert_eq!(meter.dr_numerator(), DrNumerator::TruePeak);
+    }
+}
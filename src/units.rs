@@ -0,0 +1,81 @@
+//! Conversions between linear values and decibels.
+//!
+//! All DR values, peaks and RMS levels are measured relative to full scale,
+//! so linear `1.0` corresponds to 0 dBFS.
+
+use crate::math;
+
+/// 20 * log10(n)
+///
+/// Converts a linear amplitude ratio to decibels. Zero maps to negative infinity.
+#[inline]
+pub fn decibel(n: f64) -> f64 {
+    20.0 * math::log10(n)
+}
+
+/// Inverse of [`decibel`]
+///
+/// Converts decibels to a linear amplitude ratio.
+#[inline]
+pub fn from_decibel(db: f64) -> f64 {
+    math::powf(10.0, db / 20.0)
+}
+
+/// Converts a linear sample amplitude (full scale is `1.0`) to dBFS.
+#[inline]
+pub fn amplitude_to_dbfs(amplitude: f64) -> f64 {
+    decibel(amplitude)
+}
+
+/// Converts dBFS to a linear sample amplitude (full scale is `1.0`).
+#[inline]
+pub fn dbfs_to_amplitude(dbfs: f64) -> f64 {
+    from_decibel(dbfs)
+}
+
+/// Converts a linear true peak amplitude (full scale is `1.0`) to dBTP.
+///
+/// The scale is the same as dBFS, the unit only marks that the value
+/// was measured on an oversampled signal.
+#[inline]
+pub fn true_peak_to_dbtp(true_peak: f64) -> f64 {
+    decibel(true_peak)
+}
+
+/// Converts dBTP to a linear true peak amplitude (full scale is `1.0`).
+#[inline]
+pub fn dbtp_to_true_peak(dbtp: f64) -> f64 {
+    from_decibel(dbtp)
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn full_scale() {
+        assert_float_eq!(amplitude_to_dbfs(1.0), 0.0, abs <= 1e-12);
+        assert_float_eq!(dbfs_to_amplitude(0.0), 1.0, abs <= 1e-12);
+        assert_float_eq!(true_peak_to_dbtp(1.0), 0.0, abs <= 1e-12);
+    }
+
+    #[test]
+    fn half_amplitude() {
+        assert_float_eq!(decibel(0.5), -6.020_599_913_279_624, abs <= 1e-12);
+        assert_float_eq!(from_decibel(-6.020_599_913_279_624), 0.5, abs <= 1e-12);
+    }
+
+    #[test]
+    fn silence() {
+        assert_eq!(amplitude_to_dbfs(0.0), f64::NEG_INFINITY);
+        assert_eq!(dbfs_to_amplitude(f64::NEG_INFINITY), 0.0);
+    }
+
+    #[quickcheck_macros::quickcheck]
+    fn roundtrip(amplitude: u16) -> bool {
+        let amplitude = (amplitude as f64 + 1.0) / 65536.0;
+        (dbfs_to_amplitude(amplitude_to_dbfs(amplitude)) - amplitude).abs() < 1e-12
+    }
+}
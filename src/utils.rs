@@ -1,12 +1,6 @@
 //! This file mostly copy of ebur128's utils.rs
 #![allow(dead_code)]
 
-/// 20 * log10(n)
-#[inline]
-pub fn decibel(n: f64) -> f64 {
-    20.0 * f64::log10(n)
-}
-
 /// Square
 ///
 /// Return a*a
@@ -55,6 +49,9 @@ pub fn energy_to_loudness(energy: f64) -> f64 {
 }
 
 /// Trait for abstracting over interleaved and planar samples.
+///
+/// Implement it for other buffer layouts (e.g. ring buffers) and pass them
+/// to [`DRMeter::add_samples`](crate::DRMeter::add_samples) without copying into slices.
 pub trait Samples<'a, S: Sample + 'a>: Sized {
     /// Call the given closure for each sample of the given channel.
     // FIXME: Workaround for TrustedLen / TrustedRandomAccess being unstable
@@ -71,8 +68,28 @@ pub trait Samples<'a, S: Sample + 'a>: Sized {
         func: impl FnMut(&'a S, U),
     );
 
+    /// Call the given closure for each frame.
     fn foreach_frame<F: Frame<Sample = S>>(&self, func: impl FnMut(F));
 
+    /// Samples of all channels are interleaved in one buffer.
+    ///
+    /// Such samples are deinterleaved by [`Samples::deinterleave`]
+    /// before processing instead of striding through them per channel.
+    const INTERLEAVED: bool = false;
+
+    /// Convert samples into `f64` planes (channel after channel) in `scratch`.
+    fn deinterleave(&self, scratch: &mut Vec<f64>) {
+        scratch.clear();
+        for channel in 0..self.channels() {
+            self.foreach_sample(channel, |s| scratch.push(s.to_sample::<f64>()));
+        }
+    }
+
+    /// Samples of the given channel, if they are stored contiguously.
+    fn channel_slice(&self, _channel: usize) -> Option<&'a [S]> {
+        None
+    }
+
     /// Number of frames.
     fn frames(&self) -> usize;
 
@@ -84,6 +101,7 @@ pub trait Samples<'a, S: Sample + 'a>: Sized {
 }
 
 /// Struct representing interleaved samples.
+#[derive(Clone, Copy)]
 pub struct Interleaved<'a, S> {
     /// Interleaved sample data.
     data: &'a [S],
@@ -98,7 +116,7 @@ impl<'a, S> Interleaved<'a, S> {
             return Err(crate::Error::NoMem);
         }
 
-        if data.len() % channels != 0 {
+        if !data.len().is_multiple_of(channels) {
             return Err(crate::Error::NoMem);
         }
 
@@ -107,6 +125,8 @@ impl<'a, S> Interleaved<'a, S> {
 }
 
 impl<'a, S: Sample> Samples<'a, S> for Interleaved<'a, S> {
+    const INTERLEAVED: bool = true;
+
     #[inline]
     fn foreach_sample(&self, channel: usize, mut func: impl FnMut(&'a S)) {
         assert!(channel < self.channels);
@@ -138,6 +158,35 @@ impl<'a, S: Sample> Samples<'a, S> for Interleaved<'a, S> {
         }
     }
 
+    /// Single pass over the buffer, instead of one strided pass per channel
+    ///
+    /// Planes are filled through their iterators, so there are no bounds checks.
+    fn deinterleave(&self, scratch: &mut Vec<f64>) {
+        let frames = self.frames();
+        scratch.clear();
+        if frames == 0 {
+            return;
+        }
+        scratch.resize(self.data.len(), 0.0);
+        let mut planes: Vec<_> = scratch
+            .chunks_exact_mut(frames)
+            .map(<[f64]>::iter_mut)
+            .collect();
+        for samples in self.data.chunks_exact(self.channels) {
+            for (plane, s) in planes.iter_mut().zip(samples) {
+                if let Some(d) = plane.next() {
+                    *d = s.to_sample::<f64>();
+                }
+            }
+        }
+    }
+
+    /// Only mono samples are contiguous
+    #[inline]
+    fn channel_slice(&self, _channel: usize) -> Option<&'a [S]> {
+        (self.channels == 1).then_some(self.data)
+    }
+
     #[inline]
     fn frames(&self) -> usize {
         self.data.len() / self.channels
@@ -166,7 +215,8 @@ impl<'a, S: Sample> Samples<'a, S> for Interleaved<'a, S> {
     }
 }
 
-/// Struct representing interleaved samples.
+/// Struct representing planar samples.
+#[derive(Clone, Copy)]
 pub struct Planar<'a, S> {
     data: &'a [&'a [S]],
     start: usize,
@@ -225,6 +275,11 @@ impl<'a, S: Sample> Samples<'a, S> for Planar<'a, S> {
         }
     }
 
+    #[inline]
+    fn channel_slice(&self, channel: usize) -> Option<&'a [S]> {
+        Some(&self.data[channel][self.start..self.end])
+    }
+
     #[inline]
     fn frames(&self) -> usize {
         self.end - self.start
@@ -254,40 +309,222 @@ impl<'a, S: Sample> Samples<'a, S> for Planar<'a, S> {
     }
 }
 
+/// Samples with channels selected and reordered by channel map.
+///
+/// Channel `i` of remapped samples is channel `map[i]` of inner samples,
+/// so map can also select a subset of inner channels.
+#[derive(Clone, Copy)]
+pub struct Remapped<'m, I> {
+    inner: I,
+    map: &'m [usize],
+}
+
+impl<'m, I> Remapped<'m, I> {
+    /// Wrap samples, `map` must not repeat inner channels.
+    pub fn new(inner: I, map: &'m [usize]) -> Self {
+        Remapped { inner, map }
+    }
+}
+
+impl<'a, 'm, S: Sample + 'a, I: Samples<'a, S>> Samples<'a, S> for Remapped<'m, I> {
+    const INTERLEAVED: bool = I::INTERLEAVED;
+
+    #[inline]
+    fn foreach_sample(&self, channel: usize, func: impl FnMut(&'a S)) {
+        self.inner.foreach_sample(self.map[channel], func)
+    }
+
+    #[inline]
+    fn foreach_sample_zipped<U>(
+        &self,
+        channel: usize,
+        iter: impl Iterator<Item = U>,
+        func: impl FnMut(&'a S, U),
+    ) {
+        self.inner
+            .foreach_sample_zipped(self.map[channel], iter, func)
+    }
+
+    /// Only supported for reordering all inner channels
+    #[inline]
+    fn foreach_frame<F: Frame<Sample = S>>(&self, mut func: impl FnMut(F)) {
+        assert_eq!(self.map.len(), self.inner.channels());
+        self.inner
+            .foreach_frame(|f: F| func(F::from_fn(|c| f.channel(self.map[c]).copied().unwrap())))
+    }
+
+    /// Inner samples are deinterleaved and planes are then permuted in place
+    ///
+    /// Subset of channels is gathered channel by channel instead.
+    fn deinterleave(&self, scratch: &mut Vec<f64>) {
+        if self.map.len() != self.inner.channels() {
+            scratch.clear();
+            for channel in 0..self.channels() {
+                self.foreach_sample(channel, |s| scratch.push(s.to_sample::<f64>()));
+            }
+            return;
+        }
+        self.inner.deinterleave(scratch);
+        let frames = self.frames();
+        if frames == 0 {
+            return;
+        }
+        // follow cycles of permutation, so every plane is moved once
+        // there are at most 64 channels
+        let mut placed = 0u64;
+        for start in 0..self.map.len() {
+            let mut j = start;
+            while placed & (1 << j) == 0 {
+                placed |= 1 << j;
+                let k = self.map[j];
+                if k == start {
+                    break;
+                }
+                let (lo, hi) = scratch.split_at_mut(j.max(k) * frames);
+                let (a, b) = (&mut lo[j.min(k) * frames..][..frames], &mut hi[..frames]);
+                a.swap_with_slice(b);
+                j = k;
+            }
+        }
+    }
+
+    #[inline]
+    fn channel_slice(&self, channel: usize) -> Option<&'a [S]> {
+        self.inner.channel_slice(self.map[channel])
+    }
+
+    #[inline]
+    fn frames(&self) -> usize {
+        self.inner.frames()
+    }
+
+    #[inline]
+    fn channels(&self) -> usize {
+        self.map.len()
+    }
+
+    #[inline]
+    fn split_at(self, sample: usize) -> (Self, Self) {
+        let (fst, snd) = self.inner.split_at(sample);
+        (
+            Remapped {
+                inner: fst,
+                map: self.map,
+            },
+            Remapped {
+                inner: snd,
+                map: self.map,
+            },
+        )
+    }
+}
+
+/// Type of samples the meter can process
+///
+/// Implemented for `u8`, `i16`, `i32`, `i64`, `f32` and `f64`.
 pub trait Sample:
     dasp_sample::Sample + dasp_sample::Duplex<f32> + dasp_sample::Duplex<f64>
 {
+    /// Raw value of full scale
     const MAX_AMPLITUDE: f64;
 
+    /// All values of type are finite (integer samples)
+    const FINITE: bool = true;
+
+    /// Squares of samples are accumulated exactly in integers
+    const INTEGER: bool = false;
+
+    /// Raw value (relative to silence) without scaling to full scale
     fn as_f64_raw(self) -> f64;
+
+    /// Square of raw sample value, only used if [`Sample::INTEGER`]
+    #[inline(always)]
+    fn square_raw(self) -> u64 {
+        0
+    }
+
+    /// Returns `false` for NaN and infinite samples
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        true
+    }
 }
 
 impl Sample for f32 {
     const MAX_AMPLITUDE: f64 = 1.0;
+    const FINITE: bool = false;
 
     #[inline(always)]
     fn as_f64_raw(self) -> f64 {
         self as f64
     }
+
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        f32::is_finite(self)
+    }
 }
 impl Sample for f64 {
     const MAX_AMPLITUDE: f64 = 1.0;
+    const FINITE: bool = false;
 
     #[inline(always)]
     fn as_f64_raw(self) -> f64 {
-        self as f64
+        self
+    }
+
+    #[inline(always)]
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
+/// Unsigned samples are offset by `128`, which is silence
+impl Sample for u8 {
+    const MAX_AMPLITUDE: f64 = 128.0;
+    const INTEGER: bool = true;
+
+    #[inline(always)]
+    fn as_f64_raw(self) -> f64 {
+        self as f64 - 128.0
+    }
+
+    #[inline(always)]
+    fn square_raw(self) -> u64 {
+        let v = self as i32 - 128;
+        (v * v) as u64
     }
 }
 impl Sample for i16 {
     const MAX_AMPLITUDE: f64 = -(Self::MIN as f64);
+    const INTEGER: bool = true;
 
     #[inline(always)]
     fn as_f64_raw(self) -> f64 {
         self as f64
     }
+
+    #[inline(always)]
+    fn square_raw(self) -> u64 {
+        (self as i32 * self as i32) as u64
+    }
 }
 impl Sample for i32 {
     const MAX_AMPLITUDE: f64 = -(Self::MIN as f64);
+    const INTEGER: bool = true;
+
+    #[inline(always)]
+    fn as_f64_raw(self) -> f64 {
+        self as f64
+    }
+
+    #[inline(always)]
+    fn square_raw(self) -> u64 {
+        (self as i64 * self as i64) as u64
+    }
+}
+/// Squares do not fit into `u64`, so they are accumulated in floats
+impl Sample for i64 {
+    const MAX_AMPLITUDE: f64 = -(Self::MIN as f64);
 
     #[inline(always)]
     fn as_f64_raw(self) -> f64 {
@@ -363,14 +600,7 @@ pub trait SampleAccumulator: Sample {
 impl SampleAccumulator for f32 {
     #[inline(always)]
     fn scale_add(&mut self, other: Self, coeff: f32) {
-        #[cfg(feature = "precision-true-peak")]
-        {
-            *self = other.mul_add(coeff, *self);
-        }
-        #[cfg(not(feature = "precision-true-peak"))]
-        {
-            *self += other * coeff
-        }
+        *self += other * coeff
     }
 }
 
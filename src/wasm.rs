@@ -0,0 +1,120 @@
+//! WASM surface for Web Audio `AudioWorklet`s
+//!
+//! [`WorkletMeter`] is pushed one render quantum (128 frames) at a time
+//! from `AudioWorkletProcessor.process()` and never allocates while doing so.
+//! Results are returned as `Float64Array` snapshots whose buffer can be
+//! transferred to the main thread with `postMessage`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::DRMeter;
+
+/// Number of frames in one Web Audio render quantum
+pub const RENDER_QUANTUM: usize = 128;
+
+/// Number of values in snapshot before per-channel values
+const SNAPSHOT_HEADER: usize = 2;
+/// Number of per-channel values in snapshot
+const SNAPSHOT_CHANNEL: usize = 3;
+
+/// Meter for use inside `AudioWorkletProcessor`
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct WorkletMeter {
+    meter: DRMeter,
+    /// Scratch for interleaving one render quantum
+    interleaved: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl WorkletMeter {
+    /// Create a new meter
+    #[wasm_bindgen(constructor)]
+    pub fn new(channels: u32, rate: u32) -> Result<WorkletMeter, JsError> {
+        Ok(Self {
+            meter: DRMeter::new(channels, rate)?,
+            interleaved: vec![0.0; channels as usize * RENDER_QUANTUM],
+        })
+    }
+
+    /// Number of channels
+    #[wasm_bindgen(getter)]
+    pub fn channels(&self) -> u32 {
+        self.meter.channels()
+    }
+
+    /// Number of pushed frames
+    #[wasm_bindgen(getter)]
+    pub fn frames(&self) -> f64 {
+        self.meter.frames() as f64
+    }
+
+    /// Push one render quantum
+    ///
+    /// `planar` holds 128 frames of every channel, one channel after another.
+    pub fn push(&mut self, planar: &[f32]) -> Result<(), JsError> {
+        if planar.len() != self.interleaved.len() {
+            return Err(JsError::new("expected one render quantum of every channel"));
+        }
+
+        let channels = self.meter.channels() as usize;
+        for (ch, samples) in planar.chunks_exact(RENDER_QUANTUM).enumerate() {
+            for (i, &s) in samples.iter().enumerate() {
+                self.interleaved[i * channels + ch] = s;
+            }
+        }
+
+        Ok(self.meter.add_frames_f32(&self.interleaved)?)
+    }
+
+    /// Finalize the meter, so the last partial block is counted
+    pub fn finalize(&mut self) -> Result<(), JsError> {
+        Ok(self.meter.finalize()?)
+    }
+
+    /// Snapshot of current results
+    ///
+    /// Layout is `[frames, exact_dr]` followed by
+    /// `[exact_dr, first_peak, second_peak]` of every channel.
+    /// Values that are not available yet are `NaN`.
+    pub fn snapshot(&self) -> Vec<f64> {
+        let channels = self.meter.channels();
+        let mut snapshot =
+            Vec::with_capacity(SNAPSHOT_HEADER + SNAPSHOT_CHANNEL * channels as usize);
+        snapshot.push(self.meter.frames() as f64);
+        snapshot.push(self.meter.exact_dr().unwrap_or(f64::NAN));
+        for ch in 0..channels {
+            snapshot.push(self.meter.exact_channel_dr(ch).unwrap_or(f64::NAN));
+            snapshot.push(self.meter.first_peak(ch).unwrap_or(f64::NAN));
+            snapshot.push(self.meter.second_peak(ch).unwrap_or(f64::NAN));
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_quanta() {
+        let frames: Vec<f32> = (0..RENDER_QUANTUM * 2 * 375 * 4)
+            .map(|i| f32::sin(i as f32 * 0.1) * (i % 48_000) as f32 / 48_000.0)
+            .collect();
+        let mut expected = DRMeter::new(2, 48_000).unwrap();
+        expected.add_frames_f32(&frames).unwrap();
+
+        let mut worklet = WorkletMeter::new(2, 48_000).unwrap();
+        for quantum in frames.chunks(RENDER_QUANTUM * 2) {
+            let left = quantum.iter().step_by(2);
+            let right = quantum.iter().skip(1).step_by(2);
+            let planar: Vec<f32> = left.chain(right).copied().collect();
+            worklet.push(&planar).unwrap();
+        }
+
+        let snapshot = worklet.snapshot();
+        assert_eq!(snapshot.len(), 2 + 3 * 2);
+        assert_eq!(snapshot[0], 4.0 * 48_000.0);
+        assert_eq!(snapshot[1], expected.exact_dr().unwrap());
+    }
+}
@@ -0,0 +1,182 @@
+//! Measuring WAV files read with [hound](https://github.com/ruuda/hound)
+
+use std::io::Read;
+use std::path::Path;
+use std::{error, fmt};
+
+use hound::{SampleFormat, WavReader, WavSpec};
+
+use crate::{DRMeter, DrReport, Error};
+
+/// Number of frames read from file before processing
+const CHUNK_FRAMES: usize = 4096;
+
+/// Error of reading and measuring WAV file
+#[derive(Debug)]
+pub enum WavError {
+    /// File cannot be opened or is not a valid WAV
+    Wav(hound::Error),
+    /// Meter rejected the audio (e.g. unsupported sample rate)
+    Meter(Error),
+}
+
+impl error::Error for WavError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            WavError::Wav(e) => Some(e),
+            WavError::Meter(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WavError::Wav(e) => write!(f, "WAV error: {e}"),
+            WavError::Meter(e) => write!(f, "Meter error: {e}"),
+        }
+    }
+}
+
+impl From<hound::Error> for WavError {
+    fn from(e: hound::Error) -> Self {
+        WavError::Wav(e)
+    }
+}
+
+impl From<Error> for WavError {
+    fn from(e: Error) -> Self {
+        WavError::Meter(e)
+    }
+}
+
+impl DRMeter {
+    /// Create a new instance with channels and rate of WAV file.
+    pub fn from_wav_spec(spec: &WavSpec) -> Result<Self, Error> {
+        Self::new(u32::from(spec.channels), spec.sample_rate)
+    }
+}
+
+/// Add samples to meter in chunks of whole frames
+fn add_chunks<T>(
+    meter: &mut DRMeter,
+    mut samples: impl Iterator<Item = hound::Result<T>>,
+    add: fn(&mut DRMeter, &[T]) -> Result<(), Error>,
+) -> Result<(), WavError> {
+    let chunk_len = CHUNK_FRAMES * meter.channels() as usize;
+    let mut chunk = Vec::with_capacity(chunk_len);
+    loop {
+        chunk.clear();
+        for sample in samples.by_ref().take(chunk_len) {
+            chunk.push(sample?);
+        }
+        if chunk.is_empty() {
+            return Ok(());
+        }
+        add(meter, &chunk)?;
+    }
+}
+
+/// Measure all samples of WAV reader
+fn measure<R: Read>(mut reader: WavReader<R>) -> Result<DrReport, WavError> {
+    let spec = reader.spec();
+    let mut meter = DRMeter::from_wav_spec(&spec)?;
+    match spec.sample_format {
+        SampleFormat::Float => {
+            add_chunks(&mut meter, reader.samples::<f32>(), DRMeter::add_frames_f32)?
+        }
+        SampleFormat::Int => {
+            // scale to full range of `i32`, so integer samples stay exact
+            let shift = 32 - u32::from(spec.bits_per_sample);
+            add_chunks(
+                &mut meter,
+                reader.samples::<i32>().map(|s| s.map(|s| s << shift)),
+                DRMeter::add_frames_i32,
+            )?
+        }
+    }
+    Ok(meter.into_report()?)
+}
+
+/// Open WAV file, measure all of its samples and return report
+///
+/// Integer (8 to 32 bit) and 32-bit float samples are supported.
+pub fn analyze_wav(path: impl AsRef<Path>) -> Result<DrReport, WavError> {
+    measure(WavReader::open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use hound::WavWriter;
+
+    use super::*;
+
+    fn wav(spec: WavSpec, write: impl FnOnce(&mut WavWriter<&mut Cursor<Vec<u8>>>)) -> Vec<u8> {
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+        write(&mut writer);
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn bit_depths() {
+        let samples: Vec<f32> = (0..44_100 * 2 * 10)
+            .map(|i| f32::sin(i as f32 * 0.003) * (1 + i / 200_000) as f32 / 5.0)
+            .collect();
+        let mut dr = DRMeter::new(2, 44_100).unwrap();
+        dr.add_frames_f32(&samples).unwrap();
+        let expected = dr.into_report().unwrap();
+
+        let spec = |bits, sample_format| WavSpec {
+            channels: 2,
+            sample_rate: 44_100,
+            bits_per_sample: bits,
+            sample_format,
+        };
+        let float = wav(spec(32, SampleFormat::Float), |w| {
+            samples.iter().for_each(|s| w.write_sample(*s).unwrap())
+        });
+        let report = measure(WavReader::new(Cursor::new(float)).unwrap()).unwrap();
+        assert_eq!(report, expected);
+
+        for bits in [16, 24] {
+            let scale = ((1 << (bits - 1)) - 1) as f32;
+            let int = wav(spec(bits, SampleFormat::Int), |w| {
+                samples
+                    .iter()
+                    .for_each(|s| w.write_sample((s * scale) as i32).unwrap())
+            });
+            let report = measure(WavReader::new(Cursor::new(int)).unwrap()).unwrap();
+            assert_eq!(report.rate, 44_100);
+            assert_eq!(report.duration, expected.duration);
+            assert!((report.dr - expected.dr).abs() < 0.01, "{bits}: {report}");
+        }
+    }
+
+    #[test]
+    fn file() {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let samples: Vec<i16> = (0..8000 * 4).map(|i| ((i * 91) % 12_000) as i16).collect();
+        let path = std::env::temp_dir().join(format!("drmeter-hound-{}.wav", std::process::id()));
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        samples
+            .iter()
+            .for_each(|s| writer.write_sample(*s).unwrap());
+        writer.finalize().unwrap();
+        let report = analyze_wav(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let mut dr = DRMeter::from_wav_spec(&spec).unwrap();
+        dr.add_frames_i16(&samples).unwrap();
+        assert_eq!(report.unwrap(), dr.into_report().unwrap());
+        assert!(matches!(analyze_wav(path), Err(WavError::Wav(_))));
+    }
+}